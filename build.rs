@@ -0,0 +1,42 @@
+use std::fs;
+use std::path::Path;
+
+/// Collect the doc comments of `Config`'s fields from src/config.rs, so the
+/// generated config.toml can explain each setting without a second copy of
+/// the text
+fn main() {
+    println!("cargo:rerun-if-changed=src/config.rs");
+
+    let source = fs::read_to_string("src/config.rs").expect("src/config.rs is readable");
+    let mut docs: Vec<(String, String)> = Vec::new();
+    let mut comment: Vec<String> = Vec::new();
+    let mut in_struct = false;
+    for line in source.lines() {
+        let line = line.trim();
+        if !in_struct {
+            in_struct = line == "pub struct Config {";
+            continue;
+        }
+        if line == "}" {
+            break;
+        }
+        if let Some(doc) = line.strip_prefix("///") {
+            comment.push(doc.trim().to_string());
+        } else if let Some(field) = line.strip_prefix("pub ") {
+            if let Some((name, _)) = field.split_once(':') {
+                docs.push((name.trim().to_string(), comment.join("\n")));
+            }
+            comment.clear();
+        } else if line.is_empty() {
+            comment.clear();
+        }
+    }
+
+    let mut generated = String::from("const FIELD_DOCS: &[(&str, &str)] = &[\n");
+    for (name, doc) in &docs {
+        generated.push_str(&format!("    ({:?}, {:?}),\n", name, doc));
+    }
+    generated.push_str("];\n");
+    let out_dir = std::env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    fs::write(Path::new(&out_dir).join("config_docs.rs"), generated).expect("OUT_DIR is writable");
+}
@@ -0,0 +1,183 @@
+use reqwest::{Response, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::retry::{self, RetriesExhausted};
+
+/// Longest stretch of a response body that isn't JSON kept in a message
+const MAX_DETAIL_CHARS: usize = 200;
+
+/// Why an API request failed, sorted by what the user can do about it
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ApiError {
+    /// The key is missing, wrong or has no access
+    #[error("{what} was refused: {detail}")]
+    Auth { what: String, detail: String },
+    /// Too many requests; `retry_after` is how long the API asked to wait
+    #[error("{what} was rate limited: {detail}")]
+    RateLimited {
+        what: String,
+        retry_after: Option<Duration>,
+        detail: String,
+    },
+    /// The server couldn't be reached or didn't answer in time
+    #[error("{what} couldn't reach the server: {detail}")]
+    Network { what: String, detail: String },
+    /// The account is out of credit or over its usage limit
+    #[error("{what} was refused, the account's quota is used up: {detail}")]
+    QuotaExceeded { what: String, detail: String },
+    /// The audio is corrupt, in a format the API doesn't take or too large
+    #[error("{what} was rejected, the audio couldn't be used: {detail}")]
+    InvalidAudio { what: String, detail: String },
+    /// Any other error status, server errors included
+    #[error("{what} failed with status {status}: {detail}")]
+    Failed {
+        what: String,
+        status: StatusCode,
+        detail: String,
+    },
+}
+
+/// `{"error": {"message": ..., "code": ...}}` from OpenAI and Google, or
+/// `{"error": "..."}` from AssemblyAI
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    error: ErrorField,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ErrorField {
+    Message(String),
+    Detailed {
+        #[serde(default)]
+        message: String,
+        /// OpenAI's error code ("invalid_api_key"); a number from Google
+        #[serde(default)]
+        code: Option<serde_json::Value>,
+        /// Google's status name ("RESOURCE_EXHAUSTED")
+        #[serde(default)]
+        status: Option<String>,
+    },
+}
+
+impl ApiError {
+    /// The error a failed `response` to `what` stands for
+    pub async fn from_response(what: &str, response: Response) -> Self {
+        let status = response.status();
+        let retry_after = retry::retry_after(&response);
+        let body = response.text().await.unwrap_or_default();
+        Self::from_status(what, status, retry_after, &body)
+    }
+
+    /// Sort an error status by the API's own error code where it gives one,
+    /// keeping only its message rather than the whole body
+    pub fn from_status(
+        what: &str,
+        status: StatusCode,
+        retry_after: Option<Duration>,
+        body: &str,
+    ) -> Self {
+        let (message, code) = match serde_json::from_str::<ErrorBody>(body) {
+            Ok(ErrorBody {
+                error: ErrorField::Message(message),
+            }) => (message, String::new()),
+            Ok(ErrorBody {
+                error:
+                    ErrorField::Detailed {
+                        message,
+                        code,
+                        status,
+                    },
+            }) => {
+                let code = code
+                    .as_ref()
+                    .and_then(|code| code.as_str())
+                    .or(status.as_deref())
+                    .unwrap_or_default()
+                    .to_lowercase();
+                (message, code)
+            }
+            Err(_) => (
+                body.trim().chars().take(MAX_DETAIL_CHARS).collect(),
+                String::new(),
+            ),
+        };
+        let detail = match message.trim() {
+            "" => status
+                .canonical_reason()
+                .unwrap_or("no details")
+                .to_string(),
+            message => message.to_string(),
+        };
+        let lower = detail.to_lowercase();
+        let what = what.to_string();
+
+        // OpenAI reports an exhausted quota as a 429 too
+        if code.contains("quota") || status == StatusCode::PAYMENT_REQUIRED {
+            ApiError::QuotaExceeded { what, detail }
+        } else if status == StatusCode::UNAUTHORIZED
+            || status == StatusCode::FORBIDDEN
+            || code.contains("api_key")
+            || lower.contains("api key")
+        {
+            ApiError::Auth { what, detail }
+        } else if status == StatusCode::TOO_MANY_REQUESTS || code == "resource_exhausted" {
+            ApiError::RateLimited {
+                what,
+                retry_after,
+                detail,
+            }
+        } else if status == StatusCode::PAYLOAD_TOO_LARGE
+            || status == StatusCode::UNSUPPORTED_MEDIA_TYPE
+            || (status == StatusCode::BAD_REQUEST
+                && ["audio", "file", "format", "decode"]
+                    .iter()
+                    .any(|word| lower.contains(word)))
+        {
+            ApiError::InvalidAudio { what, detail }
+        } else {
+            ApiError::Failed {
+                what,
+                status,
+                detail,
+            }
+        }
+    }
+
+    /// The API error behind `error`, if an API request is what failed
+    pub fn find(error: &anyhow::Error) -> Option<Self> {
+        error.chain().find_map(|cause| {
+            cause.downcast_ref::<ApiError>().cloned().or_else(|| {
+                cause
+                    .downcast_ref::<RetriesExhausted>()
+                    .map(|exhausted| exhausted.error.clone())
+            })
+        })
+    }
+
+    /// What the user can do about it, for the error line
+    pub fn guidance(&self) -> Option<String> {
+        match self {
+            ApiError::Auth { .. } => Some("Check the API key in Settings".to_string()),
+            ApiError::RateLimited {
+                retry_after: Some(wait),
+                ..
+            } => Some(format!("Retry in {} s", wait.as_secs().max(1))),
+            ApiError::RateLimited { .. } => Some("Wait a minute and retry".to_string()),
+            ApiError::Network { .. } => Some("Check the network connection".to_string()),
+            ApiError::QuotaExceeded { .. } => {
+                Some("Add credit or raise the usage limit of the account".to_string())
+            }
+            ApiError::InvalidAudio { .. } => Some(
+                "Check that the file plays and is in a supported format (WAV, FLAC, MP3, \
+                 Ogg, M4A)"
+                    .to_string(),
+            ),
+            ApiError::Failed { status, .. } if status.is_server_error() => {
+                Some("The service is having trouble; retry later".to_string())
+            }
+            ApiError::Failed { .. } => None,
+        }
+    }
+}
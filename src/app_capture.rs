@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+#[cfg(not(target_os = "windows"))]
+use std::process::{Child, Command, Stdio};
+
+/// An application currently playing audio
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioApp {
+    /// PulseAudio/PipeWire sink input index of the app's stream, or its
+    /// process id on Windows
+    pub index: u32,
+    /// Application name, used to find the stream again later
+    pub name: String,
+    /// What the stream is playing, if the app says
+    pub media: Option<String>,
+    pub corked: bool,
+}
+
+impl AudioApp {
+    pub fn label(&self) -> String {
+        match &self.media {
+            Some(media) => format!("{} — {}", self.name, media),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Applications with an open playback stream, from the audio sessions of
+/// the default output device
+#[cfg(target_os = "windows")]
+pub fn list_playing_apps() -> Result<Vec<AudioApp>> {
+    crate::process_loopback::list_playing_apps()
+}
+
+/// Applications with an open playback stream, from `pactl list sink-inputs`.
+///
+/// Works on PulseAudio and on PipeWire through pipewire-pulse.
+#[cfg(not(target_os = "windows"))]
+pub fn list_playing_apps() -> Result<Vec<AudioApp>> {
+    let output = Command::new("pactl")
+        .args(["list", "sink-inputs"])
+        .env("LC_ALL", "C")
+        .output()
+        .context("Failed to run pactl. Per-application capture needs PulseAudio or PipeWire (pipewire-pulse).")?;
+    if !output.status.success() {
+        anyhow::bail!("pactl exited with status {}", output.status);
+    }
+
+    Ok(parse_sink_inputs(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Find the playing stream of the named application, preferring one that is
+/// not paused
+pub fn find_app(name: &str) -> Result<AudioApp> {
+    let apps = list_playing_apps()?;
+    apps.iter()
+        .find(|app| app.name == name && !app.corked)
+        .or_else(|| apps.iter().find(|app| app.name == name))
+        .cloned()
+        .with_context(|| format!("'{}' is not playing any audio", name))
+}
+
+/// Start `parec` recording only `app`'s stream as raw little-endian f32 at
+/// `sample_rate`, mono or interleaved stereo, on the child's stdout
+#[cfg(not(target_os = "windows"))]
+pub fn spawn_recorder(app: &AudioApp, sample_rate: u32, channels: usize) -> Result<Child> {
+    Command::new("parec")
+        .arg(format!("--monitor-stream={}", app.index))
+        .arg("--format=float32le")
+        .arg(format!("--rate={}", sample_rate))
+        .arg(format!("--channels={}", channels))
+        .arg("--latency-msec=50")
+        .arg("--raw")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run parec. Per-application capture needs PulseAudio or PipeWire (pipewire-pulse).")
+}
+
+#[cfg(not(target_os = "windows"))]
+fn parse_sink_inputs(output: &str) -> Vec<AudioApp> {
+    let mut apps: Vec<AudioApp> = Vec::new();
+    let mut binary = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(index) = line.strip_prefix("Sink Input #") {
+            finish_entry(&mut apps, binary.take());
+            if let Ok(index) = index.trim().parse() {
+                apps.push(AudioApp {
+                    index,
+                    name: String::new(),
+                    media: None,
+                    corked: false,
+                });
+            }
+            continue;
+        }
+
+        let Some(app) = apps.last_mut() else {
+            continue;
+        };
+        if let Some(corked) = line.strip_prefix("Corked:") {
+            app.corked = corked.trim() == "yes";
+        } else if let Some(name) = property(line, "application.name") {
+            app.name = name;
+        } else if let Some(media) = property(line, "media.name") {
+            app.media = Some(media);
+        } else if let Some(name) = property(line, "application.process.binary") {
+            binary = Some(name);
+        }
+    }
+    finish_entry(&mut apps, binary);
+
+    apps
+}
+
+/// Fall back to the process name for streams without an application name
+#[cfg(not(target_os = "windows"))]
+fn finish_entry(apps: &mut [AudioApp], binary: Option<String>) {
+    if let Some(app) = apps.last_mut()
+        && app.name.is_empty()
+    {
+        app.name = binary.unwrap_or_else(|| format!("Stream #{}", app.index));
+    }
+}
+
+/// Value of a `key = "value"` line from pactl's property list
+#[cfg(not(target_os = "windows"))]
+fn property(line: &str, key: &str) -> Option<String> {
+    let value = line
+        .strip_prefix(key)?
+        .trim_start()
+        .strip_prefix('=')?
+        .trim();
+    Some(value.trim_matches('"').to_string())
+}
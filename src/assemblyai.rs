@@ -0,0 +1,234 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use reqwest::header::CONTENT_LENGTH;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::api_error::ApiError;
+use crate::http;
+use crate::retry::RetryPolicy;
+use crate::transcription::{
+    Transcript, TranscriptionContext, TranscriptionProvider, Utterance, file_body,
+    normalize_language,
+};
+
+pub const API_BASE: &str = "https://api.assemblyai.com/v2";
+
+/// How often a queued transcript is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Give up on a transcript that hasn't finished after this long
+const MAX_WAIT: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    upload_url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TranscriptRequest<'a> {
+    audio_url: &'a str,
+    speaker_labels: bool,
+    language_detection: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language_code: Option<&'a str>,
+    /// Custom vocabulary to favour
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    word_boost: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptResponse {
+    id: String,
+    status: String,
+    text: Option<String>,
+    utterances: Option<Vec<ApiUtterance>>,
+    /// Detected or requested language, e.g. "en_us"
+    language_code: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiUtterance {
+    /// "A", "B", ...
+    speaker: String,
+    text: String,
+    /// Milliseconds from the start of the audio
+    start: u64,
+}
+
+/// AssemblyAI's asynchronous transcription API with speaker diarization.
+///
+/// Each chunk is uploaded, queued with `speaker_labels` and polled until it
+/// finishes, which takes a few seconds longer than Whisper.
+pub struct AssemblyAi {
+    api_key: String,
+    /// ISO-639-1 code to transcribe as, or `None` to auto-detect
+    language: Option<String>,
+    retry: RetryPolicy,
+    client: Client,
+}
+
+impl AssemblyAi {
+    pub fn new(api_key: String, language: Option<String>, retry: RetryPolicy) -> Self {
+        Self {
+            api_key,
+            language,
+            retry,
+            client: http::client(),
+        }
+    }
+
+    /// Upload the audio, streamed from disk, returning the private URL
+    /// AssemblyAI reads it from
+    async fn upload(&self, audio_file: &Path, retries: &mut u32) -> Result<String> {
+        let (response, retried) = self
+            .retry
+            .try_send("AssemblyAI upload", || {
+                let (body, len) = file_body(audio_file)?;
+                Ok(self
+                    .client
+                    .post(format!("{}/upload", API_BASE))
+                    .header("Authorization", &self.api_key)
+                    .header(CONTENT_LENGTH, len)
+                    .body(body))
+            })
+            .await?;
+        *retries += retried;
+        let upload: UploadResponse = check_status(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse AssemblyAI upload response")?;
+
+        Ok(upload.upload_url)
+    }
+
+    async fn submit(
+        &self,
+        audio_url: &str,
+        vocabulary: &[String],
+        retries: &mut u32,
+    ) -> Result<TranscriptResponse> {
+        let request = TranscriptRequest {
+            audio_url,
+            speaker_labels: true,
+            language_detection: self.language.is_none(),
+            language_code: self.language.as_deref(),
+            word_boost: vocabulary,
+        };
+        let (response, retried) = self
+            .retry
+            .send("AssemblyAI transcription request", || {
+                self.client
+                    .post(format!("{}/transcript", API_BASE))
+                    .header("Authorization", &self.api_key)
+                    .json(&request)
+            })
+            .await?;
+        *retries += retried;
+
+        check_status(response)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse AssemblyAI response")
+    }
+
+    /// Poll a queued transcript until it completes or fails
+    async fn wait_for(
+        &self,
+        mut transcript: TranscriptResponse,
+        retries: &mut u32,
+    ) -> Result<TranscriptResponse> {
+        let started = Instant::now();
+        loop {
+            match transcript.status.as_str() {
+                "completed" => return Ok(transcript),
+                "error" => anyhow::bail!(
+                    "AssemblyAI transcription failed: {}",
+                    transcript.error.unwrap_or_default()
+                ),
+                _ if started.elapsed() > MAX_WAIT => {
+                    anyhow::bail!("AssemblyAI transcript {} did not finish", transcript.id)
+                }
+                _ => {}
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let (response, retried) = self
+                .retry
+                .send("AssemblyAI status check", || {
+                    self.client
+                        .get(format!("{}/transcript/{}", API_BASE, transcript.id))
+                        .header("Authorization", &self.api_key)
+                })
+                .await?;
+            *retries += retried;
+            transcript = check_status(response)
+                .await?
+                .json()
+                .await
+                .context("Failed to parse AssemblyAI response")?;
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for AssemblyAi {
+    fn name(&self) -> &'static str {
+        "AssemblyAI"
+    }
+
+    /// AssemblyAI has no free-form prompt, so only the vocabulary is used
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        let mut retries = 0;
+        let audio_url = self.upload(audio_file, &mut retries).await?;
+        let queued = self
+            .submit(&audio_url, &context.vocabulary, &mut retries)
+            .await?;
+        let transcript = self.wait_for(queued, &mut retries).await?;
+
+        let utterances = transcript
+            .utterances
+            .unwrap_or_default()
+            .into_iter()
+            .map(|u| Utterance {
+                speaker: speaker_label(&u.speaker),
+                text: u.text,
+                start_secs: u.start as f64 / 1000.0,
+            })
+            .collect();
+
+        Ok(Transcript {
+            text: transcript.text.unwrap_or_default(),
+            utterances,
+            language: transcript.language_code.as_deref().map(normalize_language),
+            retries,
+            ..Default::default()
+        })
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    if !response.status().is_success() {
+        return Err(ApiError::from_response("AssemblyAI request", response)
+            .await
+            .into());
+    }
+    Ok(response)
+}
+
+/// "A" → "Speaker 1", "B" → "Speaker 2", ...
+fn speaker_label(speaker: &str) -> String {
+    match speaker.as_bytes() {
+        [letter @ b'A'..=b'Z'] => format!("Speaker {}", letter - b'A' + 1),
+        _ => format!("Speaker {}", speaker),
+    }
+}
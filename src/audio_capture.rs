@@ -1,21 +1,266 @@
 use anyhow::{Context, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, Host, SampleFormat, Stream, StreamConfig};
-use hound::{WavSpec, WavWriter};
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::cell::Cell;
+use std::collections::VecDeque;
+#[cfg(not(target_os = "windows"))]
+use std::io::Read;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(not(target_os = "windows"))]
+use std::process::Child;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::app_capture;
+use crate::bluetooth;
+use crate::capture_stats::{AudioCaptureStats, CaptureCounters, StatsWindow};
+use crate::chunk_writer::{ChunkMetadata, ChunkSettings, ChunkWriter, SessionRecorder};
+use crate::denoise::SpectralGate;
+use crate::encoding::{AudioFormat, WavSampleFormat};
+use crate::mixer::MicMixer;
+use crate::monitor::{self, MonitorFeed};
+#[cfg(target_os = "windows")]
+use crate::process_loopback::{self, ProcessRecorder};
+use crate::resample::LinearResampler;
+use crate::vad::{VadSettings, VoiceActivityDetector};
+
+/// Seconds of audio the capture ring buffer can hold before dropping samples
+const RING_BUFFER_SECS: usize = 10;
+
+/// How often capture statistics are logged while recording
+const STATS_LOG_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sample magnitude treated as clipping
+pub const CLIP_LEVEL: f32 = 0.99;
+
+/// Input level measured over the most recent ~100ms of audio
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputLevel {
+    pub peak: f32,
+    pub rms: f32,
+}
+
+impl InputLevel {
+    /// Peak and RMS level of a block of samples
+    pub fn measure(samples: &[f32]) -> Self {
+        Self {
+            peak: samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs())),
+            rms: crate::vad::rms(samples),
+        }
+    }
+
+    /// Whether the signal reached full scale in the last window
+    pub fn is_clipping(&self) -> bool {
+        self.peak >= CLIP_LEVEL
+    }
+}
+
+/// The stream configuration negotiated with the device
+#[derive(Debug, Clone)]
+pub struct StreamInfo {
+    pub device_name: String,
+    pub channels: u16,
+    pub device_sample_rate: u32,
+    pub sample_format: SampleFormat,
+    /// Rate of the mono audio written to chunks
+    pub output_sample_rate: u32,
+}
+
+impl std::fmt::Display for StreamInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} ch, {} Hz, {:?} → mono {} Hz",
+            self.device_name,
+            self.channels,
+            self.device_sample_rate,
+            self.sample_format,
+            self.output_sample_rate
+        )
+    }
+}
+
+/// Audio buffered before a recording started, written ahead of it
+pub struct PreRoll {
+    /// Interleaved when channels are split
+    samples: Vec<f32>,
+    sample_rate: u32,
+    channels: usize,
+}
+
+/// A running source of captured audio
+enum InputStream {
+    Device(Stream),
+    /// `parec` recording a single application's playback
+    #[cfg(not(target_os = "windows"))]
+    App(AppRecorder),
+    /// WASAPI process loopback recording a single application's playback
+    #[cfg(target_os = "windows")]
+    Process(ProcessRecorder),
+}
+
+impl InputStream {
+    fn play(&mut self) -> Result<()> {
+        match self {
+            InputStream::Device(stream) => stream.play()?,
+            // parec records as soon as it starts; just make sure it did
+            #[cfg(not(target_os = "windows"))]
+            InputStream::App(recorder) => {
+                if let Some(status) = recorder.child.try_wait()? {
+                    anyhow::bail!("parec exited with status {}", status);
+                }
+            }
+            // So does process loopback, which fails before returning if not
+            #[cfg(target_os = "windows")]
+            InputStream::Process(_) => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+struct AppRecorder {
+    child: Child,
+    reader: Option<JoinHandle<()>>,
+}
+
+#[cfg(not(target_os = "windows"))]
+impl Drop for AppRecorder {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Hands converted samples to the ring buffer and publishes the input level;
+/// shared by device callbacks and the per-application reader
+struct CaptureOutput {
+    producer: Producer<f32>,
+    channels: usize,
+    dropped_samples: Arc<AtomicU64>,
+    counters: Arc<CaptureCounters>,
+    /// Rate of the frames passed to `record_callback`
+    device_rate: u32,
+    last_callback: Option<Instant>,
+    input_level: Arc<[AtomicU32; 2]>,
+    window_len: usize,
+    window_peak: f32,
+    window_sum_sq: f32,
+    window_count: usize,
+}
+
+impl CaptureOutput {
+    /// Count a delivery of `frames` device frames, noting a gap since the
+    /// previous one longer than twice the audio it carries
+    fn record_callback(&mut self, frames: usize, latency: Option<Duration>) {
+        let now = Instant::now();
+        if let Some(last) = self.last_callback {
+            let audio = Duration::from_secs_f64(frames as f64 / self.device_rate.max(1) as f64);
+            if now - last > audio * 2 + Duration::from_millis(20) {
+                self.counters.record_underrun();
+            }
+        }
+        self.last_callback = Some(now);
+        self.counters.record_callback(frames, latency);
+    }
+
+    /// Queue interleaved samples for the chunk writer
+    fn push(&mut self, interleaved: &[f32]) {
+        // Never block the audio thread: if the writer is behind, count
+        // what doesn't fit instead of waiting. Only whole frames are
+        // written so split channels stay aligned.
+        let writable = self.producer.slots().min(interleaved.len()) / self.channels * self.channels;
+        if let Ok(chunk) = self.producer.write_chunk_uninit(writable) {
+            chunk.fill_from_iter(interleaved.iter().copied());
+        }
+        if writable < interleaved.len() {
+            self.counters.record_overrun();
+            self.dropped_samples.fetch_add(
+                ((interleaved.len() - writable) / self.channels) as u64,
+                Ordering::Relaxed,
+            );
+        }
+
+        // Level meter, published every 100ms of audio
+        for &sample in interleaved {
+            self.window_peak = self.window_peak.max(sample.abs());
+            self.window_sum_sq += sample * sample;
+            self.window_count += 1;
+            if self.window_count >= self.window_len {
+                let rms = (self.window_sum_sq / self.window_count as f32).sqrt();
+                self.input_level[0].store(self.window_peak.to_bits(), Ordering::Relaxed);
+                self.input_level[1].store(rms.to_bits(), Ordering::Relaxed);
+                self.window_peak = 0.0;
+                self.window_sum_sq = 0.0;
+                self.window_count = 0;
+            }
+        }
+    }
+}
+
+/// Idle input stream keeping the most recent audio in memory
+struct PreRollStream {
+    stream: InputStream,
+    running: Arc<AtomicBool>,
+    thread: JoinHandle<VecDeque<f32>>,
+    channels: usize,
+}
 
 pub struct AudioCapture {
     host: Host,
     device: Option<Device>,
-    stream: Option<Stream>,
+    stream: Option<InputStream>,
+    /// Application whose playback is captured instead of a device
+    capture_app: Option<String>,
+    /// Microphone mixed into the main capture, with echo cancellation on/off
+    microphone: Option<(Device, bool)>,
+    mic_stream: Option<Stream>,
+    /// Output device to play captured audio on (empty for the default)
+    monitor_output: Option<String>,
+    /// Monitor gain as f32 bits, adjustable while the stream runs
+    monitor_volume: Arc<AtomicU32>,
+    monitor_stream: Option<Stream>,
+    /// Receives the mono samples as they are captured, for streaming
+    live_feed: Option<UnboundedSender<Vec<f32>>>,
     is_recording: Arc<AtomicBool>,
     sample_rate: u32,
     chunk_duration: Duration,
     output_dir: PathBuf,
+    capture_system_audio: bool,
+    vad: Option<VadSettings>,
+    silence_threshold: Option<f32>,
+    noise_suppression: Option<f32>,
+    audio_format: AudioFormat,
+    wav_format: WavSampleFormat,
+    chunk_overlap: Duration,
+    max_chunk_bytes: Option<u64>,
+    /// Speaker labels when left and right are chunked separately
+    channel_labels: Option<[String; 2]>,
+    /// Label of this capture when several record at once
+    source: Option<String>,
+    session_recording: Option<(PathBuf, AudioFormat)>,
+    session_path: Option<PathBuf>,
+    pre_roll_stream: Option<PreRollStream>,
+    pre_roll: Option<PreRoll>,
+    skipped_chunks: Arc<AtomicUsize>,
+    dropped_samples: Arc<AtomicU64>,
+    /// Peak and RMS as f32 bit patterns, written by the audio callback
+    input_level: Arc<[AtomicU32; 2]>,
+    counters: Arc<CaptureCounters>,
+    stats_window: Cell<StatsWindow>,
+    stream_info: Option<StreamInfo>,
+    /// Switch away from an input that only delivers telephone-quality audio
+    avoid_narrowband_input: bool,
+    /// Why the opened input is low quality, if it is
+    input_warning: Option<String>,
 }
 
 impl AudioCapture {
@@ -27,15 +272,316 @@ impl AudioCapture {
             host,
             device: None,
             stream: None,
+            capture_app: None,
+            microphone: None,
+            mic_stream: None,
+            monitor_output: None,
+            monitor_volume: Arc::new(AtomicU32::new(1.0f32.to_bits())),
+            monitor_stream: None,
+            live_feed: None,
             is_recording: Arc::new(AtomicBool::new(false)),
             sample_rate,
             chunk_duration: Duration::from_secs(chunk_duration_secs),
             output_dir,
+            capture_system_audio: false,
+            vad: None,
+            silence_threshold: None,
+            noise_suppression: None,
+            audio_format: AudioFormat::default(),
+            wav_format: WavSampleFormat::default(),
+            chunk_overlap: Duration::ZERO,
+            max_chunk_bytes: None,
+            channel_labels: None,
+            source: None,
+            session_recording: None,
+            session_path: None,
+            pre_roll_stream: None,
+            pre_roll: None,
+            skipped_chunks: Arc::new(AtomicUsize::new(0)),
+            dropped_samples: Arc::new(AtomicU64::new(0)),
+            input_level: Arc::new([AtomicU32::new(0), AtomicU32::new(0)]),
+            counters: Arc::new(CaptureCounters::default()),
+            stats_window: Cell::new(StatsWindow::new()),
+            stream_info: None,
+            avoid_narrowband_input: false,
+            input_warning: None,
         })
     }
 
+    /// Stream configuration in use, once recording has started
+    pub fn stream_info(&self) -> Option<&StreamInfo> {
+        self.stream_info.as_ref()
+    }
+
+    /// Warning about the opened input's quality, e.g. a Bluetooth headset
+    /// stuck in its 8 kHz hands-free profile
+    pub fn input_warning(&self) -> Option<&str> {
+        self.input_warning.as_deref()
+    }
+
+    /// Record from another input device when the selected one turns out to
+    /// deliver only telephone-quality audio
+    pub fn set_avoid_narrowband_input(&mut self, avoid: bool) {
+        self.avoid_narrowband_input = avoid;
+    }
+
+    /// Samples lost because the chunk writer fell behind the audio callback
+    pub fn dropped_samples(&self) -> u64 {
+        self.dropped_samples.load(Ordering::Relaxed)
+    }
+
+    /// Callback, buffer and device statistics of the running capture
+    pub fn stats(&self) -> AudioCaptureStats {
+        let mut window = self.stats_window.get();
+        let stats = window.snapshot(
+            &self.counters,
+            self.stream_info.clone(),
+            self.dropped_samples(),
+            self.sample_rate,
+        );
+        self.stats_window.set(window);
+        stats
+    }
+
+    /// Current input level for metering
+    pub fn input_level(&self) -> InputLevel {
+        InputLevel {
+            peak: f32::from_bits(self.input_level[0].load(Ordering::Relaxed)),
+            rms: f32::from_bits(self.input_level[1].load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Discard chunks whose RMS level is below `threshold` instead of
+    /// handing them on for transcription
+    pub fn set_silence_threshold(&mut self, threshold: Option<f32>) {
+        self.silence_threshold = threshold;
+    }
+
+    /// Run a spectral-gate denoiser with the given strength (0.0-1.0) over
+    /// each chunk before it is written
+    pub fn set_noise_suppression(&mut self, strength: Option<f32>) {
+        self.noise_suppression = strength;
+    }
+
+    /// Encoding used for chunk files
+    pub fn set_audio_format(&mut self, format: AudioFormat) {
+        self.audio_format = format;
+    }
+
+    /// Sample encoding for WAV chunks and WAV session recordings
+    pub fn set_wav_format(&mut self, format: WavSampleFormat) {
+        self.wav_format = format;
+    }
+
+    /// Repeat this much audio from the end of each chunk at the start of the
+    /// next, so words spoken across a boundary are not cut
+    pub fn set_chunk_overlap(&mut self, overlap: Duration) {
+        self.chunk_overlap = overlap;
+    }
+
+    /// End chunks early once their file would exceed this many bytes
+    pub fn set_max_chunk_bytes(&mut self, max_bytes: Option<u64>) {
+        self.max_chunk_bytes = max_bytes;
+    }
+
+    /// Tag chunks with a source label, telling them apart from those of
+    /// other captures running at the same time
+    pub fn set_source(&mut self, source: Option<String>) {
+        self.source = source;
+    }
+
+    /// Chunk the left and right channels as separate streams tagged with
+    /// these speaker labels, instead of mixing down to mono
+    pub fn set_channel_split(&mut self, labels: Option<[String; 2]>) {
+        self.channel_labels = labels;
+    }
+
+    /// Audio to write ahead of the next recording, usually taken from an
+    /// idle capture with `take_pre_roll`
+    pub fn set_pre_roll(&mut self, pre_roll: Option<PreRoll>) {
+        self.pre_roll = pre_roll;
+    }
+
+    /// Keep the last `duration` of input in memory without recording, so it
+    /// can be prepended to a recording that starts a little late. The input
+    /// is also played on the monitor, if one is set.
+    pub fn start_pre_roll(&mut self, duration: Duration) -> Result<()> {
+        if self.pre_roll_stream.is_some() || self.is_recording() {
+            anyhow::bail!("Already capturing");
+        }
+
+        let channels = self.output_channels();
+        let capacity = (duration.as_secs_f64() * self.sample_rate as f64) as usize * channels;
+        let (producer, mut consumer) =
+            RingBuffer::<f32>::new(self.sample_rate as usize * RING_BUFFER_SECS * channels);
+        let mut stream = self.open_input_stream(producer, channels)?;
+        let mut monitor = self.open_monitor(channels);
+
+        let running = Arc::new(AtomicBool::new(true));
+        let running_clone = Arc::clone(&running);
+        let thread = thread::spawn(move || {
+            let mut buffer = VecDeque::with_capacity(capacity);
+            let mut incoming = Vec::new();
+
+            loop {
+                let running = running_clone.load(Ordering::SeqCst);
+                if running {
+                    thread::sleep(Duration::from_millis(100));
+                }
+
+                // The callback only writes whole frames, so trimming to a
+                // multiple of the channel count keeps split channels aligned
+                incoming.clear();
+                drain_ring(&mut consumer, &mut incoming);
+                if let Some(monitor) = monitor.as_mut() {
+                    monitor.push(&incoming);
+                }
+                buffer.extend(&incoming);
+                let excess = buffer.len().saturating_sub(capacity);
+                buffer.drain(..excess);
+
+                if !running {
+                    break;
+                }
+            }
+
+            buffer
+        });
+
+        stream.play()?;
+        self.pre_roll_stream = Some(PreRollStream {
+            stream,
+            running,
+            thread,
+            channels,
+        });
+        if capacity > 0 {
+            println!("Buffering {:.0}s of pre-roll audio", duration.as_secs_f32());
+        }
+        Ok(())
+    }
+
+    /// Stop the pre-roll stream and return what it buffered
+    pub fn take_pre_roll(&mut self) -> Option<PreRoll> {
+        let PreRollStream {
+            stream,
+            running,
+            thread,
+            channels,
+        } = self.pre_roll_stream.take()?;
+
+        drop(stream);
+        self.monitor_stream = None;
+        running.store(false, Ordering::SeqCst);
+        let samples = thread.join().ok()?;
+
+        Some(PreRoll {
+            samples: samples.into(),
+            sample_rate: self.sample_rate,
+            channels,
+        })
+    }
+
+    /// Play captured audio on an output device (by name, or the default
+    /// output if empty) to check what is being recorded. Takes effect when
+    /// capture next starts.
+    pub fn set_monitor(&mut self, output: Option<String>) {
+        self.monitor_output = output;
+    }
+
+    /// Send every block of captured audio, mixed to mono, to `feed` as well
+    /// as the chunk writer. Takes effect when capture next starts; the feed
+    /// closes when it stops.
+    pub fn set_live_feed(&mut self, feed: Option<UnboundedSender<Vec<f32>>>) {
+        self.live_feed = feed;
+    }
+
+    /// Monitor gain from 0.0 (silent) to 1.0; applies immediately
+    pub fn set_monitor_volume(&self, volume: f32) {
+        self.monitor_volume
+            .store(volume.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Start the monitor output stream if monitoring is enabled. Failures
+    /// are only logged since recording works without it.
+    fn open_monitor(&mut self, channels: usize) -> Option<MonitorFeed> {
+        let name = self.monitor_output.as_deref()?;
+        let device = if name.is_empty() {
+            self.host.default_output_device()
+        } else {
+            self.host
+                .output_devices()
+                .ok()
+                .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == name)))
+        };
+        let Some(device) = device else {
+            eprintln!("Monitor output device '{}' not found", name);
+            return None;
+        };
+
+        let volume = Arc::clone(&self.monitor_volume);
+        let opened = monitor::open(&device, self.sample_rate, channels, volume).and_then(
+            |(stream, feed)| {
+                stream.play()?;
+                Ok((stream, feed))
+            },
+        );
+        match opened {
+            Ok((stream, feed)) => {
+                println!("Monitoring input on: {}", device.name().unwrap_or_default());
+                self.monitor_stream = Some(stream);
+                Some(feed)
+            }
+            Err(e) => {
+                eprintln!("Error starting input monitor: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Also record the whole session, unprocessed, as one file in `dir`
+    pub fn set_session_recording(&mut self, dir: Option<PathBuf>, format: AudioFormat) {
+        self.session_recording = dir.map(|dir| (dir, format));
+    }
+
+    /// File the current session is being recorded to, if enabled
+    pub fn session_path(&self) -> Option<&PathBuf> {
+        self.session_path.as_ref()
+    }
+
+    /// Number of chunks discarded as silent since recording started
+    pub fn skipped_chunks(&self) -> usize {
+        self.skipped_chunks.load(Ordering::SeqCst)
+    }
+
+    /// End chunks at pauses in speech instead of after a fixed duration
+    pub fn set_vad(&mut self, settings: Option<VadSettings>) {
+        self.vad = settings;
+    }
+
+    /// Capture what the system is playing instead of a microphone.
+    ///
+    /// On Windows this opens a WASAPI loopback stream on the default render
+    /// device, so meeting audio can be recorded without a virtual cable.
+    pub fn set_capture_system_audio(&mut self, enabled: bool) {
+        if self.capture_system_audio != enabled {
+            self.capture_system_audio = enabled;
+            self.device = None;
+        }
+    }
+
+    /// Record only the named application's playback (see `app_capture`)
+    /// instead of an input device
+    pub fn set_capture_app(&mut self, name: Option<String>) {
+        self.capture_app = name;
+    }
+
     /// Get the default input device (microphone or system audio)
     pub fn get_default_device(&mut self) -> Result<()> {
+        if self.capture_system_audio {
+            return self.get_loopback_device();
+        }
+
         // Try to get default input device
         let device = self
             .host
@@ -47,6 +593,28 @@ impl AudioCapture {
         Ok(())
     }
 
+    /// Get the default output device for WASAPI loopback capture
+    #[cfg(target_os = "windows")]
+    fn get_loopback_device(&mut self) -> Result<()> {
+        // WASAPI records what an output device renders when an input stream
+        // is built on it
+        let device = self
+            .host
+            .default_output_device()
+            .context("No default output device found for system audio capture")?;
+
+        println!("Using loopback device: {}", device.name()?);
+        self.device = Some(device);
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn get_loopback_device(&mut self) -> Result<()> {
+        anyhow::bail!(
+            "System audio loopback is only available on Windows. On Linux, select a PulseAudio/PipeWire monitor source as the default input instead."
+        )
+    }
+
     /// List all available audio devices
     #[allow(dead_code)]
     pub fn list_devices(&self) -> Result<Vec<String>> {
@@ -61,116 +629,528 @@ impl AudioCapture {
         Ok(devices)
     }
 
+    /// Names of the audio hosts (ALSA, JACK, WASAPI, ASIO, ...) usable in this build
+    pub fn available_hosts() -> Vec<String> {
+        cpal::available_hosts()
+            .into_iter()
+            .map(|id| id.name().to_string())
+            .collect()
+    }
+
+    /// Input device names on the named host (empty for the default host)
+    pub fn list_host_devices(host_name: &str) -> Result<Vec<String>> {
+        let host = open_host(host_name)?;
+        Ok(host
+            .input_devices()?
+            .filter_map(|device| device.name().ok())
+            .collect())
+    }
+
+    /// Output device names on the named host (empty for the default host)
+    pub fn list_output_devices(host_name: &str) -> Result<Vec<String>> {
+        let host = open_host(host_name)?;
+        Ok(host
+            .output_devices()?
+            .filter_map(|device| device.name().ok())
+            .collect())
+    }
+
+    /// Name of the host used when none is configured
+    pub fn default_host_name() -> String {
+        cpal::default_host().id().name().to_string()
+    }
+
+    /// Open devices on the named host instead of the platform default.
+    /// An empty name selects the default host.
+    pub fn set_host(&mut self, name: &str) -> Result<()> {
+        self.host = open_host(name)?;
+        self.device = None;
+        Ok(())
+    }
+
+    /// Use the named input device on the current host instead of its default
+    pub fn select_device(&mut self, name: &str) -> Result<()> {
+        let device = self.find_input_device(name)?;
+
+        println!("Using audio device: {}", name);
+        self.device = Some(device);
+        Ok(())
+    }
+
+    /// Also record a microphone (by name, or the default input if empty) and
+    /// mix it into the main capture, e.g. your voice on top of system audio.
+    ///
+    /// With echo cancellation, speaker audio picked up by the mic is removed
+    /// using the main capture as the reference, so it isn't heard twice.
+    pub fn mix_in_microphone(&mut self, name: &str, echo_cancellation: bool) -> Result<()> {
+        let device = if name.is_empty() {
+            self.host
+                .default_input_device()
+                .context("No default input device found for the microphone")?
+        } else {
+            self.find_input_device(name)?
+        };
+
+        println!("Mixing in microphone: {}", device.name()?);
+        self.microphone = Some((device, echo_cancellation));
+        Ok(())
+    }
+
+    fn find_input_device(&self, name: &str) -> Result<Device> {
+        self.host
+            .input_devices()?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .with_context(|| {
+                format!(
+                    "Input device '{}' not found on the {} host",
+                    name,
+                    self.host.id().name()
+                )
+            })
+    }
+
     /// Start recording audio in chunks
     pub fn start_recording<F>(&mut self, on_chunk_ready: F) -> Result<()>
     where
-        F: Fn(PathBuf) + Send + 'static,
+        F: Fn(PathBuf, ChunkMetadata) + Send + 'static,
     {
         if self.is_recording.load(Ordering::SeqCst) {
             anyhow::bail!("Already recording");
         }
 
-        if self.device.is_none() {
-            self.get_default_device()?;
+        // Take over this capture's own pre-roll if it was buffering
+        if let Some(pre_roll) = self.take_pre_roll() {
+            self.pre_roll = Some(pre_roll);
         }
 
-        let device = self.device.as_ref().unwrap();
-
-        // Get supported config
-        let config = device.default_input_config()?;
-        println!("Default input config: {:?}", config);
+        // Lock-free ring buffer from the audio callback to the chunk writer,
+        // carrying interleaved frames when channels are split
+        let channels = self.output_channels();
+        let pre_roll = match self.pre_roll.take() {
+            Some(pre_roll) if pre_roll.samples.is_empty() => Vec::new(),
+            Some(pre_roll)
+                if pre_roll.sample_rate == self.sample_rate && pre_roll.channels == channels =>
+            {
+                println!(
+                    "Prepending {:.1}s of pre-roll audio",
+                    pre_roll.samples.len() as f32 / (self.sample_rate as usize * channels) as f32
+                );
+                pre_roll.samples
+            }
+            Some(_) => {
+                eprintln!("Discarding pre-roll audio recorded with different settings");
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        let (producer, mut consumer) =
+            RingBuffer::<f32>::new(self.sample_rate as usize * RING_BUFFER_SECS * channels);
+        self.counters
+            .reset(self.sample_rate as usize * RING_BUFFER_SECS);
+        let mut stream = self.open_input_stream(producer, channels)?;
 
-        // Create stream config with our desired sample rate
-        let stream_config = StreamConfig {
-            channels: 1, // Mono audio
-            sample_rate: cpal::SampleRate(self.sample_rate),
-            buffer_size: cpal::BufferSize::Default,
+        let mic_mixer = match &self.microphone {
+            Some(_) if channels > 1 => {
+                anyhow::bail!("A microphone can't be mixed in while stereo channels are split")
+            }
+            Some((device, echo_cancellation)) => {
+                let (mic_producer, mic_consumer) =
+                    RingBuffer::<f32>::new(self.sample_rate as usize * RING_BUFFER_SECS);
+                self.mic_stream = Some(self.open_microphone_stream(device, mic_producer)?);
+                Some(MicMixer::new(
+                    mic_consumer,
+                    self.sample_rate,
+                    *echo_cancellation,
+                ))
+            }
+            None => None,
         };
 
+        let monitor = self.open_monitor(channels);
+        let live_feed = self.live_feed.take();
+
         let is_recording = Arc::clone(&self.is_recording);
         is_recording.store(true, Ordering::SeqCst);
 
         let sample_rate = self.sample_rate;
-        let chunk_duration = self.chunk_duration;
-        let output_dir = self.output_dir.clone();
+        let device_name = self.stream_info.as_ref().unwrap().device_name.clone();
+        let make_settings = |speaker: Option<String>, channel_tag| ChunkSettings {
+            sample_rate,
+            device_name: device_name.clone(),
+            source: self.source.clone(),
+            speaker,
+            channel_tag,
+            chunk_duration: self.chunk_duration,
+            output_dir: self.output_dir.clone(),
+            audio_format: self.audio_format,
+            wav_format: self.wav_format,
+            vad: self
+                .vad
+                .as_ref()
+                .map(|settings| VoiceActivityDetector::new(sample_rate, settings)),
+            denoiser: self.noise_suppression.map(SpectralGate::new),
+            silence_threshold: self.silence_threshold,
+            overlap_samples: (sample_rate as f32 * self.chunk_overlap.as_secs_f32()) as usize,
+            max_chunk_bytes: self.max_chunk_bytes,
+        };
+        let channel_settings = match &self.channel_labels {
+            Some([left, right]) => vec![
+                make_settings(Some(left.clone()), Some("left")),
+                make_settings(Some(right.clone()), Some("right")),
+            ],
+            None => vec![make_settings(None, None)],
+        };
 
-        // Shared buffer for collecting samples
-        let samples_buffer: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
-        let samples_clone = Arc::clone(&samples_buffer);
+        self.session_path = None;
+        let wav_format = self.wav_format;
+        let session = match &self.session_recording {
+            Some((dir, format)) => {
+                match SessionRecorder::create(dir, *format, wav_format, sample_rate) {
+                    Ok(recorder) => {
+                        self.session_path = Some(recorder.final_path.clone());
+                        Some(recorder)
+                    }
+                    Err(e) => {
+                        eprintln!("Error starting session recording: {}", e);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        let skipped_chunks = Arc::clone(&self.skipped_chunks);
+        skipped_chunks.store(0, Ordering::SeqCst);
+        self.dropped_samples.store(0, Ordering::Relaxed);
+        let counters = Arc::clone(&self.counters);
+        let dropped_samples = Arc::clone(&self.dropped_samples);
+        let stream_info = self.stream_info.clone();
+        let stats_label = match &self.source {
+            Some(source) => format!("Capture stats ({})", source),
+            None => "Capture stats".to_string(),
+        };
 
         // Spawn a thread to handle chunk writing
         let is_recording_clone = Arc::clone(&is_recording);
         thread::spawn(move || {
-            let chunk_samples = (sample_rate as u64 * chunk_duration.as_secs()) as usize;
-
-            while is_recording_clone.load(Ordering::SeqCst) {
-                thread::sleep(Duration::from_millis(100));
-
-                let mut buffer = samples_clone.lock().unwrap();
-                if buffer.len() >= chunk_samples {
-                    // Extract chunk
-                    let chunk: Vec<f32> = buffer.drain(..chunk_samples).collect();
-                    drop(buffer); // Release lock
-
-                    // Write chunk to file
-                    let timestamp = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-                    let filename = format!("chunk_{}.wav", timestamp);
-                    let filepath = output_dir.join(filename);
-
-                    if let Err(e) = write_wav_file(&filepath, &chunk, sample_rate) {
-                        eprintln!("Error writing audio chunk: {}", e);
-                    } else {
-                        println!("Audio chunk saved: {:?}", filepath);
-                        on_chunk_ready(filepath);
-                    }
+            let mut stats_window = StatsWindow::new();
+            let mut last_stats_log = Instant::now();
+            let log_stats = |window: &mut StatsWindow| {
+                let stats = window.snapshot(
+                    &counters,
+                    stream_info.clone(),
+                    dropped_samples.load(Ordering::Relaxed),
+                    sample_rate,
+                );
+                println!("{}: {}", stats_label, stats);
+            };
+
+            let on_chunk_ready = Rc::new(on_chunk_ready);
+            let mut writers: Vec<_> = channel_settings
+                .into_iter()
+                .map(|settings| {
+                    let on_chunk_ready = Rc::clone(&on_chunk_ready);
+                    ChunkWriter::new(settings, Arc::clone(&skipped_chunks), move |path, meta| {
+                        on_chunk_ready(path, meta)
+                    })
+                })
+                .collect();
+            let mut session = session;
+            let mut mic_mixer = mic_mixer;
+            let mut monitor = monitor;
+            let mut with_mic = Vec::new();
+            let mut incoming = pre_roll;
+            let mut planar = vec![Vec::new(); writers.len()];
+
+            loop {
+                let running = is_recording_clone.load(Ordering::SeqCst);
+                if running {
+                    thread::sleep(Duration::from_millis(100));
                 }
-            }
 
-            // Write remaining samples when stopped
-            let mut buffer = samples_clone.lock().unwrap();
-            if !buffer.is_empty() {
-                let chunk: Vec<f32> = buffer.drain(..).collect();
-                drop(buffer);
+                counters.record_backlog(consumer.slots() / channels);
+                drain_ring(&mut consumer, &mut incoming);
+                if last_stats_log.elapsed() >= STATS_LOG_INTERVAL {
+                    log_stats(&mut stats_window);
+                    last_stats_log = Instant::now();
+                }
 
-                let timestamp = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
-                let filename = format!("chunk_{}_final.wav", timestamp);
-                let filepath = output_dir.join(filename);
+                if let Some(mixer) = mic_mixer.as_mut() {
+                    with_mic.clear();
+                    mixer.mix(&incoming, &mut with_mic);
+                    std::mem::swap(&mut incoming, &mut with_mic);
+                }
+                if !running && let Some(mixer) = mic_mixer.take() {
+                    mixer.finish(&mut incoming);
+                }
+                if let Some(monitor) = monitor.as_mut() {
+                    monitor.push(&incoming);
+                }
 
-                if let Err(e) = write_wav_file(&filepath, &chunk, sample_rate) {
-                    eprintln!("Error writing final audio chunk: {}", e);
+                if writers.len() == 1 {
+                    if let Some(session) = session.as_mut() {
+                        session.append(&incoming);
+                    }
+                    if let Some(feed) = &live_feed {
+                        let _ = feed.send(incoming.clone());
+                    }
+                    writers[0].push(&incoming);
                 } else {
-                    println!("Final audio chunk saved: {:?}", filepath);
-                    on_chunk_ready(filepath);
+                    // Split interleaved frames into one stream per channel
+                    for channel in planar.iter_mut() {
+                        channel.clear();
+                    }
+                    let mut mixed = Vec::with_capacity(incoming.len() / writers.len());
+                    for frame in incoming.chunks_exact(writers.len()) {
+                        for (channel, &sample) in planar.iter_mut().zip(frame) {
+                            channel.push(sample);
+                        }
+                        mixed.push(frame.iter().sum::<f32>() / frame.len() as f32);
+                    }
+                    if let Some(session) = session.as_mut() {
+                        session.append(&mixed);
+                    }
+                    if let Some(feed) = &live_feed {
+                        let _ = feed.send(mixed);
+                    }
+                    for (writer, samples) in writers.iter_mut().zip(&planar) {
+                        writer.push(samples);
+                    }
                 }
-            }
-        });
 
-        // Build the input stream
-        let stream = match config.sample_format() {
-            SampleFormat::I16 => {
-                self.build_stream::<i16>(device, &stream_config, samples_buffer)?
+                incoming.clear();
+
+                // Once stopped, the final drain above wrote the remaining samples
+                if !running {
+                    break;
+                }
             }
-            SampleFormat::U16 => {
-                self.build_stream::<u16>(device, &stream_config, samples_buffer)?
+
+            log_stats(&mut stats_window);
+            for writer in writers {
+                writer.finish();
             }
-            SampleFormat::F32 => {
-                self.build_stream::<f32>(device, &stream_config, samples_buffer)?
+            if let Some(session) = session {
+                match session.finish() {
+                    Ok(path) => println!("Session recording saved: {:?}", path),
+                    Err(e) => eprintln!("Error finishing session recording: {}", e),
+                }
             }
-            format => anyhow::bail!("Unsupported sample format: {:?}", format),
-        };
+        });
 
         stream.play()?;
+        if let Some(mic_stream) = &self.mic_stream {
+            mic_stream.play()?;
+        }
         self.stream = Some(stream);
 
         Ok(())
     }
 
+    /// Record a fixed length of audio and return it, e.g. for a microphone test
+    pub fn record_samples(&mut self, duration: Duration) -> Result<Vec<f32>> {
+        if self.is_recording() {
+            anyhow::bail!("Already recording");
+        }
+
+        let wanted = (self.sample_rate as f64 * duration.as_secs_f64()) as usize;
+        let (producer, mut consumer) = RingBuffer::<f32>::new(wanted + self.sample_rate as usize);
+        let mut stream = self.open_input_stream(producer, 1)?;
+
+        stream.play()?;
+        thread::sleep(duration);
+        drop(stream);
+
+        let mut samples = Vec::with_capacity(wanted);
+        drain_ring(&mut consumer, &mut samples);
+        samples.truncate(wanted);
+        Ok(samples)
+    }
+
+    /// Open the selected (or default) device with a negotiated config and
+    /// build a stream that feeds samples at `sample_rate` into `producer`,
+    /// either mixed to mono or as interleaved stereo (`channels` = 2)
+    fn open_input_stream(
+        &mut self,
+        producer: Producer<f32>,
+        channels: usize,
+    ) -> Result<InputStream> {
+        if let Some(name) = self.capture_app.clone() {
+            return self.open_app_stream(&name, producer, channels);
+        }
+
+        if self.device.is_none() {
+            self.get_default_device()?;
+        }
+
+        let device = self.device.as_ref().unwrap();
+
+        let (stream_config, sample_format) = negotiate_config(
+            device,
+            self.capture_system_audio,
+            self.sample_rate,
+            channels as u16,
+        )?;
+
+        let device_name = device
+            .name()
+            .unwrap_or_else(|_| "Unknown device".to_string());
+        let narrowband = if self.capture_system_audio {
+            None
+        } else {
+            bluetooth::narrowband_warning(&device_name, stream_config.sample_rate.0)
+        };
+        if let Some(warning) = &narrowband
+            && self.avoid_narrowband_input
+            && let Some(alternative) = self.find_wideband_device(&device_name, channels as u16)
+        {
+            println!(
+                "{}; recording from {} instead",
+                warning,
+                alternative.name().unwrap_or_default()
+            );
+            self.device = Some(alternative);
+            return self.open_input_stream(producer, channels);
+        }
+        if let Some(warning) = &narrowband {
+            eprintln!("Warning: {}", warning);
+        }
+        self.input_warning = narrowband;
+
+        self.stream_info = Some(StreamInfo {
+            device_name,
+            channels: stream_config.channels,
+            device_sample_rate: stream_config.sample_rate.0,
+            sample_format,
+            output_sample_rate: self.sample_rate,
+        });
+        println!("Negotiated stream: {}", self.stream_info.as_ref().unwrap());
+
+        if stream_config.sample_rate.0 != self.sample_rate {
+            println!(
+                "Device runs at {} Hz, resampling to {} Hz",
+                stream_config.sample_rate.0, self.sample_rate
+            );
+        }
+
+        let output = self.capture_output(
+            producer,
+            channels,
+            stream_config.sample_rate.0,
+            Arc::clone(&self.input_level),
+            Arc::clone(&self.counters),
+        );
+        let stream = self.build_stream_for_format(device, &stream_config, sample_format, output)?;
+        Ok(InputStream::Device(stream))
+    }
+
+    /// An input device on the current host, other than `current`, that
+    /// delivers full speech bandwidth and isn't a Bluetooth headset
+    fn find_wideband_device(&self, current: &str, channels: u16) -> Option<Device> {
+        self.host.input_devices().ok()?.find(|device| {
+            let Ok(name) = device.name() else {
+                return false;
+            };
+            name != current
+                && !bluetooth::looks_like_bluetooth(&name)
+                && negotiate_config(device, false, self.sample_rate, channels)
+                    .is_ok_and(|(config, _)| !bluetooth::is_narrowband(&name, config.sample_rate.0))
+        })
+    }
+
+    /// Record one application's playback through `parec`, or WASAPI process
+    /// loopback on Windows, which also convert it to the output rate and
+    /// channel count
+    fn open_app_stream(
+        &mut self,
+        name: &str,
+        producer: Producer<f32>,
+        channels: usize,
+    ) -> Result<InputStream> {
+        let app = app_capture::find_app(name)?;
+        let output = self.capture_output(
+            producer,
+            channels,
+            self.sample_rate,
+            Arc::clone(&self.input_level),
+            Arc::clone(&self.counters),
+        );
+        let stream = record_app(&app, self.sample_rate, channels, output)?;
+
+        self.stream_info = Some(StreamInfo {
+            device_name: format!("{} (application)", app.name),
+            channels: channels as u16,
+            device_sample_rate: self.sample_rate,
+            sample_format: SampleFormat::F32,
+            output_sample_rate: self.sample_rate,
+        });
+        println!("Capturing application: {}", app.label());
+        Ok(stream)
+    }
+
+    fn capture_output(
+        &self,
+        producer: Producer<f32>,
+        channels: usize,
+        device_rate: u32,
+        input_level: Arc<[AtomicU32; 2]>,
+        counters: Arc<CaptureCounters>,
+    ) -> CaptureOutput {
+        CaptureOutput {
+            producer,
+            channels,
+            dropped_samples: Arc::clone(&self.dropped_samples),
+            counters,
+            device_rate,
+            last_callback: None,
+            input_level,
+            window_len: (self.sample_rate / 10).max(1) as usize * channels,
+            window_peak: 0.0,
+            window_sum_sq: 0.0,
+            window_count: 0,
+        }
+    }
+
+    /// Open the microphone being mixed in, feeding mono samples at
+    /// `sample_rate` into `producer`
+    fn open_microphone_stream(&self, device: &Device, producer: Producer<f32>) -> Result<Stream> {
+        let (stream_config, sample_format) = negotiate_config(device, false, self.sample_rate, 1)?;
+        println!(
+            "Microphone stream: {} ch, {} Hz, {:?}",
+            stream_config.channels, stream_config.sample_rate.0, sample_format
+        );
+
+        // The level meter and statistics keep showing the main capture
+        let output = self.capture_output(
+            producer,
+            1,
+            stream_config.sample_rate.0,
+            Arc::new([AtomicU32::new(0), AtomicU32::new(0)]),
+            Arc::new(CaptureCounters::default()),
+        );
+        self.build_stream_for_format(device, &stream_config, sample_format, output)
+    }
+
+    fn build_stream_for_format(
+        &self,
+        device: &Device,
+        config: &StreamConfig,
+        sample_format: SampleFormat,
+        output: CaptureOutput,
+    ) -> Result<Stream> {
+        let stream = match sample_format {
+            SampleFormat::I8 => self.build_stream::<i8>(device, config, output)?,
+            SampleFormat::I16 => self.build_stream::<i16>(device, config, output)?,
+            SampleFormat::I32 => self.build_stream::<i32>(device, config, output)?,
+            SampleFormat::U8 => self.build_stream::<u8>(device, config, output)?,
+            SampleFormat::U16 => self.build_stream::<u16>(device, config, output)?,
+            SampleFormat::F32 => self.build_stream::<f32>(device, config, output)?,
+            SampleFormat::F64 => self.build_stream::<f64>(device, config, output)?,
+            format => anyhow::bail!("Unsupported sample format: {:?}", format),
+        };
+
+        Ok(stream)
+    }
+
     /// Stop recording
     pub fn stop_recording(&mut self) -> Result<()> {
         if !self.is_recording.load(Ordering::SeqCst) {
@@ -182,6 +1162,8 @@ impl AudioCapture {
         if let Some(stream) = self.stream.take() {
             drop(stream);
         }
+        self.mic_stream = None;
+        self.monitor_stream = None;
 
         // Give time for the final chunk to be written
         thread::sleep(Duration::from_millis(500));
@@ -190,31 +1172,79 @@ impl AudioCapture {
     }
 
     /// Check if currently recording
-    #[allow(dead_code)]
     pub fn is_recording(&self) -> bool {
         self.is_recording.load(Ordering::SeqCst)
     }
 
+    /// Channels fed to the chunk writer: mono, or left/right when split
+    fn output_channels(&self) -> usize {
+        if self.channel_labels.is_some() { 2 } else { 1 }
+    }
+
     /// Build input stream for a specific sample type
     fn build_stream<T>(
         &self,
         device: &Device,
         config: &StreamConfig,
-        samples_buffer: Arc<Mutex<Vec<f32>>>,
+        mut output: CaptureOutput,
     ) -> Result<Stream>
     where
         T: cpal::Sample + cpal::SizedSample,
         f32: cpal::FromSample<T>,
     {
-        let err_fn = |err| eprintln!("Stream error: {}", err);
+        let counters = Arc::clone(&output.counters);
+        let err_fn = move |err| {
+            counters.record_stream_error();
+            eprintln!("Stream error: {}", err);
+        };
+        let channels = config.channels as usize;
+        let output_channels = output.channels;
+        let mut resamplers: Vec<_> = (0..output_channels)
+            .map(|_| LinearResampler::new(config.sample_rate.0, self.sample_rate))
+            .collect();
+        let mut planar = vec![Vec::new(); output_channels];
+        let mut resampled = vec![Vec::new(); output_channels];
+        let mut interleaved = Vec::new();
 
         let stream = device.build_input_stream(
             config,
-            move |data: &[T], _: &cpal::InputCallbackInfo| {
-                let mut buffer = samples_buffer.lock().unwrap();
-                for &sample in data {
-                    buffer.push(sample.to_sample::<f32>());
+            move |data: &[T], info: &cpal::InputCallbackInfo| {
+                let timestamp = info.timestamp();
+                output.record_callback(
+                    data.len() / channels,
+                    timestamp.callback.duration_since(&timestamp.capture),
+                );
+
+                // Average interleaved frames down to mono, or keep the first
+                // two channels apart when splitting left and right
+                for channel in planar.iter_mut() {
+                    channel.clear();
+                }
+                for frame in data.chunks(channels) {
+                    if output_channels == 1 {
+                        let sum: f32 = frame.iter().map(|s| s.to_sample::<f32>()).sum();
+                        planar[0].push(sum / frame.len() as f32);
+                    } else {
+                        for (channel, out) in planar.iter_mut().enumerate() {
+                            let sample = frame.get(channel).or(frame.last());
+                            out.push(sample.map_or(0.0, |s| s.to_sample::<f32>()));
+                        }
+                    }
+                }
+
+                for ((resampler, input), output) in
+                    resamplers.iter_mut().zip(&planar).zip(resampled.iter_mut())
+                {
+                    output.clear();
+                    resampler.process(input, output);
+                }
+                interleaved.clear();
+                let frames = resampled.iter().map(Vec::len).min().unwrap_or(0);
+                for i in 0..frames {
+                    interleaved.extend(resampled.iter().map(|channel| channel[i]));
                 }
+
+                output.push(&interleaved);
             },
             err_fn,
             None,
@@ -224,29 +1254,170 @@ impl AudioCapture {
     }
 }
 
-/// Write samples to a WAV file
-fn write_wav_file(path: &PathBuf, samples: &[f32], sample_rate: u32) -> Result<()> {
-    let spec = WavSpec {
-        channels: 1,
-        sample_rate,
-        bits_per_sample: 16,
-        sample_format: hound::SampleFormat::Int,
+/// Move everything queued by the audio callback into `buffer`
+fn drain_ring(consumer: &mut Consumer<f32>, buffer: &mut Vec<f32>) {
+    let available = consumer.slots();
+    if let Ok(chunk) = consumer.read_chunk(available) {
+        buffer.extend(chunk);
+    }
+}
+
+/// Sample formats `build_stream` can convert, in order of preference
+const SUPPORTED_FORMATS: [SampleFormat; 7] = [
+    SampleFormat::F32,
+    SampleFormat::I16,
+    SampleFormat::I32,
+    SampleFormat::F64,
+    SampleFormat::U16,
+    SampleFormat::I8,
+    SampleFormat::U8,
+];
+
+/// Start `parec` on `app`'s stream and a thread feeding what it records,
+/// raw f32 at `sample_rate` with `channels`, to `output`
+#[cfg(not(target_os = "windows"))]
+fn record_app(
+    app: &app_capture::AudioApp,
+    sample_rate: u32,
+    channels: usize,
+    mut output: CaptureOutput,
+) -> Result<InputStream> {
+    let mut child = app_capture::spawn_recorder(app, sample_rate, channels)?;
+    let mut stdout = child.stdout.take().context("parec has no output")?;
+    let reader = thread::spawn(move || {
+        let frame_bytes = 4 * channels;
+        let mut buf = [0u8; 8192];
+        let mut bytes = Vec::new();
+        let mut samples = Vec::new();
+
+        // Ends when parec exits: on stop, or when the app closes its stream
+        while let Ok(read) = stdout.read(&mut buf) {
+            if read == 0 {
+                break;
+            }
+            bytes.extend_from_slice(&buf[..read]);
+            let whole = bytes.len() / frame_bytes * frame_bytes;
+
+            samples.clear();
+            samples.extend(
+                bytes[..whole]
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            );
+            bytes.drain(..whole);
+            output.record_callback(samples.len() / channels, None);
+            output.push(&samples);
+        }
+        println!("Application capture ended");
+    });
+
+    Ok(InputStream::App(AppRecorder {
+        child,
+        reader: Some(reader),
+    }))
+}
+
+/// Capture what `app`'s process (and its children) play through WASAPI
+/// process loopback, converted by Windows to `sample_rate` and `channels`
+#[cfg(target_os = "windows")]
+fn record_app(
+    app: &app_capture::AudioApp,
+    sample_rate: u32,
+    channels: usize,
+    mut output: CaptureOutput,
+) -> Result<InputStream> {
+    let recorder =
+        process_loopback::record(app.index, sample_rate, channels, move |samples, frames| {
+            output.record_callback(frames, None);
+            output.push(samples);
+        })?;
+    Ok(InputStream::Process(recorder))
+}
+
+/// Look up a cpal host by name (case-insensitive), or the default host if empty
+fn open_host(name: &str) -> Result<Host> {
+    if name.is_empty() {
+        return Ok(cpal::default_host());
+    }
+
+    let id = cpal::available_hosts()
+        .into_iter()
+        .find(|id| id.name().eq_ignore_ascii_case(name))
+        .with_context(|| {
+            format!(
+                "Audio host '{}' is not available. Available hosts: {}",
+                name,
+                AudioCapture::available_hosts().join(", ")
+            )
+        })?;
+
+    cpal::host_from_id(id).with_context(|| format!("Failed to open audio host '{}'", name))
+}
+
+/// Pick a stream config the device actually supports.
+///
+/// Loopback streams must use the render device's mix format. For inputs, a
+/// config at the target rate is preferred (the fewest channels that still
+/// gives `min_channels`), falling back to the device default; extra channels
+/// are downmixed and other rates resampled in the callback.
+fn negotiate_config(
+    device: &Device,
+    loopback: bool,
+    target_rate: u32,
+    min_channels: u16,
+) -> Result<(StreamConfig, SampleFormat)> {
+    let default = if loopback {
+        device.default_output_config()?
+    } else {
+        device.default_input_config()?
     };
+    println!("Default input config: {:?}", default);
 
-    let mut writer = WavWriter::create(path, spec)?;
+    let format_rank = |format: SampleFormat| SUPPORTED_FORMATS.iter().position(|f| *f == format);
 
-    for &sample in samples {
-        // Convert f32 (-1.0 to 1.0) to i16
-        let amplitude = (sample * i16::MAX as f32) as i16;
-        writer.write_sample(amplitude)?;
+    if !loopback {
+        let best = device.supported_input_configs().ok().and_then(|configs| {
+            configs
+                .filter(|c| {
+                    c.channels() >= min_channels
+                        && c.min_sample_rate().0 <= target_rate
+                        && target_rate <= c.max_sample_rate().0
+                        && format_rank(c.sample_format()).is_some()
+                })
+                .min_by_key(|c| (c.channels(), format_rank(c.sample_format())))
+        });
+
+        if let Some(best) = best {
+            let config = StreamConfig {
+                channels: best.channels(),
+                sample_rate: cpal::SampleRate(target_rate),
+                buffer_size: cpal::BufferSize::Default,
+            };
+            return Ok((config, best.sample_format()));
+        }
     }
 
-    writer.finalize()?;
-    Ok(())
+    if format_rank(default.sample_format()).is_none() {
+        anyhow::bail!("Unsupported sample format: {:?}", default.sample_format());
+    }
+    if default.channels() < min_channels {
+        eprintln!(
+            "Warning: device has {} channel(s); the same audio will be used for both sides",
+            default.channels()
+        );
+    }
+
+    let config = StreamConfig {
+        channels: default.channels(),
+        sample_rate: default.sample_rate(),
+        buffer_size: cpal::BufferSize::Default,
+    };
+    Ok((config, default.sample_format()))
 }
 
 impl Drop for AudioCapture {
     fn drop(&mut self) {
+        self.take_pre_roll();
         let _ = self.stop_recording();
     }
 }
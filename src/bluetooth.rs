@@ -0,0 +1,95 @@
+use std::process::Command;
+
+/// Lowest input rate that carries the full speech band; Bluetooth headsets
+/// in the hands-free profile (HFP/HSP) deliver 8 kHz telephone audio
+pub const MIN_SPEECH_RATE: u32 = 16_000;
+
+/// Device names that route through the sound server, where the real source
+/// can run at a lower rate than the stream that is opened
+const SOUND_SERVER_DEVICES: [&str; 3] = ["default", "pulse", "pipewire"];
+
+/// Whether a device name looks like a Bluetooth headset
+pub fn looks_like_bluetooth(name: &str) -> bool {
+    let name = name.to_lowercase();
+    [
+        "bluez",
+        "bluetooth",
+        "hands-free",
+        "handsfree",
+        "headset_head_unit",
+        "airpods",
+    ]
+    .iter()
+    .any(|hint| name.contains(hint))
+}
+
+/// Why the input is limited to telephone quality, if it is.
+///
+/// `device_rate` is the rate the stream was opened at. For the sound server's
+/// ALSA devices the server's default source is checked instead, since the
+/// server resamples an 8 kHz headset up to whatever rate was asked for.
+pub fn narrowband_warning(device_name: &str, device_rate: u32) -> Option<String> {
+    let (name, rate) = if SOUND_SERVER_DEVICES.contains(&device_name) {
+        default_source().unwrap_or((device_name.to_string(), device_rate))
+    } else {
+        (device_name.to_string(), device_rate)
+    };
+    if rate >= MIN_SPEECH_RATE {
+        return None;
+    }
+
+    let advice = if looks_like_bluetooth(&name) {
+        "the Bluetooth headset is in its hands-free profile. Record from another \
+         microphone so the headset can stay in its high-quality profile"
+    } else {
+        "transcription accuracy will suffer"
+    };
+    Some(format!(
+        "{} only delivers {} kHz audio: {}",
+        name,
+        rate as f32 / 1000.0,
+        advice
+    ))
+}
+
+/// Whether opening `device_name` would record from a narrowband source
+pub fn is_narrowband(device_name: &str, device_rate: u32) -> bool {
+    narrowband_warning(device_name, device_rate).is_some()
+}
+
+/// Name and rate of the PulseAudio/PipeWire default source, from `pactl`
+fn default_source() -> Option<(String, u32)> {
+    let pactl = |args: &[&str]| {
+        let output = Command::new("pactl")
+            .args(args)
+            .env("LC_ALL", "C")
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    let info = pactl(&["info"])?;
+    let name = info
+        .lines()
+        .find_map(|line| line.strip_prefix("Default Source:"))?
+        .trim()
+        .to_string();
+
+    // Lines look like "57\tbluez_input.XX\tPipeWire\ts16le 1ch 8000Hz\tRUNNING"
+    let sources = pactl(&["list", "short", "sources"])?;
+    let rate = sources.lines().find_map(|line| {
+        let mut fields = line.split('\t').skip(1);
+        if fields.next()? != name {
+            return None;
+        }
+        fields
+            .nth(1)?
+            .split_whitespace()
+            .find_map(|part| part.strip_suffix("Hz")?.parse().ok())
+    })?;
+
+    Some((name, rate))
+}
@@ -0,0 +1,189 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::audio_capture::StreamInfo;
+
+/// Shortest period callback rates are averaged over
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Counters shared between the audio callback, the chunk writer and whoever
+/// asks for statistics
+#[derive(Debug, Default)]
+pub struct CaptureCounters {
+    callbacks: AtomicU64,
+    /// Frames delivered by the device, before resampling
+    frames: AtomicU64,
+    underruns: AtomicU64,
+    overruns: AtomicU64,
+    stream_errors: AtomicU64,
+    /// Capture-to-callback delay reported by the host, in microseconds
+    latency_us: AtomicU64,
+    /// Frames waiting in the ring buffer when the writer last drained it
+    buffered_frames: AtomicUsize,
+    capacity_frames: AtomicUsize,
+}
+
+impl CaptureCounters {
+    /// Zero everything for a new recording with a ring buffer of `capacity_frames`
+    pub fn reset(&self, capacity_frames: usize) {
+        for counter in [
+            &self.callbacks,
+            &self.frames,
+            &self.underruns,
+            &self.overruns,
+            &self.stream_errors,
+            &self.latency_us,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.buffered_frames.store(0, Ordering::Relaxed);
+        self.capacity_frames
+            .store(capacity_frames, Ordering::Relaxed);
+    }
+
+    pub fn record_callback(&self, frames: usize, latency: Option<Duration>) {
+        self.callbacks.fetch_add(1, Ordering::Relaxed);
+        self.frames.fetch_add(frames as u64, Ordering::Relaxed);
+        if let Some(latency) = latency {
+            self.latency_us
+                .store(latency.as_micros() as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_underrun(&self) {
+        self.underruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_overrun(&self) {
+        self.overruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_stream_error(&self) {
+        self.stream_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_backlog(&self, frames: usize) {
+        self.buffered_frames.store(frames, Ordering::Relaxed);
+    }
+}
+
+/// How well capture is keeping up, for debugging transcripts that lag far
+/// behind the conversation
+#[derive(Debug, Clone, Default)]
+pub struct AudioCaptureStats {
+    /// Configuration actually negotiated with the device
+    pub stream: Option<StreamInfo>,
+    pub callbacks_per_sec: f32,
+    /// Device frames delivered per callback, on average
+    pub frames_per_callback: f32,
+    /// Delay between capture and the callback, when the host reports it
+    pub device_latency: Option<Duration>,
+    /// Audio waiting between the callback and the chunk writer
+    pub buffered_secs: f32,
+    /// `buffered_secs` as a fraction of the ring buffer
+    pub buffer_fill: f32,
+    /// Gaps in delivery: callbacks arriving more than twice their audio's
+    /// length after the previous one (device stalls, or WASAPI loopback
+    /// while nothing plays)
+    pub underruns: u64,
+    /// Callbacks that found the ring buffer full
+    pub overruns: u64,
+    pub dropped_samples: u64,
+    pub stream_errors: u64,
+}
+
+impl std::fmt::Display for AudioCaptureStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(stream) = &self.stream {
+            write!(f, "{}; ", stream)?;
+        }
+        write!(
+            f,
+            "{:.1} callbacks/s × {:.0} frames",
+            self.callbacks_per_sec, self.frames_per_callback
+        )?;
+        if let Some(latency) = self.device_latency {
+            write!(f, ", latency {:.1} ms", latency.as_secs_f32() * 1000.0)?;
+        }
+        write!(
+            f,
+            ", buffer {:.0}% ({:.2}s), {} underruns, {} overruns, {} dropped samples, {} stream errors",
+            self.buffer_fill * 100.0,
+            self.buffered_secs,
+            self.underruns,
+            self.overruns,
+            self.dropped_samples,
+            self.stream_errors
+        )
+    }
+}
+
+/// Turns the running counters into rates; each reader keeps its own
+#[derive(Debug, Clone, Copy)]
+pub struct StatsWindow {
+    since: Instant,
+    callbacks: u64,
+    frames: u64,
+    callbacks_per_sec: f32,
+    frames_per_callback: f32,
+}
+
+impl StatsWindow {
+    pub fn new() -> Self {
+        Self {
+            since: Instant::now(),
+            callbacks: 0,
+            frames: 0,
+            callbacks_per_sec: 0.0,
+            frames_per_callback: 0.0,
+        }
+    }
+
+    pub fn snapshot(
+        &mut self,
+        counters: &CaptureCounters,
+        stream: Option<StreamInfo>,
+        dropped_samples: u64,
+        sample_rate: u32,
+    ) -> AudioCaptureStats {
+        let callbacks = counters.callbacks.load(Ordering::Relaxed);
+        let frames = counters.frames.load(Ordering::Relaxed);
+        let elapsed = self.since.elapsed();
+        // Callback counters restart with each recording
+        if callbacks < self.callbacks {
+            *self = Self::new();
+        } else if elapsed >= RATE_WINDOW {
+            let new_callbacks = callbacks - self.callbacks;
+            self.callbacks_per_sec = new_callbacks as f32 / elapsed.as_secs_f32();
+            self.frames_per_callback = if new_callbacks == 0 {
+                0.0
+            } else {
+                (frames - self.frames) as f32 / new_callbacks as f32
+            };
+            self.since = Instant::now();
+            self.callbacks = callbacks;
+            self.frames = frames;
+        }
+
+        let buffered = counters.buffered_frames.load(Ordering::Relaxed);
+        let capacity = counters.capacity_frames.load(Ordering::Relaxed);
+        let latency_us = counters.latency_us.load(Ordering::Relaxed);
+
+        AudioCaptureStats {
+            stream,
+            callbacks_per_sec: self.callbacks_per_sec,
+            frames_per_callback: self.frames_per_callback,
+            device_latency: (latency_us > 0).then(|| Duration::from_micros(latency_us)),
+            buffered_secs: buffered as f32 / sample_rate.max(1) as f32,
+            buffer_fill: if capacity == 0 {
+                0.0
+            } else {
+                buffered as f32 / capacity as f32
+            },
+            underruns: counters.underruns.load(Ordering::Relaxed),
+            overruns: counters.overruns.load(Ordering::Relaxed),
+            dropped_samples,
+            stream_errors: counters.stream_errors.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,704 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use hound::WavWriter;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::denoise::SpectralGate;
+use crate::diagnostics::{AudioDiagnostics, DiagnosticsBuilder};
+use crate::encoding::{self, AudioFormat, WavSampleFormat};
+use crate::vad::{self, VoiceActivityDetector};
+use crate::waveform::{WAVEFORM_POINTS, Waveform, WaveformBuilder};
+
+/// Appended to chunk files while they are being written; a chunk only gets
+/// its real name once it is complete
+pub const PARTIAL_EXTENSION: &str = "part";
+
+/// Temporary name a chunk is written under until it is finished
+pub fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(PARTIAL_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Everything the chunk writer needs to know about how to cut and store chunks
+pub struct ChunkSettings {
+    pub sample_rate: u32,
+    /// Capture device name, recorded in each chunk's metadata
+    pub device_name: String,
+    /// Label of the capture, when several record at once
+    pub source: Option<String>,
+    /// Speaker label for chunks from one side of a split stereo stream
+    pub speaker: Option<String>,
+    /// Added to chunk file names to tell parallel channel streams apart
+    pub channel_tag: Option<&'static str>,
+    pub chunk_duration: Duration,
+    pub output_dir: PathBuf,
+    pub audio_format: AudioFormat,
+    /// Sample encoding of WAV chunks
+    pub wav_format: WavSampleFormat,
+    pub vad: Option<VoiceActivityDetector>,
+    pub denoiser: Option<SpectralGate>,
+    pub silence_threshold: Option<f32>,
+    pub overlap_samples: usize,
+    /// File size at which a chunk is cut regardless of its duration
+    pub max_chunk_bytes: Option<u64>,
+}
+
+/// Provenance of a chunk, written as a JSON sidecar next to the audio file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkMetadata {
+    /// Position of the chunk within its recording session, starting at 1
+    pub sequence: u64,
+    pub device_name: String,
+    /// Label of the capture the chunk came from, when several record at once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Speaker label when stereo channels are transcribed separately
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    pub sample_rate: u32,
+    /// Length of the audio in the chunk, including any overlap
+    pub duration_secs: f64,
+    pub peak: f32,
+    pub rms: f32,
+    /// When the first sample of the chunk was captured, worked out from its
+    /// position in the capture stream
+    pub recorded_at: DateTime<Utc>,
+    #[serde(default)]
+    pub waveform: Waveform,
+    #[serde(default)]
+    pub diagnostics: AudioDiagnostics,
+    /// RMS of the written audio over the RMS of the captured audio: what
+    /// noise suppression took out, 1.0 when it's off (a mixed-in microphone
+    /// is added at unity). Not the device's input gain, which isn't known.
+    #[serde(default = "unity_gain")]
+    pub gain: f32,
+}
+
+fn unity_gain() -> f32 {
+    1.0
+}
+
+impl ChunkMetadata {
+    /// When the last sample of the chunk was captured
+    pub fn recorded_until(&self) -> DateTime<Utc> {
+        self.recorded_at + chrono::Duration::milliseconds((self.duration_secs * 1000.0) as i64)
+    }
+
+    /// Sidecar file that sits next to a chunk
+    pub fn sidecar_path(chunk_path: &Path) -> PathBuf {
+        chunk_path.with_extension("json")
+    }
+
+    /// One-line summary for transcript exports
+    pub fn describe(&self) -> String {
+        let labels: Vec<&str> = [&self.source, &self.speaker]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        let channel = if labels.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", labels.join(" · "))
+        };
+        let gain = if (self.gain - 1.0).abs() >= 0.005 {
+            format!(" · gain {:.2}", self.gain)
+        } else {
+            String::new()
+        };
+        let warning = match self.diagnostics.warning() {
+            Some(warning) => format!(" · ⚠ {}", warning),
+            None => String::new(),
+        };
+        format!(
+            "chunk #{} · {}{} · {:.1}s @ {} Hz · peak {:.2} · RMS {:.3}{}{}",
+            self.sequence,
+            self.device_name,
+            channel,
+            self.duration_secs,
+            self.sample_rate,
+            self.peak,
+            self.rms,
+            gain,
+            warning
+        )
+    }
+
+    fn write(&self, chunk_path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::sidecar_path(chunk_path), json)?;
+        Ok(())
+    }
+}
+
+/// Splits the captured sample stream into chunk files.
+///
+/// Plain WAV chunks are streamed to disk as samples arrive, so memory use does
+/// not grow with the chunk duration. VAD, denoising and compressed formats
+/// need the whole chunk at once and are buffered in memory instead.
+pub struct ChunkWriter<F> {
+    settings: ChunkSettings,
+    on_chunk_ready: F,
+    skipped_chunks: Arc<AtomicUsize>,
+    /// Sequence number of the last chunk started
+    sequence: u64,
+
+    /// Samples not yet written to a chunk (buffered mode)
+    buffer: Vec<f32>,
+    /// Samples at the head of `buffer` already sent in the previous chunk
+    overlap_len: usize,
+
+    /// Chunk file currently being written (streaming mode)
+    current: Option<StreamingChunk>,
+    /// Most recent samples, repeated at the start of the next streamed chunk
+    tail: Vec<f32>,
+
+    /// Samples pushed so far, and when the last of them arrived; together
+    /// they date each chunk by its position in the stream rather than by
+    /// when it happened to be written
+    received: u64,
+    last_push_at: DateTime<Utc>,
+}
+
+impl<F> ChunkWriter<F>
+where
+    F: Fn(PathBuf, ChunkMetadata),
+{
+    pub fn new(
+        settings: ChunkSettings,
+        skipped_chunks: Arc<AtomicUsize>,
+        on_chunk_ready: F,
+    ) -> Self {
+        Self {
+            settings,
+            on_chunk_ready,
+            skipped_chunks,
+            sequence: 0,
+            buffer: Vec::new(),
+            overlap_len: 0,
+            current: None,
+            tail: Vec::new(),
+            received: 0,
+            last_push_at: Utc::now(),
+        }
+    }
+
+    /// When the sample at `position` in the stream was captured
+    fn capture_time(&self, position: u64) -> DateTime<Utc> {
+        let behind = self.received.saturating_sub(position);
+        let micros = behind * 1_000_000 / self.settings.sample_rate.max(1) as u64;
+        self.last_push_at - chrono::Duration::microseconds(micros as i64)
+    }
+
+    /// Samples per chunk: the chunk duration or the size limit, whichever is shorter
+    fn chunk_samples(&self) -> usize {
+        let by_duration = self.settings.sample_rate as u64 * self.settings.chunk_duration.as_secs();
+        by_duration.min(self.size_limit_samples() as u64).max(1) as usize
+    }
+
+    /// Most samples a chunk may hold without exceeding the size limit.
+    ///
+    /// Based on the WAV size; FLAC and Opus chunks stay under the 16-bit size.
+    fn size_limit_samples(&self) -> usize {
+        let wav_format = match self.settings.audio_format {
+            AudioFormat::Wav => self.settings.wav_format,
+            _ => WavSampleFormat::Int16,
+        };
+        let bytes_per_sample = wav_format.bits_per_sample() as u64 / 8;
+        match self.settings.max_chunk_bytes {
+            Some(max_bytes) => (max_bytes.saturating_sub(wav_format.header_bytes())
+                / bytes_per_sample)
+                .max(1) as usize,
+            None => usize::MAX,
+        }
+    }
+
+    /// Whether chunks can be written incrementally instead of buffered
+    fn is_streaming(&self) -> bool {
+        self.settings.audio_format == AudioFormat::Wav
+            && self.settings.vad.is_none()
+            && self.settings.denoiser.is_none()
+    }
+
+    /// Feed newly captured samples, writing out any chunks they complete
+    pub fn push(&mut self, samples: &[f32]) {
+        self.received += samples.len() as u64;
+        self.last_push_at = Utc::now();
+        if self.is_streaming() {
+            self.push_streaming(samples);
+            return;
+        }
+
+        self.buffer.extend_from_slice(samples);
+        loop {
+            let chunk_samples = self.chunk_samples();
+            let boundary = match &self.settings.vad {
+                Some(vad) => {
+                    let size_limit = self.size_limit_samples();
+                    vad.find_chunk_boundary(&self.buffer)
+                        .map(|end| end.min(size_limit))
+                        .or((self.buffer.len() >= size_limit).then_some(size_limit))
+                }
+                None => (self.buffer.len() >= chunk_samples).then_some(chunk_samples),
+            };
+            let Some(chunk_end) = boundary else {
+                break;
+            };
+
+            // Extract chunk, leaving the overlap in the buffer for the next one
+            let recorded_at = self.capture_time(self.received - self.buffer.len() as u64);
+            let chunk = self.buffer[..chunk_end].to_vec();
+            let keep = self.settings.overlap_samples.min(chunk_end / 2);
+            self.buffer.drain(..chunk_end - keep);
+            self.overlap_len = keep;
+
+            self.write_chunk(chunk, recorded_at, false);
+        }
+    }
+
+    /// Write whatever audio remains as the final chunk
+    pub fn finish(mut self) {
+        if self.is_streaming() {
+            if let Some(chunk) = self.current.take() {
+                self.finalize_streaming(chunk, true);
+            }
+        } else if self.buffer.len() > self.overlap_len {
+            let recorded_at = self.capture_time(self.received - self.buffer.len() as u64);
+            let chunk = std::mem::take(&mut self.buffer);
+            self.write_chunk(chunk, recorded_at, true);
+        }
+    }
+
+    fn write_chunk(&mut self, mut chunk: Vec<f32>, recorded_at: DateTime<Utc>, is_final: bool) {
+        // Measured before denoising, which would hide clipping
+        let diagnostics = AudioDiagnostics::from_samples(&chunk, self.settings.sample_rate);
+
+        let mut gain = 1.0;
+        if let Some(denoiser) = self.settings.denoiser.as_mut() {
+            let rms_in = vad::rms(&chunk);
+            chunk = denoiser.process(&chunk);
+            if rms_in > 0.0 {
+                gain = vad::rms(&chunk) / rms_in;
+            }
+        }
+
+        if is_silent(&chunk, self.settings.silence_threshold) {
+            println!("Skipping silent audio chunk");
+            self.skipped_chunks.fetch_add(1, Ordering::SeqCst);
+            return;
+        }
+
+        self.sequence += 1;
+        let filepath = self.chunk_path(self.sequence, is_final);
+        let partial = partial_path(&filepath);
+        let format = self.settings.audio_format;
+        let rate = self.settings.sample_rate;
+
+        if let Err(e) = write_audio_file(&partial, &chunk, rate, format, self.settings.wav_format) {
+            eprintln!("Error writing audio chunk: {}", e);
+            let _ = std::fs::remove_file(&partial);
+            return;
+        }
+
+        let peak = chunk.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        let mut metadata = self.metadata(
+            self.sequence,
+            chunk.len(),
+            peak,
+            vad::rms(&chunk),
+            recorded_at,
+        );
+        metadata.waveform = Waveform::from_samples(&chunk, WAVEFORM_POINTS);
+        metadata.diagnostics = diagnostics;
+        metadata.gain = gain;
+        self.chunk_ready(&partial, filepath, metadata);
+    }
+
+    fn metadata(
+        &self,
+        sequence: u64,
+        len: usize,
+        peak: f32,
+        rms: f32,
+        recorded_at: DateTime<Utc>,
+    ) -> ChunkMetadata {
+        let duration_secs = len as f64 / self.settings.sample_rate as f64;
+        ChunkMetadata {
+            sequence,
+            device_name: self.settings.device_name.clone(),
+            source: self.settings.source.clone(),
+            speaker: self.settings.speaker.clone(),
+            sample_rate: self.settings.sample_rate,
+            duration_secs,
+            peak,
+            rms,
+            recorded_at,
+            waveform: Waveform::default(),
+            diagnostics: AudioDiagnostics::default(),
+            gain: 1.0,
+        }
+    }
+
+    /// Move a finished chunk from its temporary name to `path` and hand it to
+    /// the callback. The sidecar is written first, so any chunk found under
+    /// its final name after a crash is complete and has its metadata.
+    fn chunk_ready(&self, partial: &Path, path: PathBuf, metadata: ChunkMetadata) {
+        if let Err(e) = metadata.write(&path) {
+            eprintln!("Error writing chunk metadata: {}", e);
+        }
+        if let Err(e) = std::fs::rename(partial, &path) {
+            eprintln!("Error finishing audio chunk: {}", e);
+            return;
+        }
+        if let Some(warning) = metadata.diagnostics.warning() {
+            eprintln!(
+                "Warning: chunk #{} audio degraded: {}",
+                metadata.sequence, warning
+            );
+        }
+
+        println!("Audio chunk saved: {:?}", path);
+        (self.on_chunk_ready)(path, metadata);
+    }
+
+    fn chunk_path(&self, sequence: u64, is_final: bool) -> PathBuf {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let source = match &self.settings.source {
+            Some(source) => format!("_{}", file_name_tag(source)),
+            None => String::new(),
+        };
+        let channel = match self.settings.channel_tag {
+            Some(tag) => format!("_{}", tag),
+            None => String::new(),
+        };
+        let suffix = if is_final { "_final" } else { "" };
+        let filename = format!(
+            "chunk_{}_{:04}{}{}{}.{}",
+            timestamp,
+            sequence,
+            source,
+            channel,
+            suffix,
+            self.settings.audio_format.extension()
+        );
+        self.settings.output_dir.join(filename)
+    }
+
+    fn push_streaming(&mut self, mut samples: &[f32]) {
+        let chunk_samples = self.chunk_samples();
+        let overlap = self.settings.overlap_samples.min(chunk_samples / 2);
+        // Stream position of `samples[0]`
+        let mut position = self.received - samples.len() as u64;
+
+        while !samples.is_empty() {
+            if self.current.is_none() {
+                self.sequence += 1;
+                let path = self.chunk_path(self.sequence, false);
+                let rate = self.settings.sample_rate;
+                let bucket_len = chunk_samples.div_ceil(WAVEFORM_POINTS);
+                let wav_format = self.settings.wav_format;
+                let recorded_at = self.capture_time(position - self.tail.len() as u64);
+                let created = StreamingChunk::create(
+                    path,
+                    self.sequence,
+                    recorded_at,
+                    rate,
+                    wav_format,
+                    bucket_len,
+                    &self.tail,
+                );
+                match created {
+                    Ok(chunk) => self.current = Some(chunk),
+                    Err(e) => {
+                        eprintln!("Error creating audio chunk: {}", e);
+                        return;
+                    }
+                }
+            }
+            let current = self.current.as_mut().unwrap();
+
+            let take = (chunk_samples - current.len.min(chunk_samples)).min(samples.len());
+            current.write(&samples[..take]);
+
+            self.tail.extend_from_slice(&samples[..take]);
+            let excess = self.tail.len().saturating_sub(overlap);
+            self.tail.drain(..excess);
+
+            samples = &samples[take..];
+            position += take as u64;
+
+            if current.len >= chunk_samples {
+                let chunk = self.current.take().unwrap();
+                self.finalize_streaming(chunk, false);
+            }
+        }
+    }
+
+    fn finalize_streaming(&mut self, chunk: StreamingChunk, is_final: bool) {
+        let has_new_audio = chunk.len > chunk.prefix_len;
+        let rms = chunk.rms();
+        let mut metadata = self.metadata(
+            chunk.sequence,
+            chunk.len,
+            chunk.peak,
+            rms,
+            chunk.recorded_at,
+        );
+        let (partial, waveform, diagnostics) = match chunk.finalize() {
+            Ok(finalized) => finalized,
+            Err(e) => {
+                eprintln!("Error writing audio chunk: {}", e);
+                return;
+            }
+        };
+
+        metadata.waveform = waveform;
+        metadata.diagnostics = diagnostics;
+
+        let silent = self.settings.silence_threshold.is_some_and(|t| rms < t);
+        if !has_new_audio || silent {
+            if silent {
+                println!("Skipping silent audio chunk");
+                self.skipped_chunks.fetch_add(1, Ordering::SeqCst);
+            }
+            let _ = std::fs::remove_file(&partial);
+            return;
+        }
+
+        let path = if is_final {
+            self.chunk_path(metadata.sequence, true)
+        } else {
+            partial.with_extension("")
+        };
+
+        self.chunk_ready(&partial, path, metadata);
+    }
+}
+
+/// A WAV chunk written incrementally as samples arrive, under its partial name
+struct StreamingChunk {
+    writer: WavWriter<BufWriter<File>>,
+    wav_format: WavSampleFormat,
+    partial: PathBuf,
+    sequence: u64,
+    /// When the first sample, overlap included, was captured
+    recorded_at: DateTime<Utc>,
+    len: usize,
+    /// Samples carried over from the previous chunk as overlap
+    prefix_len: usize,
+    sum_sq: f64,
+    peak: f32,
+    waveform: WaveformBuilder,
+    diagnostics: DiagnosticsBuilder,
+}
+
+impl StreamingChunk {
+    fn create(
+        path: PathBuf,
+        sequence: u64,
+        recorded_at: DateTime<Utc>,
+        sample_rate: u32,
+        wav_format: WavSampleFormat,
+        waveform_bucket_len: usize,
+        prefix: &[f32],
+    ) -> Result<Self> {
+        let partial = partial_path(&path);
+        let writer = WavWriter::create(&partial, wav_format.spec(sample_rate))?;
+        let mut chunk = Self {
+            writer,
+            wav_format,
+            partial,
+            sequence,
+            recorded_at,
+            len: 0,
+            prefix_len: prefix.len(),
+            sum_sq: 0.0,
+            peak: 0.0,
+            waveform: WaveformBuilder::new(waveform_bucket_len),
+            diagnostics: DiagnosticsBuilder::new(sample_rate),
+        };
+        chunk.write(prefix);
+        Ok(chunk)
+    }
+
+    fn write(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            if let Err(e) = self.wav_format.write_sample(&mut self.writer, sample) {
+                eprintln!("Error writing audio chunk: {}", e);
+                return;
+            }
+            self.sum_sq += (sample * sample) as f64;
+            self.peak = self.peak.max(sample.abs());
+            self.len += 1;
+        }
+        self.waveform.push(samples);
+        self.diagnostics.push(samples);
+    }
+
+    fn rms(&self) -> f32 {
+        if self.len == 0 {
+            0.0
+        } else {
+            (self.sum_sq / self.len as f64).sqrt() as f32
+        }
+    }
+
+    /// Finish the WAV file, returning its (still partial) path
+    fn finalize(self) -> Result<(PathBuf, Waveform, AudioDiagnostics)> {
+        self.writer.finalize()?;
+        Ok((
+            self.partial,
+            self.waveform.finish(),
+            self.diagnostics.finish(),
+        ))
+    }
+}
+
+/// Continuous recording of a whole session alongside the chunk pipeline.
+///
+/// Samples are streamed to a WAV file as they arrive; compressed formats are
+/// encoded from that (16-bit) WAV once the session ends.
+pub struct SessionRecorder {
+    writer: WavWriter<BufWriter<File>>,
+    wav_format: WavSampleFormat,
+    wav_path: PathBuf,
+    pub final_path: PathBuf,
+    format: AudioFormat,
+    sample_rate: u32,
+}
+
+impl SessionRecorder {
+    pub fn create(
+        dir: &Path,
+        format: AudioFormat,
+        wav_format: WavSampleFormat,
+        sample_rate: u32,
+    ) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let name = format!("session_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"));
+        let wav_path = dir.join(format!("{}.wav", name));
+        let final_path = dir.join(format!("{}.{}", name, format.extension()));
+        let wav_format = match format {
+            AudioFormat::Wav => wav_format,
+            _ => WavSampleFormat::Int16,
+        };
+        let writer = WavWriter::create(&wav_path, wav_format.spec(sample_rate))?;
+
+        Ok(Self {
+            writer,
+            wav_format,
+            wav_path,
+            final_path,
+            format,
+            sample_rate,
+        })
+    }
+
+    pub fn append(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            if let Err(e) = self.wav_format.write_sample(&mut self.writer, sample) {
+                eprintln!("Error writing session recording: {}", e);
+                break;
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<PathBuf> {
+        self.writer.finalize()?;
+
+        match self.format {
+            AudioFormat::Wav => {}
+            AudioFormat::Flac => {
+                let reader = hound::WavReader::open(&self.wav_path)?;
+                let total_samples = reader.len() as u64;
+                encoding::write_flac_stream(
+                    &self.final_path,
+                    total_samples,
+                    self.sample_rate,
+                    reader.into_samples::<i16>(),
+                )?;
+                std::fs::remove_file(&self.wav_path)?;
+            }
+            AudioFormat::Opus => {
+                encoding::encode_opus_file(&self.wav_path, &self.final_path)?;
+                std::fs::remove_file(&self.wav_path)?;
+            }
+        }
+
+        Ok(self.final_path)
+    }
+}
+
+/// A label reduced to characters that are safe in file names, e.g.
+/// "Room mic #2" becomes "room-mic-2"
+fn file_name_tag(label: &str) -> String {
+    label
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// Whether a chunk is too quiet to be worth transcribing
+fn is_silent(samples: &[f32], threshold: Option<f32>) -> bool {
+    threshold.is_some_and(|threshold| vad::rms(samples) < threshold)
+}
+
+/// Convert f32 (-1.0 to 1.0) to i16
+fn to_i16(sample: f32) -> i16 {
+    (sample * i16::MAX as f32) as i16
+}
+
+/// Write samples to a chunk file in the requested format
+fn write_audio_file(
+    path: &Path,
+    samples: &[f32],
+    sample_rate: u32,
+    format: AudioFormat,
+    wav_format: WavSampleFormat,
+) -> Result<()> {
+    match format {
+        AudioFormat::Wav => write_wav_file(path, samples, sample_rate, wav_format),
+        AudioFormat::Flac => {
+            let samples: Vec<i16> = samples.iter().map(|&s| to_i16(s)).collect();
+            encoding::write_flac_file(path, &samples, sample_rate)
+        }
+        AudioFormat::Opus => {
+            // opusenc reads WAV, so stage one next to the output
+            let wav_path = path.with_extension("wav");
+            write_wav_file(&wav_path, samples, sample_rate, WavSampleFormat::Int16)?;
+            let result = encoding::encode_opus_file(&wav_path, path);
+            let _ = std::fs::remove_file(&wav_path);
+            result
+        }
+    }
+}
+
+/// Write samples to a WAV file
+fn write_wav_file(
+    path: &Path,
+    samples: &[f32],
+    sample_rate: u32,
+    wav_format: WavSampleFormat,
+) -> Result<()> {
+    let mut writer = WavWriter::create(path, wav_format.spec(sample_rate))?;
+
+    for &sample in samples {
+        wav_format.write_sample(&mut writer, sample)?;
+    }
+
+    writer.finalize()?;
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::transcription;
+
+/// Capture audio, transcribe it and summarize the conversation
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Profile to use, instead of the one used last
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Record a few seconds from the configured input, report whether real
+    /// audio arrived and exit (status 1 when it didn't)
+    #[arg(long)]
+    pub self_test: bool,
+
+    #[command(flatten)]
+    pub overrides: Overrides,
+}
+
+/// Settings that replace the config's for this run or session; they
+/// aren't saved
+#[derive(Debug, Clone, Default, PartialEq, clap::Args)]
+pub struct Overrides {
+    /// Input device, by its name in the device list
+    #[arg(long, value_name = "NAME")]
+    pub device: Option<String>,
+
+    /// Audio host to capture from, e.g. ALSA, JACK or ASIO
+    #[arg(long, value_name = "NAME")]
+    pub host: Option<String>,
+
+    /// Length of each audio chunk in seconds
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub chunk_secs: Option<u64>,
+
+    /// Transcribe chunks as soon as they're recorded
+    #[arg(long, conflicts_with = "no_realtime")]
+    pub realtime: bool,
+
+    /// Transcribe chunks in batches rather than as they're recorded
+    #[arg(long)]
+    pub no_realtime: bool,
+
+    /// Folder for the audio chunks, transcriptions, summaries and other data,
+    /// each in its own subfolder
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Language to transcribe as, an ISO-639-1 code such as "de"; "auto"
+    /// detects it
+    #[arg(long, value_name = "CODE")]
+    pub language: Option<String>,
+
+    /// Keep the audio chunks after they're transcribed
+    #[arg(long)]
+    pub keep_audio: bool,
+
+    /// Analysis preset for summaries, by id; "none" uses the summary mode
+    #[arg(long = "preset", value_name = "ID")]
+    pub analysis_preset: Option<String>,
+}
+
+impl Overrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// What is overridden, e.g. "10 s chunks"
+    pub fn describe(&self) -> Vec<String> {
+        let mut changes = Vec::new();
+        if let Some(device) = &self.device {
+            changes.push(format!("device {:?}", device));
+        }
+        if let Some(host) = &self.host {
+            changes.push(format!("host {}", host));
+        }
+        if let Some(secs) = self.chunk_secs {
+            changes.push(format!("{} s chunks", secs));
+        }
+        if self.realtime {
+            changes.push("real-time processing".to_string());
+        }
+        if self.no_realtime {
+            changes.push("batch processing".to_string());
+        }
+        if let Some(dir) = &self.output_dir {
+            changes.push(format!("data in {}", dir.display()));
+        }
+        match self.transcription_language().as_deref() {
+            Some("") => changes.push("auto-detected language".to_string()),
+            Some(code) => changes.push(format!(
+                "language {}",
+                transcription::language_name(code).unwrap_or(code)
+            )),
+            None => {}
+        }
+        if self.keep_audio {
+            changes.push("audio kept".to_string());
+        }
+        match self.analysis_preset().as_deref() {
+            Some("") => changes.push("no analysis preset".to_string()),
+            Some(id) => changes.push(format!("preset {}", id)),
+            None => {}
+        }
+        changes
+    }
+
+    /// Put the overridden settings into `config`
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(device) = &self.device {
+            config.input_device = device.clone();
+        }
+        if let Some(host) = &self.host {
+            config.audio_host = host.clone();
+        }
+        if let Some(secs) = self.chunk_secs {
+            config.chunk_duration_secs = secs;
+        }
+        if let Some(realtime) = self.realtime_processing() {
+            config.realtime_processing = realtime;
+        }
+        if let Some(dir) = &self.output_dir {
+            config.use_data_folder(dir);
+        }
+        if let Some(language) = self.transcription_language() {
+            config.transcription_language = language;
+        }
+        if self.keep_audio {
+            config.keep_audio_files = true;
+        }
+        if let Some(preset) = self.analysis_preset() {
+            config.analysis_preset = preset;
+        }
+    }
+
+    /// Put `file`'s values back where `config` still has the overridden
+    /// ones, so saving doesn't write them; settings changed since are kept
+    pub fn restore(&self, config: &mut Config, file: &Config) {
+        let mut overridden = config.clone();
+        self.apply(&mut overridden);
+        if self.device.is_some() && config.input_device == overridden.input_device {
+            config.input_device = file.input_device.clone();
+        }
+        if self.host.is_some() && config.audio_host == overridden.audio_host {
+            config.audio_host = file.audio_host.clone();
+        }
+        if self.chunk_secs.is_some() && config.chunk_duration_secs == overridden.chunk_duration_secs
+        {
+            config.chunk_duration_secs = file.chunk_duration_secs;
+        }
+        if self.realtime_processing() == Some(config.realtime_processing) {
+            config.realtime_processing = file.realtime_processing;
+        }
+        if let Some(dir) = &self.output_dir
+            && config.data_folder().as_deref() == Some(dir.as_path())
+        {
+            config.transcription_cache_dir = file.transcription_cache_dir.clone();
+            config.audio_chunks_dir = file.audio_chunks_dir.clone();
+            config.transcriptions_dir = file.transcriptions_dir.clone();
+            config.summaries_dir = file.summaries_dir.clone();
+            config.recordings_dir = file.recordings_dir.clone();
+            config.embeddings_dir = file.embeddings_dir.clone();
+        }
+        if self.language.is_some()
+            && config.transcription_language == overridden.transcription_language
+        {
+            config.transcription_language = file.transcription_language.clone();
+        }
+        if self.keep_audio && config.keep_audio_files {
+            config.keep_audio_files = file.keep_audio_files;
+        }
+        if self.analysis_preset.is_some() && config.analysis_preset == overridden.analysis_preset {
+            config.analysis_preset = file.analysis_preset.clone();
+        }
+    }
+
+    fn realtime_processing(&self) -> Option<bool> {
+        match (self.realtime, self.no_realtime) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+
+    fn analysis_preset(&self) -> Option<String> {
+        let preset = self.analysis_preset.as_deref()?.trim();
+        Some(if preset.eq_ignore_ascii_case("none") {
+            String::new()
+        } else {
+            preset.to_string()
+        })
+    }
+
+    fn transcription_language(&self) -> Option<String> {
+        let language = self.language.as_deref()?.trim().to_lowercase();
+        Some(if language == "auto" {
+            String::new()
+        } else {
+            language
+        })
+    }
+}
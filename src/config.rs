@@ -1,13 +1,209 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::assemblyai::{self, AssemblyAi};
+use crate::cli::Overrides;
+use crate::corrections::{CorrectingProvider, Correction, Corrector};
+use crate::cost::Pricing;
+use crate::embeddings::{self, EmbeddingBackend};
+use crate::encoding::{AudioFormat, WavSampleFormat};
+use crate::google_speech::{self, GoogleSpeech};
+use crate::http::HttpSettings;
+use crate::local_whisper::{LocalWhisper, WhisperModel};
+use crate::openai_api::{self, ApiEndpoint, AuthHeader};
+use crate::pii::PiiScrubber;
+use crate::presets::{self, AnalysisPreset};
+use crate::punctuation::{PunctuatingProvider, PunctuationMode};
+use crate::rate_limit::RateLimiter;
+use crate::realtime::RealtimeSettings;
+use crate::redaction::{RedactingProvider, Redactor};
+use crate::retention::RetentionPolicy;
+use crate::retry::RetryPolicy;
+use crate::secrets;
+use crate::summarization::{self, Summarizer, SummaryMode};
+use crate::transcription::{
+    self, OpenAiWhisper, TranscriptionBackend, TranscriptionContext, TranscriptionOverrides,
+    TranscriptionProvider,
+};
+use crate::transcription_cache::{CachedProvider, TranscriptionCache};
+use crate::upload_split::{self, SplittingProvider};
+use crate::vad::VadSettings;
+use crate::vosk::Vosk;
+use crate::whisper_server::{self, WhisperServer};
+
+// FIELD_DOCS: the doc comments of `Config`'s fields, by field, collected
+// by build.rs for the comments in config.toml
+include!(concat!(env!("OUT_DIR"), "/config_docs.rs"));
+
+/// The profile kept in config.toml; the others are in profiles/<name>.toml
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Put at the top of config.toml
+const TOML_HEADER: &str = "# Audio Assistant configuration. Settings left out take their default.\n\
+# Comments are written by the app; it rewrites this file when settings are saved.\n";
+
+/// Holds the name of the profile used last
+const LAST_PROFILE_FILE: &str = "profile";
+
+/// Subfolders of the data folder, one per data directory
+const DATA_SUBDIRS: [&str; 6] = [
+    "transcription_cache",
+    "audio_chunks",
+    "transcriptions",
+    "summaries",
+    "recordings",
+    "embeddings",
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    /// Profile this config belongs to, from its file name
+    #[serde(skip)]
+    pub profile: String,
+
     /// OpenAI API key for Whisper and GPT
     pub openai_api_key: String,
 
+    /// Keep the API keys and the Whisper server token in the OS keyring
+    /// (Keychain, Windows Credential Manager or the Secret Service) rather
+    /// than in this file; keys found here are moved there on start
+    pub use_keyring: bool,
+
+    /// Why the keys couldn't go to the keyring and were saved in this file
+    /// instead, when that happened
+    #[serde(skip)]
+    pub keyring_error: Option<String>,
+
+    /// `use_keyring` as last saved, so turning it off knows the keyring
+    /// has entries to delete
+    #[serde(skip)]
+    pub keyring_saved: bool,
+
+    /// Keys set in the environment, by entry name, with the saved values
+    /// they stand in for
+    #[serde(skip)]
+    pub env_keys: Vec<(&'static str, String)>,
+
+    /// Settings given on the command line or as session options, which
+    /// aren't saved, with the config as it was before them
+    #[serde(skip)]
+    pub overrides: Option<(Overrides, Box<Config>)>,
+
+    /// Base URL for transcription requests: OpenAI, an Azure OpenAI Whisper
+    /// deployment or an OpenAI-compatible proxy such as LiteLLM
+    pub transcription_base_url: String,
+
+    /// Base URL for summarization (chat completion) requests
+    pub chat_base_url: String,
+
+    /// `api-version` query parameter sent with every request; Azure OpenAI
+    /// requires it (empty to omit)
+    pub api_version: String,
+
+    /// How the API key is sent: "bearer" or "api-key" (Azure OpenAI)
+    pub api_auth_header: AuthHeader,
+
+    /// Transcribe through the OpenAI API, locally with whisper.cpp or
+    /// through AssemblyAI
+    pub transcription_backend: TranscriptionBackend,
+
+    /// Model for the OpenAI backend: "whisper-1", "gpt-4o-transcribe" or
+    /// "gpt-4o-mini-transcribe" (ignored by Azure, which uses the deployment)
+    pub transcription_model: String,
+
+    /// ISO-639-1 code of the language to transcribe as (e.g. "en"); empty
+    /// lets the engine auto-detect it for every chunk
+    pub transcription_language: String,
+
+    /// Translate speech in any language into English transcripts (Whisper
+    /// backends only)
+    pub translate_to_english: bool,
+
+    /// Names, acronyms and terms the engine should expect, e.g. colleagues'
+    /// names or product names
+    pub transcription_vocabulary: Vec<String>,
+
+    /// Prime each chunk's transcription with the end of the previous
+    /// segment so sentences and spellings carry across chunk boundaries
+    pub prompt_with_previous_text: bool,
+
+    /// Stream audio to the OpenAI Realtime API and show text while it is
+    /// spoken, instead of transcribing a chunk at a time (OpenAI backend
+    /// only)
+    pub stream_transcription: bool,
+
+    /// Reuse the transcript of audio that was already transcribed with the
+    /// same settings instead of sending it again
+    pub transcription_cache_enabled: bool,
+
+    /// Directory cached transcripts are kept in, named by audio hash
+    pub transcription_cache_dir: PathBuf,
+
+    /// Restore punctuation and capitalization in transcripts that have
+    /// none: "off", "rules" or "llm" (a chat model)
+    pub punctuation_restoration: PunctuationMode,
+
+    /// Chat model that punctuates in "llm" mode
+    pub punctuation_model: String,
+
+    /// Fixes for terms the engine keeps getting wrong ("acme corp" →
+    /// "AcmeCorp"), applied to every transcript
+    pub term_corrections: Vec<Correction>,
+
+    /// Mask common swear words in transcripts ("f***")
+    pub profanity_filter: bool,
+
+    /// Case-insensitive regular expressions for sensitive words (client
+    /// names, account numbers) replaced with "[REDACTED]" in transcripts
+    pub redaction_patterns: Vec<String>,
+
+    /// Replace emails, phone numbers and card numbers with placeholders in
+    /// text sent for summarization, exported or copied; the transcript on
+    /// screen keeps them, marked
+    pub pii_scrubbing: bool,
+
+    /// Extra case-insensitive regular expressions scrubbed as "[PII]"
+    pub pii_patterns: Vec<String>,
+
+    /// AssemblyAI API key, for the AssemblyAI backend
+    pub assemblyai_api_key: String,
+
+    /// Google Cloud API key with Speech-to-Text enabled, for the Google
+    /// backend
+    pub google_api_key: String,
+
+    /// whisper.cpp model used by the local backend
+    pub whisper_model: WhisperModel,
+
+    /// Directory downloaded whisper.cpp models are kept in
+    pub whisper_models_dir: PathBuf,
+
+    /// whisper.cpp command-line program (`whisper-cli`, or `main` in older builds)
+    pub whisper_cli_path: String,
+
+    /// Unpacked Vosk model directory; empty lets Vosk fetch the small model
+    /// for the transcription language (English when auto-detecting)
+    pub vosk_model_dir: PathBuf,
+
+    /// Vosk command-line program, installed with `pip install vosk`
+    pub vosk_cli_path: String,
+
+    /// Base URL of the self-hosted Whisper server's OpenAI-compatible API,
+    /// e.g. "http://gpu-box:8000/v1"
+    pub whisper_server_url: String,
+
+    /// Sent as a bearer token to the Whisper server; empty for none
+    pub whisper_server_token: String,
+
+    /// Model the Whisper server should transcribe with, as it names them
+    pub whisper_server_model: String,
+
     /// Duration of each audio chunk in seconds
     pub chunk_duration_secs: u64,
 
@@ -23,73 +219,827 @@ pub struct Config {
     /// Directory to store summaries
     pub summaries_dir: PathBuf,
 
+    /// Directory to store full-session recordings
+    pub recordings_dir: PathBuf,
+
+    /// Directory to store segment embeddings for semantic search
+    pub embeddings_dir: PathBuf,
+
     /// Whether to keep audio files after transcription
     pub keep_audio_files: bool,
 
     /// Process in real-time or batch mode
     pub realtime_processing: bool,
 
+    /// In batch mode, summarize every this many minutes while listening
+    /// (0 = off)
+    pub summary_interval_mins: u64,
+
+    /// In batch mode, summarize once this many segments were added since
+    /// the last summary (0 = off)
+    pub summary_interval_segments: usize,
+
     /// OpenAI model for summarization
     pub summarization_model: String,
+
+    /// Plain summaries, or meeting minutes with attendees, agenda,
+    /// decisions, risks and owned action items
+    pub summary_mode: SummaryMode,
+
+    /// Id of the analysis preset (standup, interview, lecture, 1:1 or one
+    /// of `analysis_presets`) whose sections summaries fill in instead of
+    /// the summary mode's; empty for none
+    pub analysis_preset: String,
+
+    /// Presets of your own, added to the built-in ones or replacing the
+    /// one with the same id
+    pub analysis_presets: Vec<AnalysisPreset>,
+
+    /// Tokens the summarization model reads at once; transcripts longer
+    /// than that are summarized in parts. 0 goes by the model's name.
+    pub summary_context_tokens: usize,
+
+    /// Sampling temperature of summaries, from 0 (same wording every time)
+    /// to 2
+    pub summary_temperature: f32,
+
+    /// Longest summary answer in tokens; 0 leaves it to the model
+    pub summary_max_tokens: u32,
+
+    /// Summarize with the built-in extractive summarizer when there is no
+    /// API key or the API can't be reached
+    pub extractive_fallback: bool,
+
+    /// Update the last summary with the segments added since, rather than
+    /// sending the whole transcript for every summary
+    pub incremental_summaries: bool,
+
+    /// Rate each new segment's sentiment and tension with the chat model,
+    /// for the sentiment timeline
+    pub sentiment_analysis: bool,
+
+    /// List the people, organizations, products and dates each new segment
+    /// mentions with the chat model, for the entity index
+    pub entity_extraction: bool,
+
+    /// Title and tag each session with the chat model after its first few
+    /// segments, for summaries, file names and the session list
+    pub session_titles: bool,
+
+    /// Explain the session's acronyms and domain terms with the chat model
+    /// after each summary, for a glossary saved with it
+    pub glossary: bool,
+
+    /// Embed each new segment for semantic search across sessions
+    pub semantic_search: bool,
+
+    /// Embed segments through the OpenAI API or a local server
+    pub embedding_backend: EmbeddingBackend,
+
+    /// OpenAI embedding model
+    pub embedding_model: String,
+
+    /// Base URL of the local embeddings server's OpenAI-compatible API,
+    /// e.g. Ollama's "http://localhost:11434/v1"
+    pub local_embedding_url: String,
+
+    /// Model the local server embeds with, as it names them
+    pub local_embedding_model: String,
+
+    /// Chunks transcribed at once; later chunks wait their turn
+    pub max_concurrent_transcriptions: usize,
+
+    /// Tries per API request before a chunk or summary fails; rate limits,
+    /// server errors and timeouts are retried (1 disables retries)
+    pub api_max_attempts: u32,
+
+    /// Wait before the first retry in seconds; doubles for each one after
+    pub api_retry_base_delay_secs: f32,
+
+    /// Longest wait between retries in seconds, even if the server asks for more
+    pub api_retry_max_delay_secs: f32,
+
+    /// Longest a single API request may take in seconds, upload included,
+    /// before it is abandoned and retried (0 waits forever)
+    pub api_timeout_secs: f32,
+
+    /// Proxy for all HTTP requests, e.g. "http://proxy.corp:3128" (with
+    /// "user:pass@" before the host if it needs a login); empty uses the
+    /// HTTPS_PROXY and HTTP_PROXY environment variables
+    pub http_proxy: String,
+
+    /// Comma-separated hosts, domains and IP ranges reached without the
+    /// proxy, e.g. "localhost,.corp.example.com,10.0.0.0/8"
+    pub no_proxy: String,
+
+    /// PEM files of root certificates to trust besides the system's, such
+    /// as the CA of a TLS-inspecting proxy
+    pub ca_certificates: Vec<PathBuf>,
+
+    /// List prices for the running cost estimate
+    pub pricing: Pricing,
+
+    /// cpal audio host to capture from, e.g. "ALSA", "JACK" or "ASIO" (empty for the platform default)
+    pub audio_host: String,
+
+    /// Input device name on the audio host (empty for the host's default input)
+    pub input_device: String,
+
+    /// Record system output (WASAPI loopback) instead of the default input
+    pub capture_system_audio: bool,
+
+    /// Record from another input when the selected one only delivers
+    /// telephone-quality audio (a Bluetooth headset in hands-free mode)
+    pub avoid_narrowband_input: bool,
+
+    /// Record only this application's playback instead of an input device,
+    /// e.g. "Firefox" (empty to use the input device)
+    pub capture_app: String,
+
+    /// Also record a microphone and mix it into the main capture
+    pub mix_microphone: bool,
+
+    /// Microphone to mix in (empty for the host's default input)
+    pub microphone_device: String,
+
+    /// Remove speaker audio picked up by the mixed-in microphone
+    pub echo_cancellation: bool,
+
+    /// Play captured audio back on an output device while capturing
+    pub monitor_enabled: bool,
+
+    /// Output device for monitoring (empty for the host's default output)
+    pub monitor_output_device: String,
+
+    /// Monitor volume, from 0.0 to 1.0
+    pub monitor_volume: f32,
+
+    /// Transcribe the left and right channels separately, e.g. a call
+    /// recording with the local side on one channel and the remote on the other
+    pub split_stereo_channels: bool,
+
+    /// Speaker label for the left channel when splitting channels
+    pub left_channel_label: String,
+
+    /// Speaker label for the right channel when splitting channels
+    pub right_channel_label: String,
+
+    /// More input devices recorded at the same time as the main capture,
+    /// each chunked and transcribed as its own source (e.g. two microphones
+    /// in a room)
+    pub extra_sources: Vec<CaptureSource>,
+
+    /// Source label of the main capture when extra sources are recorded
+    pub main_source_label: String,
+
+    /// End chunks at pauses in speech instead of after a fixed duration
+    pub vad_enabled: bool,
+
+    /// Shortest chunk VAD may produce, in seconds
+    pub vad_min_chunk_secs: u64,
+
+    /// Longest chunk VAD may produce before forcing a cut, in seconds
+    pub vad_max_chunk_secs: u64,
+
+    /// Pause length that ends a chunk, in milliseconds
+    pub vad_silence_ms: u64,
+
+    /// RMS level (0.0-1.0) below which audio counts as silence
+    pub vad_energy_threshold: f32,
+
+    /// Discard silent chunks instead of sending them for transcription
+    pub skip_silent_chunks: bool,
+
+    /// RMS level (0.0-1.0) below which a whole chunk counts as silent
+    pub silence_threshold: f32,
+
+    /// Keep chunks classified as music, noise or silence in the transcript,
+    /// exports and summaries instead of setting them aside
+    pub include_non_speech: bool,
+
+    /// Denoise chunks before they are written and transcribed
+    pub noise_suppression: bool,
+
+    /// Noise suppression strength, from 0.0 (off) to 1.0 (maximum)
+    pub noise_suppression_strength: f32,
+
+    /// Encoding for audio chunks (wav, flac or opus)
+    pub audio_format: AudioFormat,
+
+    /// Sample encoding for WAV output (16-bit, 24-bit or 32-bit float)
+    pub wav_format: WavSampleFormat,
+
+    /// Seconds of audio repeated at the start of each chunk (0 disables)
+    pub chunk_overlap_secs: f32,
+
+    /// Drop words at the start of a segment that repeat the end of the
+    /// previous one, tolerating small differences in wording. Catches
+    /// repeats from chunk overlap, retried chunks and Whisper echoing its
+    /// prompt.
+    pub remove_repeated_text: bool,
+
+    /// Seconds of audio kept in memory while idle and prepended to the next
+    /// recording, so a late start doesn't lose the beginning (0 disables)
+    pub pre_roll_secs: f32,
+
+    /// Largest chunk file size in megabytes; chunks end early when they would
+    /// exceed it (0 disables). Whisper rejects uploads over 25 MB; bigger
+    /// files are split before they are sent.
+    pub max_chunk_size_mb: f32,
+
+    /// Record each session to one continuous file alongside the chunks
+    pub record_full_session: bool,
+
+    /// Encoding for full-session recordings (wav, flac or opus)
+    pub session_recording_format: AudioFormat,
+
+    /// Delete old files from the audio chunk, transcription and summary
+    /// directories to stay within the limits below
+    pub retention_enabled: bool,
+
+    /// Most disk space audio chunks may use, in megabytes (0 for no limit)
+    pub audio_chunks_quota_mb: f32,
+
+    /// Most disk space transcriptions and summaries may each use, in
+    /// megabytes (0 for no limit)
+    pub transcripts_quota_mb: f32,
+
+    /// Delete files older than this many days (0 keeps them regardless of age)
+    pub retention_days: u32,
+}
+
+/// A setting that is wrong, shown next to it in the settings window
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    /// The config field, e.g. "vad_min_chunk_secs"
+    pub field: &'static str,
+    pub message: String,
+    /// Whether the config can't be saved with it; a key or model that
+    /// isn't there yet doesn't stop saving, only listening
+    pub blocks_save: bool,
+}
+
+impl FieldError {
+    fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+            blocks_save: true,
+        }
+    }
+
+    fn unset(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            blocks_save: false,
+            ..Self::new(field, message)
+        }
+    }
+}
+
+fn first_error(errors: Vec<FieldError>) -> Result<()> {
+    match errors.into_iter().next() {
+        Some(error) => Err(anyhow::anyhow!(error.message)),
+        None => Ok(()),
+    }
+}
+
+/// An extra input device recorded as its own source
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureSource {
+    /// Shown with the source's transcriptions and added to its chunk names
+    pub label: String,
+    /// Input device on the configured audio host
+    pub device: String,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        let base_dir = dirs::data_local_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("audio-assistant");
+        let base_dir = data_dir(DEFAULT_PROFILE);
 
         Self {
+            profile: DEFAULT_PROFILE.to_string(),
             openai_api_key: String::new(),
+            use_keyring: true,
+            keyring_error: None,
+            keyring_saved: false,
+            env_keys: Vec::new(),
+            overrides: None,
+            transcription_base_url: openai_api::DEFAULT_BASE_URL.to_string(),
+            chat_base_url: openai_api::DEFAULT_BASE_URL.to_string(),
+            api_version: String::new(),
+            api_auth_header: AuthHeader::Bearer,
+            transcription_backend: TranscriptionBackend::OpenAi,
+            transcription_model: "whisper-1".to_string(),
+            transcription_language: String::new(),
+            translate_to_english: false,
+            transcription_vocabulary: Vec::new(),
+            prompt_with_previous_text: true,
+            stream_transcription: false,
+            transcription_cache_enabled: true,
+            transcription_cache_dir: base_dir.join("transcription_cache"),
+            punctuation_restoration: PunctuationMode::Off,
+            punctuation_model: "gpt-4o-mini".to_string(),
+            term_corrections: Vec::new(),
+            profanity_filter: false,
+            redaction_patterns: Vec::new(),
+            pii_scrubbing: false,
+            pii_patterns: Vec::new(),
+            assemblyai_api_key: String::new(),
+            google_api_key: String::new(),
+            whisper_model: WhisperModel::Base,
+            whisper_models_dir: base_dir.join("models"),
+            whisper_cli_path: "whisper-cli".to_string(),
+            vosk_model_dir: PathBuf::new(),
+            vosk_cli_path: "vosk-transcriber".to_string(),
+            whisper_server_url: whisper_server::DEFAULT_URL.to_string(),
+            whisper_server_token: String::new(),
+            whisper_server_model: whisper_server::DEFAULT_MODEL.to_string(),
             chunk_duration_secs: 30, // 30 second chunks by default
             sample_rate: 16000,      // 16kHz is good for speech
             audio_chunks_dir: base_dir.join("audio_chunks"),
             transcriptions_dir: base_dir.join("transcriptions"),
             summaries_dir: base_dir.join("summaries"),
+            recordings_dir: base_dir.join("recordings"),
+            embeddings_dir: base_dir.join("embeddings"),
             keep_audio_files: false,
             realtime_processing: true,
+            summary_interval_mins: 0,
+            summary_interval_segments: 0,
             summarization_model: "gpt-4o-mini".to_string(),
+            summary_mode: SummaryMode::Summary,
+            analysis_preset: String::new(),
+            analysis_presets: Vec::new(),
+            summary_context_tokens: 0,
+            summary_temperature: summarization::SUMMARY_TEMPERATURE,
+            summary_max_tokens: 0,
+            extractive_fallback: true,
+            incremental_summaries: true,
+            sentiment_analysis: false,
+            entity_extraction: false,
+            session_titles: false,
+            glossary: false,
+            semantic_search: false,
+            embedding_backend: EmbeddingBackend::OpenAi,
+            embedding_model: embeddings::DEFAULT_MODEL.to_string(),
+            local_embedding_url: embeddings::DEFAULT_LOCAL_URL.to_string(),
+            local_embedding_model: embeddings::DEFAULT_LOCAL_MODEL.to_string(),
+            max_concurrent_transcriptions: 3,
+            api_max_attempts: 4,
+            api_retry_base_delay_secs: 1.0,
+            api_retry_max_delay_secs: 30.0,
+            api_timeout_secs: 120.0,
+            http_proxy: String::new(),
+            no_proxy: String::new(),
+            ca_certificates: Vec::new(),
+            pricing: Pricing::default(),
+            audio_host: String::new(),
+            input_device: String::new(),
+            capture_system_audio: false,
+            avoid_narrowband_input: false,
+            capture_app: String::new(),
+            mix_microphone: false,
+            microphone_device: String::new(),
+            echo_cancellation: true,
+            monitor_enabled: false,
+            monitor_output_device: String::new(),
+            monitor_volume: 0.8,
+            split_stereo_channels: false,
+            left_channel_label: "Me".to_string(),
+            right_channel_label: "Them".to_string(),
+            extra_sources: Vec::new(),
+            main_source_label: "Main".to_string(),
+            vad_enabled: false,
+            vad_min_chunk_secs: 10,
+            vad_max_chunk_secs: 60,
+            vad_silence_ms: 600,
+            vad_energy_threshold: 0.01,
+            skip_silent_chunks: false,
+            silence_threshold: 0.005,
+            include_non_speech: false,
+            noise_suppression: false,
+            noise_suppression_strength: 0.7,
+            audio_format: AudioFormat::Wav,
+            wav_format: WavSampleFormat::Int16,
+            chunk_overlap_secs: 0.0,
+            remove_repeated_text: true,
+            pre_roll_secs: 10.0,
+            max_chunk_size_mb: 25.0,
+            record_full_session: false,
+            session_recording_format: AudioFormat::Flac,
+            retention_enabled: false,
+            audio_chunks_quota_mb: 2048.0,
+            transcripts_quota_mb: 0.0,
+            retention_days: 30,
         }
     }
 }
 
 impl Config {
-    /// Load config from file, or create default if not exists
-    pub fn load() -> Result<Self> {
-        let config_path = Self::config_path();
+    /// Default settings for `profile`, with data directories of its own
+    pub fn for_profile(profile: &str) -> Self {
+        let mut config = Self {
+            profile: profile.to_string(),
+            ..Self::default()
+        };
+        config.use_profile_dirs();
+        config
+    }
 
-        if config_path.exists() {
-            let contents = fs::read_to_string(&config_path)?;
-            let config: Config = serde_json::from_str(&contents)?;
+    /// Load the config of `profile` from file, or create it with default
+    /// settings if it doesn't exist. A JSON config from before TOML is
+    /// converted to TOML, and the JSON file removed.
+    pub fn load(profile: &str) -> Result<Self> {
+        validate_profile_name(profile)?;
+        let config_path = Self::config_path(profile);
+        let json_path = config_path.with_extension("json");
+
+        if config_path.exists() || json_path.exists() {
+            let from_json = !config_path.exists();
+            let mut config: Config = if from_json {
+                serde_json::from_str(&fs::read_to_string(&json_path)?)?
+            } else {
+                toml_edit::de::from_str(&fs::read_to_string(&config_path)?)?
+            };
+            config.profile = profile.to_string();
+            config.keyring_saved = config.use_keyring;
+            if config.use_keyring {
+                config.load_secrets()?;
+            }
+            if from_json {
+                config.save()?;
+                fs::remove_file(&json_path)?;
+                println!(
+                    "Converted {} to {}",
+                    json_path.display(),
+                    config_path.display()
+                );
+            }
+            config.apply_env();
             Ok(config)
         } else {
-            let config = Config::default();
+            let mut config = Config::for_profile(profile);
             config.save()?;
+            config.apply_env();
             Ok(config)
         }
     }
 
-    /// Save config to file
-    pub fn save(&self) -> Result<()> {
-        let config_path = Self::config_path();
+    /// Save config to file, with the keys in the keyring when it's used.
+    /// Should the keyring fail, the keys are written to the file so they
+    /// aren't lost, and `keyring_error` says why. Keys from the environment
+    /// and command-line overrides are never saved; the values they stand in
+    /// for are kept. Turning the keyring off deletes the profile's entries
+    /// once the keys are in the file.
+    pub fn save(&mut self) -> Result<()> {
+        let config_path = Self::config_path(&self.profile);
 
         // Create parent directory if it doesn't exist
         if let Some(parent) = config_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let contents = serde_json::to_string_pretty(self)?;
-        fs::write(&config_path, contents)?;
+        let mut on_disk = self.clone();
+        for (name, secret) in on_disk.secrets_mut() {
+            if let Some((_, saved)) = self.env_keys.iter().find(|(n, _)| *n == name) {
+                *secret = saved.clone();
+            }
+        }
+        if let Some((overrides, file)) = &self.overrides {
+            overrides.restore(&mut on_disk, file);
+        }
+        // After a failure the keys may not have been read, so blank ones
+        // don't mean they were cleared
+        let delete_blank = self.keyring_error.take().is_none();
+        let leaving_keyring = self.keyring_saved && !self.use_keyring && delete_blank;
+        if self.use_keyring {
+            match on_disk.store_secrets(delete_blank) {
+                Ok(()) => {
+                    for (_, secret) in on_disk.secrets_mut() {
+                        secret.clear();
+                    }
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Couldn't store the API keys in the system keyring, saving them in {}: {}",
+                        config_path.display(),
+                        e
+                    );
+                    self.keyring_error = Some(e.to_string());
+                }
+            }
+        }
+
+        fs::write(&config_path, on_disk.to_toml()?)?;
+        self.keyring_saved = self.use_keyring;
+
+        if leaving_keyring {
+            let prefix = self.keyring_prefix();
+            for (name, _) in self.secrets_mut() {
+                if let Err(e) = secrets::store(&format!("{}{}", prefix, name), "") {
+                    eprintln!("Couldn't delete {} from the system keyring: {}", name, e);
+                }
+            }
+        }
 
         Ok(())
     }
 
-    /// Get the config file path
-    fn config_path() -> PathBuf {
-        dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("audio-assistant")
-            .join("config.json")
+    /// The config as TOML, each setting after its doc comment
+    fn to_toml(&self) -> Result<String> {
+        let mut document = toml_edit::ser::to_document(self)?;
+        for (_, item) in document.iter_mut() {
+            expand_toml(item, 0);
+        }
+        for (mut key, item) in document.iter_mut() {
+            let Some((_, doc)) = FIELD_DOCS.iter().find(|(name, _)| *name == key.get()) else {
+                continue;
+            };
+            let comment: String = doc
+                .lines()
+                .map(|line| format!("# {}\n", line).replace("# \n", "#\n"))
+                .collect();
+            match item {
+                toml_edit::Item::Table(table) => {
+                    table.decor_mut().set_prefix(format!("\n{}", comment))
+                }
+                toml_edit::Item::ArrayOfTables(tables) => {
+                    if let Some(table) = tables.get_mut(0) {
+                        table.decor_mut().set_prefix(format!("\n{}", comment));
+                    }
+                }
+                _ => key.leaf_decor_mut().set_prefix(format!("\n{}", comment)),
+            }
+        }
+        Ok(format!("{}{}", TOML_HEADER, document))
+    }
+
+    /// The keys kept in the keyring, by entry name
+    fn secrets_mut(&mut self) -> [(&'static str, &mut String); 4] {
+        [
+            ("openai_api_key", &mut self.openai_api_key),
+            ("assemblyai_api_key", &mut self.assemblyai_api_key),
+            ("google_api_key", &mut self.google_api_key),
+            ("whisper_server_token", &mut self.whisper_server_token),
+        ]
+    }
+
+    /// A new profile `name` with this config's settings, but without its
+    /// keys and with data directories of its own, saved
+    pub fn new_profile(&self, name: &str) -> Result<Config> {
+        validate_profile_name(name)?;
+        if profiles().iter().any(|p| p == name) {
+            anyhow::bail!("A profile named '{}' already exists", name);
+        }
+        let mut config = self.clone();
+        config.profile = name.to_string();
+        config.keyring_error = None;
+        config.keyring_saved = false;
+        config.env_keys.clear();
+        config.overrides = None;
+        for (_, secret) in config.secrets_mut() {
+            secret.clear();
+        }
+        config.use_profile_dirs();
+        config.save()?;
+        config.apply_env();
+        Ok(config)
+    }
+
+    /// Point the data directories at the profile's own, so recordings,
+    /// transcripts and summaries of different profiles never mix. Whisper
+    /// models stay shared.
+    fn use_profile_dirs(&mut self) {
+        self.use_data_folder(&data_dir(&self.profile));
+    }
+
+    /// The folders kept in the data folder, in the order of `DATA_SUBDIRS`
+    fn data_dirs(&self) -> [&PathBuf; 6] {
+        [
+            &self.transcription_cache_dir,
+            &self.audio_chunks_dir,
+            &self.transcriptions_dir,
+            &self.summaries_dir,
+            &self.recordings_dir,
+            &self.embeddings_dir,
+        ]
+    }
+
+    /// Point the data directories at their subfolders of `base`
+    pub fn use_data_folder(&mut self, base: &Path) {
+        let dirs = [
+            &mut self.transcription_cache_dir,
+            &mut self.audio_chunks_dir,
+            &mut self.transcriptions_dir,
+            &mut self.summaries_dir,
+            &mut self.recordings_dir,
+            &mut self.embeddings_dir,
+        ];
+        for (dir, name) in dirs.into_iter().zip(DATA_SUBDIRS) {
+            *dir = base.join(name);
+        }
+    }
+
+    /// The folder holding all the data directories, or None when they
+    /// were set one by one
+    pub fn data_folder(&self) -> Option<PathBuf> {
+        let base = self.audio_chunks_dir.parent()?;
+        self.data_dirs()
+            .into_iter()
+            .zip(DATA_SUBDIRS)
+            .all(|(dir, name)| *dir == base.join(name))
+            .then(|| base.to_path_buf())
+    }
+
+    /// Where each data directory goes when the data folder becomes `base`,
+    /// for the ones that move
+    pub fn data_folder_moves(&self, base: &Path) -> Vec<(PathBuf, PathBuf)> {
+        self.data_dirs()
+            .into_iter()
+            .zip(DATA_SUBDIRS)
+            .map(|(dir, name)| (dir.clone(), base.join(name)))
+            .filter(|(from, to)| from != to)
+            .collect()
+    }
+
+    /// Capture settings that differ in `other`, e.g. "sample rate"; a
+    /// running capture keeps the ones it started with
+    pub fn capture_changes(&self, other: &Config) -> Vec<&'static str> {
+        let changes = [
+            ("sample rate", self.sample_rate != other.sample_rate),
+            (
+                "input device",
+                self.audio_host != other.audio_host
+                    || self.input_device != other.input_device
+                    || self.capture_system_audio != other.capture_system_audio
+                    || self.capture_app != other.capture_app
+                    || self.avoid_narrowband_input != other.avoid_narrowband_input,
+            ),
+            (
+                "microphone",
+                self.mix_microphone != other.mix_microphone
+                    || self.microphone_device != other.microphone_device
+                    || self.echo_cancellation != other.echo_cancellation,
+            ),
+            (
+                "monitoring",
+                self.monitor_enabled != other.monitor_enabled
+                    || self.monitor_output_device != other.monitor_output_device
+                    || self.monitor_volume != other.monitor_volume,
+            ),
+            (
+                "sources",
+                self.split_stereo_channels != other.split_stereo_channels
+                    || self.left_channel_label != other.left_channel_label
+                    || self.right_channel_label != other.right_channel_label
+                    || self.main_source_label != other.main_source_label
+                    || self.extra_sources != other.extra_sources,
+            ),
+            (
+                "chunking",
+                self.chunk_duration_secs != other.chunk_duration_secs
+                    || self.chunk_overlap_secs != other.chunk_overlap_secs
+                    || self.max_chunk_size_mb != other.max_chunk_size_mb
+                    || self.audio_chunks_dir != other.audio_chunks_dir
+                    || self.vad_enabled != other.vad_enabled
+                    || self.vad_min_chunk_secs != other.vad_min_chunk_secs
+                    || self.vad_max_chunk_secs != other.vad_max_chunk_secs
+                    || self.vad_silence_ms != other.vad_silence_ms
+                    || self.vad_energy_threshold != other.vad_energy_threshold,
+            ),
+            (
+                "noise and silence",
+                self.noise_suppression != other.noise_suppression
+                    || self.noise_suppression_strength != other.noise_suppression_strength
+                    || self.skip_silent_chunks != other.skip_silent_chunks
+                    || self.silence_threshold != other.silence_threshold,
+            ),
+            (
+                "chunk format",
+                self.audio_format != other.audio_format || self.wav_format != other.wav_format,
+            ),
+            (
+                "session recording",
+                self.record_full_session != other.record_full_session
+                    || self.session_recording_format != other.session_recording_format
+                    || self.recordings_dir != other.recordings_dir,
+            ),
+            (
+                "streaming",
+                self.stream_transcription != other.stream_transcription,
+            ),
+        ];
+        changes
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(what, _)| what)
+            .collect()
+    }
+
+    /// Use `overrides` without saving them, in place of any before
+    pub fn apply_overrides(&mut self, overrides: Overrides) {
+        if let Some((previous, file)) = self.overrides.take() {
+            previous.restore(self, &file);
+        }
+        if overrides.is_empty() {
+            return;
+        }
+        let file = Box::new(self.clone());
+        overrides.apply(self);
+        self.overrides = Some((overrides, file));
+    }
+
+    /// Whether the key stored as `name` was set in the environment
+    pub fn key_from_env(&self, name: &str) -> bool {
+        self.env_keys.iter().any(|(n, _)| *n == name)
+    }
+
+    /// Use the keys set as environment variables named after them in
+    /// uppercase (`OPENAI_API_KEY`, `ASSEMBLYAI_API_KEY`, `GOOGLE_API_KEY`,
+    /// `WHISPER_SERVER_TOKEN`), or in a `.env` file in the working directory
+    /// or above, over the saved ones. The process environment wins over the
+    /// file.
+    fn apply_env(&mut self) {
+        let dotenv: HashMap<String, String> = dotenvy::dotenv_iter()
+            .map(|vars| vars.flatten().collect())
+            .unwrap_or_default();
+        let mut env_keys = Vec::new();
+        for (name, secret) in self.secrets_mut() {
+            let var = name.to_uppercase();
+            let value = std::env::var(&var)
+                .ok()
+                .or_else(|| dotenv.get(&var).cloned())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty());
+            if let Some(value) = value {
+                println!("Using {} from the environment", var);
+                env_keys.push((name, std::mem::replace(secret, value)));
+            }
+        }
+        self.env_keys = env_keys;
+    }
+
+    /// Fill in the keys from the keyring. Keys still in the file are moved
+    /// to the keyring and blanked there; if the keyring can't be reached
+    /// they stay where they are.
+    fn load_secrets(&mut self) -> Result<()> {
+        let mut in_file = false;
+        let mut error = None;
+        let prefix = self.keyring_prefix();
+        for (name, secret) in self.secrets_mut() {
+            if !secret.is_empty() {
+                in_file = true;
+                continue;
+            }
+            match secrets::load(&format!("{}{}", prefix, name)) {
+                Ok(stored) => *secret = stored.unwrap_or_default(),
+                Err(e) => {
+                    eprintln!("Couldn't read {} from the system keyring: {}", name, e);
+                    error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+        if error.is_some() {
+            self.keyring_error = error;
+        } else if in_file {
+            self.save()?;
+            if self.keyring_error.is_none() {
+                println!("Moved the API keys from the config file to the system keyring");
+            }
+        }
+        Ok(())
+    }
+
+    fn store_secrets(&mut self, delete_blank: bool) -> Result<()> {
+        let prefix = self.keyring_prefix();
+        for (name, secret) in self.secrets_mut() {
+            if delete_blank || !secret.is_empty() {
+                secrets::store(&format!("{}{}", prefix, name), secret)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Put before the keyring entry names of other profiles than the
+    /// default one: "client-a/openai_api_key"
+    fn keyring_prefix(&self) -> String {
+        if self.profile == DEFAULT_PROFILE {
+            String::new()
+        } else {
+            format!("{}/", self.profile)
+        }
+    }
+
+    /// Get the config file path of `profile`
+    pub fn config_path(profile: &str) -> PathBuf {
+        if profile == DEFAULT_PROFILE {
+            config_dir().join("config.toml")
+        } else {
+            config_dir()
+                .join("profiles")
+                .join(format!("{}.toml", profile))
+        }
     }
 
     /// Ensure all required directories exist
@@ -97,14 +1047,672 @@ impl Config {
         fs::create_dir_all(&self.audio_chunks_dir)?;
         fs::create_dir_all(&self.transcriptions_dir)?;
         fs::create_dir_all(&self.summaries_dir)?;
+        fs::create_dir_all(&self.embeddings_dir)?;
+        if self.record_full_session {
+            fs::create_dir_all(&self.recordings_dir)?;
+        }
         Ok(())
     }
 
     /// Validate that the config is ready to use
     pub fn validate(&self) -> Result<()> {
-        if self.openai_api_key.is_empty() {
-            anyhow::bail!("OpenAI API key is not set");
+        first_error(self.field_errors())
+    }
+
+    /// Everything wrong with the config, by field, for the settings window
+    pub fn field_errors(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        match self.transcription_backend {
+            TranscriptionBackend::OpenAi if self.openai_api_key.is_empty() => {
+                errors.push(FieldError::unset(
+                    "openai_api_key",
+                    "OpenAI API key is not set",
+                ));
+            }
+            TranscriptionBackend::AssemblyAi if self.assemblyai_api_key.is_empty() => {
+                errors.push(FieldError::unset(
+                    "assemblyai_api_key",
+                    "AssemblyAI API key is not set",
+                ));
+            }
+            TranscriptionBackend::Google if self.google_api_key.is_empty() => {
+                errors.push(FieldError::unset(
+                    "google_api_key",
+                    "Google API key is not set",
+                ));
+            }
+            TranscriptionBackend::WhisperServer => {
+                if !self.whisper_server_url.starts_with("http://")
+                    && !self.whisper_server_url.starts_with("https://")
+                {
+                    errors.push(FieldError::new(
+                        "whisper_server_url",
+                        "Whisper server URL must start with http:// or https://",
+                    ));
+                }
+                if self.whisper_server_model.trim().is_empty() {
+                    errors.push(FieldError::new(
+                        "whisper_server_model",
+                        "Whisper server model is not set",
+                    ));
+                }
+            }
+            TranscriptionBackend::Vosk
+                if !self.vosk_model_dir.as_os_str().is_empty() && !self.vosk_model_dir.is_dir() =>
+            {
+                errors.push(FieldError::new(
+                    "vosk_model_dir",
+                    format!(
+                        "Vosk model directory {:?} does not exist",
+                        self.vosk_model_dir
+                    ),
+                ));
+            }
+            TranscriptionBackend::Local
+                if !self.whisper_model.is_downloaded(&self.whisper_models_dir) =>
+            {
+                errors.push(FieldError::unset(
+                    "whisper_model",
+                    format!(
+                        "Whisper model '{}' is not downloaded",
+                        self.whisper_model.label()
+                    ),
+                ));
+            }
+            _ => {}
         }
-        Ok(())
+        if self.translate_to_english
+            && !matches!(
+                self.transcription_backend,
+                TranscriptionBackend::OpenAi
+                    | TranscriptionBackend::Local
+                    | TranscriptionBackend::WhisperServer
+            )
+        {
+            errors.push(FieldError::new(
+                "translate_to_english",
+                "Translation to English needs a Whisper transcription backend",
+            ));
+        } else if self.translate_to_english
+            && self.transcription_backend == TranscriptionBackend::OpenAi
+            && transcription::is_gpt4o_model(&self.transcription_model)
+        {
+            errors.push(FieldError::new(
+                "translate_to_english",
+                "Translation to English needs the whisper-1 model",
+            ));
+        }
+        if self.stream_transcription {
+            let problem = if self.transcription_backend != TranscriptionBackend::OpenAi {
+                Some("Streaming transcription needs the OpenAI backend")
+            } else if self.translate_to_english {
+                Some("Streaming transcription can't translate to English")
+            } else if self.split_stereo_channels || !self.extra_sources.is_empty() {
+                Some(
+                    "Streaming transcription can't be combined with split channels or extra \
+                     sources",
+                )
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                errors.push(FieldError::new("stream_transcription", problem));
+            }
+        }
+        if self.punctuation_restoration == PunctuationMode::Llm && !self.can_summarize() {
+            errors.push(FieldError::unset(
+                "punctuation_restoration",
+                "Punctuating with a language model needs an OpenAI API key",
+            ));
+        }
+        if let Err(e) = self.redactor() {
+            errors.push(FieldError::new("redaction_patterns", format!("{:#}", e)));
+        }
+        if let Err(e) = self.pii_scrubber() {
+            errors.push(FieldError::new("pii_patterns", format!("{:#}", e)));
+        }
+        if self.vad_enabled && self.vad_min_chunk_secs > self.vad_max_chunk_secs {
+            errors.push(FieldError::new(
+                "vad_min_chunk_secs",
+                "VAD minimum chunk length is longer than the maximum",
+            ));
+        }
+        if self.mix_microphone && self.split_stereo_channels {
+            errors.push(FieldError::new(
+                "mix_microphone",
+                "Mixing in a microphone can't be combined with split stereo channels",
+            ));
+        }
+        let mut labels = vec![self.main_source_label.as_str()];
+        for source in &self.extra_sources {
+            if source.label.trim().is_empty() || source.device.is_empty() {
+                errors.push(FieldError::new(
+                    "extra_sources",
+                    "Every extra source needs a label and an input device",
+                ));
+                break;
+            }
+            if labels.contains(&source.label.as_str()) {
+                errors.push(FieldError::new(
+                    "extra_sources",
+                    format!("Source label '{}' is used more than once", source.label),
+                ));
+                break;
+            }
+            labels.push(&source.label);
+        }
+        errors.extend(self.summary_errors());
+        errors
+    }
+
+    /// Check the summarization model and its sampling settings
+    pub fn validate_summaries(&self) -> Result<()> {
+        first_error(self.summary_errors())
+    }
+
+    fn summary_errors(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.summarization_model.trim().is_empty() {
+            errors.push(FieldError::new(
+                "summarization_model",
+                "Summarization model is not set",
+            ));
+        }
+        if !(0.0..=summarization::MAX_TEMPERATURE).contains(&self.summary_temperature) {
+            errors.push(FieldError::new(
+                "summary_temperature",
+                format!(
+                    "Summary temperature must be between 0 and {}",
+                    summarization::MAX_TEMPERATURE
+                ),
+            ));
+        }
+        if self.summary_max_tokens != 0 && self.summary_max_tokens < summarization::MIN_MAX_TOKENS {
+            errors.push(FieldError::new(
+                "summary_max_tokens",
+                format!(
+                    "Summary max tokens must be 0 (the model's default) or at least {}",
+                    summarization::MIN_MAX_TOKENS
+                ),
+            ));
+        }
+        if let Err(e) = presets::validate(&self.analysis_presets) {
+            errors.push(FieldError::new("analysis_presets", format!("{:#}", e)));
+        } else if !self.analysis_preset.is_empty() && self.analysis_preset().is_none() {
+            errors.push(FieldError::new(
+                "analysis_preset",
+                format!("There is no analysis preset '{}'", self.analysis_preset),
+            ));
+        }
+        errors
+    }
+
+    /// The analysis preset summaries are written with, if one is picked
+    pub fn analysis_preset(&self) -> Option<AnalysisPreset> {
+        if self.analysis_preset.is_empty() {
+            return None;
+        }
+        presets::all(&self.analysis_presets)
+            .into_iter()
+            .find(|preset| preset.id == self.analysis_preset)
+    }
+
+    /// Source label of the main capture, needed only alongside extra sources
+    pub fn main_source(&self) -> Option<String> {
+        (!self.extra_sources.is_empty()).then(|| self.main_source_label.clone())
+    }
+
+    /// The engine for the selected transcription backend. OpenAI requests
+    /// are paced by `rate_limit`, which is shared by all of them.
+    pub fn transcription_provider(
+        &self,
+        rate_limit: &RateLimiter,
+    ) -> Arc<dyn TranscriptionProvider> {
+        let language = self.forced_language();
+        let provider: Arc<dyn TranscriptionProvider> = match self.transcription_backend {
+            // Imported recordings are often over the upload limit
+            TranscriptionBackend::OpenAi => Arc::new(SplittingProvider::new(
+                Arc::new(OpenAiWhisper::new(
+                    self.transcription_endpoint(),
+                    self.transcription_model.clone(),
+                    language,
+                    self.translate_to_english,
+                    self.retry_policy().with_rate_limit(rate_limit.clone()),
+                )),
+                upload_split::WHISPER_UPLOAD_LIMIT,
+            )),
+            TranscriptionBackend::Local => Arc::new(LocalWhisper::new(
+                self.whisper_cli_path.clone(),
+                self.whisper_model.path(&self.whisper_models_dir),
+                language,
+                self.translate_to_english,
+            )),
+            TranscriptionBackend::AssemblyAi => Arc::new(AssemblyAi::new(
+                self.assemblyai_api_key.clone(),
+                language,
+                self.retry_policy(),
+            )),
+            TranscriptionBackend::Google => Arc::new(GoogleSpeech::new(
+                self.google_api_key.clone(),
+                language,
+                self.retry_policy(),
+            )),
+            TranscriptionBackend::Vosk => Arc::new(Vosk::new(
+                self.vosk_cli_path.clone(),
+                (!self.vosk_model_dir.as_os_str().is_empty()).then(|| self.vosk_model_dir.clone()),
+                language,
+            )),
+            TranscriptionBackend::WhisperServer => Arc::new(WhisperServer::new(
+                self.whisper_server_url.trim().to_string(),
+                self.whisper_server_token.clone(),
+                self.whisper_server_model.trim().to_string(),
+                language,
+                self.translate_to_english,
+                self.retry_policy(),
+            )),
+        };
+
+        let provider: Arc<dyn TranscriptionProvider> = match self.punctuation_restoration {
+            PunctuationMode::Off => provider,
+            PunctuationMode::Rules => Arc::new(PunctuatingProvider::new(provider, None)),
+            PunctuationMode::Llm => Arc::new(PunctuatingProvider::new(
+                provider,
+                Some(Summarizer::new(
+                    self.chat_endpoint(),
+                    self.punctuation_model.clone(),
+                    self.retry_policy(),
+                )),
+            )),
+        };
+
+        // Corrections after punctuation and before redaction, so patterns
+        // see the corrected terms
+        let corrector = Corrector::new(&self.term_corrections);
+        let provider: Arc<dyn TranscriptionProvider> = if corrector.is_empty() {
+            provider
+        } else {
+            Arc::new(CorrectingProvider::new(provider, corrector))
+        };
+        let provider: Arc<dyn TranscriptionProvider> = match self.redactor() {
+            Ok(redactor) if redactor.is_active() => {
+                Arc::new(RedactingProvider::new(provider, redactor))
+            }
+            Ok(_) => provider,
+            Err(e) => {
+                eprintln!("Transcripts are not redacted: {}", e);
+                provider
+            }
+        };
+
+        if !self.transcription_cache_enabled {
+            return provider;
+        }
+        Arc::new(CachedProvider::new(
+            provider,
+            TranscriptionCache::new(self.transcription_cache_dir.clone()),
+            self.transcription_settings_key(),
+        ))
+    }
+
+    /// The engine for transcribing one segment again with `overrides`.
+    /// Re-runs skip the cache, which would only hand back the text being
+    /// replaced.
+    pub fn retranscription_provider(
+        &self,
+        overrides: &TranscriptionOverrides,
+        rate_limit: &RateLimiter,
+    ) -> Result<Arc<dyn TranscriptionProvider>> {
+        let mut config = self.clone();
+        if let Some(model) = &overrides.model {
+            config.transcription_model = model.clone();
+        }
+        if let Some(language) = &overrides.language {
+            config.transcription_language = language.clone();
+        }
+        config.transcription_cache_enabled = false;
+        config.validate()?;
+        Ok(config.transcription_provider(rate_limit))
+    }
+
+    /// Where and how audio is streamed, or `None` when transcription goes a
+    /// chunk at a time
+    pub fn streaming_settings(&self) -> Option<RealtimeSettings> {
+        let context = TranscriptionContext {
+            vocabulary: self.transcription_vocabulary.clone(),
+            ..Default::default()
+        };
+        self.stream_transcription.then(|| RealtimeSettings {
+            endpoint: self.transcription_endpoint(),
+            model: self.transcription_model.clone(),
+            language: self.forced_language(),
+            prompt: context.prompt(),
+            timeout: self.retry_policy().timeout,
+        })
+    }
+
+    /// Everything that changes what the selected engine returns for the
+    /// same audio, so cached transcripts are only reused when it matches
+    fn transcription_settings_key(&self) -> String {
+        let engine = match self.transcription_backend {
+            TranscriptionBackend::OpenAi => format!(
+                "{} {}",
+                self.transcription_base_url, self.transcription_model
+            ),
+            TranscriptionBackend::Local => self.whisper_model.file_name().to_string(),
+            TranscriptionBackend::AssemblyAi => String::new(),
+            TranscriptionBackend::Google => google_speech::MODEL.to_string(),
+            TranscriptionBackend::Vosk => self.vosk_model_dir.display().to_string(),
+            TranscriptionBackend::WhisperServer => {
+                format!("{} {}", self.whisper_server_url, self.whisper_server_model)
+            }
+        };
+        // Cached transcripts are already punctuated, corrected and redacted
+        let corrections: Vec<String> = self
+            .term_corrections
+            .iter()
+            .map(|c| format!("{}>{}", c.from, c.to))
+            .collect();
+        format!(
+            "{}|{}|{}|translate={}|punctuate={}|correct={}|profanity={}|redact={}",
+            self.transcription_backend.label(),
+            engine,
+            self.forced_language().unwrap_or_else(|| "auto".to_string()),
+            self.translate_to_english,
+            match self.punctuation_restoration {
+                PunctuationMode::Llm => format!("llm:{}", self.punctuation_model),
+                mode => mode.label().to_lowercase(),
+            },
+            corrections.join("\u{1f}"),
+            self.profanity_filter,
+            self.redaction_patterns.join("\u{1f}")
+        )
+    }
+
+    /// Masks profanity and sensitive words in transcripts as configured
+    pub fn redactor(&self) -> Result<Redactor> {
+        Redactor::new(self.profanity_filter, &self.redaction_patterns)
+    }
+
+    /// Scrubs personal data from text that leaves the machine, or `None`
+    /// when scrubbing is off
+    pub fn pii_scrubber(&self) -> Result<Option<PiiScrubber>> {
+        if !self.pii_scrubbing {
+            return Ok(None);
+        }
+        PiiScrubber::new(&self.pii_patterns).map(Some)
+    }
+
+    /// Language every chunk is transcribed as, or `None` to auto-detect
+    pub fn forced_language(&self) -> Option<String> {
+        let language = self.transcription_language.trim();
+        (!language.is_empty() && !language.eq_ignore_ascii_case("auto"))
+            .then(|| language.to_lowercase())
+    }
+
+    /// Where transcription requests are sent
+    pub fn transcription_endpoint(&self) -> ApiEndpoint {
+        self.endpoint(&self.transcription_base_url)
+    }
+
+    /// URL of the service the selected backend transcribes with, for
+    /// connectivity checks (`None` for local transcription); the proxy's
+    /// when requests go through one, as the service may not be reachable
+    /// directly
+    pub fn transcription_host_url(&self) -> Option<String> {
+        let url = match self.transcription_backend {
+            TranscriptionBackend::OpenAi => Some(self.transcription_base_url.clone()),
+            TranscriptionBackend::Local => None,
+            TranscriptionBackend::AssemblyAi => Some(assemblyai::API_BASE.to_string()),
+            TranscriptionBackend::Google => Some(google_speech::API_BASE.to_string()),
+            TranscriptionBackend::Vosk => None,
+            TranscriptionBackend::WhisperServer => Some(self.whisper_server_url.clone()),
+        };
+        let proxy = self.http_proxy.trim();
+        url.map(|url| {
+            if proxy.is_empty() {
+                url
+            } else {
+                proxy.to_string()
+            }
+        })
+    }
+
+    /// Where summarization requests are sent
+    pub fn chat_endpoint(&self) -> ApiEndpoint {
+        self.endpoint(&self.chat_base_url)
+    }
+
+    /// How transcription and summarization requests are retried
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.api_max_attempts.max(1),
+            base_delay: Duration::from_secs_f32(self.api_retry_base_delay_secs.max(0.0)),
+            max_delay: Duration::from_secs_f32(self.api_retry_max_delay_secs.max(0.0)),
+            timeout: (self.api_timeout_secs > 0.0)
+                .then(|| Duration::from_secs_f32(self.api_timeout_secs)),
+            rate_limit: None,
+        }
+    }
+
+    /// Proxy and certificates for the shared HTTP client
+    pub fn http_settings(&self) -> HttpSettings {
+        HttpSettings {
+            proxy_url: self.http_proxy.clone(),
+            no_proxy: self.no_proxy.clone(),
+            ca_certificates: self.ca_certificates.clone(),
+        }
+    }
+
+    fn endpoint(&self, base_url: &str) -> ApiEndpoint {
+        ApiEndpoint {
+            base_url: base_url.to_string(),
+            api_version: self.api_version.clone(),
+            auth: self.api_auth_header,
+            api_key: self.openai_api_key.clone(),
+        }
+    }
+
+    /// Whether summaries can be generated (they always use the OpenAI API)
+    pub fn can_summarize(&self) -> bool {
+        !self.openai_api_key.is_empty()
+    }
+
+    /// Whether summaries can be written at all, by the chat model or, with
+    /// `extractive_fallback`, offline
+    pub fn summaries_available(&self) -> bool {
+        self.can_summarize() || self.extractive_fallback
+    }
+
+    /// Where segments are embedded, and with which model
+    pub fn embedding_endpoint(&self) -> (ApiEndpoint, String) {
+        match self.embedding_backend {
+            EmbeddingBackend::OpenAi => (self.chat_endpoint(), self.embedding_model.clone()),
+            EmbeddingBackend::Local => (
+                ApiEndpoint {
+                    base_url: self.local_embedding_url.trim().to_string(),
+                    api_version: String::new(),
+                    auth: AuthHeader::Bearer,
+                    api_key: String::new(),
+                },
+                self.local_embedding_model.trim().to_string(),
+            ),
+        }
+    }
+
+    /// Embeddings through OpenAI need an API key; a local server doesn't
+    pub fn can_embed(&self) -> bool {
+        match self.embedding_backend {
+            EmbeddingBackend::OpenAi => self.can_summarize(),
+            EmbeddingBackend::Local => true,
+        }
+    }
+
+    /// Application to capture, if one is selected
+    pub fn capture_app(&self) -> Option<String> {
+        (!self.capture_app.is_empty()).then(|| self.capture_app.clone())
+    }
+
+    /// Output device to monitor on, if monitoring is enabled
+    pub fn monitor_output(&self) -> Option<String> {
+        self.monitor_enabled
+            .then(|| self.monitor_output_device.clone())
+    }
+
+    /// Speaker labels for the left and right channels, if channels are split
+    pub fn channel_labels(&self) -> Option<[String; 2]> {
+        self.split_stereo_channels.then(|| {
+            [
+                self.left_channel_label.clone(),
+                self.right_channel_label.clone(),
+            ]
+        })
+    }
+
+    /// Chunk size limit in bytes, if one is set
+    pub fn max_chunk_bytes(&self) -> Option<u64> {
+        (self.max_chunk_size_mb > 0.0).then_some((self.max_chunk_size_mb * 1024.0 * 1024.0) as u64)
+    }
+
+    /// Directories the retention policy manages
+    pub fn retention_dirs(&self) -> [PathBuf; 3] {
+        [
+            self.audio_chunks_dir.clone(),
+            self.transcriptions_dir.clone(),
+            self.summaries_dir.clone(),
+        ]
+    }
+
+    /// Each managed directory with its limits, if retention is enabled
+    pub fn retention_policies(&self) -> Option<Vec<(PathBuf, RetentionPolicy)>> {
+        if !self.retention_enabled {
+            return None;
+        }
+        let megabytes = |mb: f32| (mb > 0.0).then_some((mb * 1024.0 * 1024.0) as u64);
+        let max_age = (self.retention_days > 0)
+            .then(|| Duration::from_secs(self.retention_days as u64 * 24 * 60 * 60));
+        let [chunks, transcriptions, summaries] = self.retention_dirs();
+        let audio = RetentionPolicy {
+            max_bytes: megabytes(self.audio_chunks_quota_mb),
+            max_age,
+        };
+        let text = RetentionPolicy {
+            max_bytes: megabytes(self.transcripts_quota_mb),
+            max_age,
+        };
+
+        Some(vec![
+            (chunks, audio),
+            (transcriptions, text),
+            (summaries, text),
+        ])
+    }
+
+    /// VAD chunking parameters, if VAD chunking is enabled
+    pub fn vad_settings(&self) -> Option<VadSettings> {
+        self.vad_enabled.then_some(VadSettings {
+            min_chunk_secs: self.vad_min_chunk_secs,
+            max_chunk_secs: self.vad_max_chunk_secs,
+            silence_ms: self.vad_silence_ms,
+            energy_threshold: self.vad_energy_threshold,
+        })
+    }
+}
+
+/// Write `item`'s inline tables, and arrays of them, as tables two levels
+/// deep, so settings such as `pricing` don't end up on one long line
+fn expand_toml(item: &mut toml_edit::Item, depth: usize) {
+    if depth >= 2 {
+        return;
+    }
+    if let Some(inline) = item.as_inline_table() {
+        let mut table = inline.clone().into_table();
+        for (_, child) in table.iter_mut() {
+            expand_toml(child, depth + 1);
+        }
+        *item = toml_edit::Item::Table(table);
+    } else if let Some(array) = item.as_array()
+        && !array.is_empty()
+        && array.iter().all(|value| value.is_inline_table())
+    {
+        let mut tables = toml_edit::ArrayOfTables::new();
+        for value in array.iter() {
+            if let Some(inline) = value.as_inline_table() {
+                tables.push(inline.clone().into_table());
+            }
+        }
+        *item = toml_edit::Item::ArrayOfTables(tables);
+    }
+}
+
+/// Names of the saved profiles, the default one first
+pub fn profiles() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(config_dir().join("profiles"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "toml" && path.extension()? != "json" {
+                return None;
+            }
+            path.file_stem()?.to_str().map(str::to_string)
+        })
+        .filter(|name| name != DEFAULT_PROFILE && validate_profile_name(name).is_ok())
+        .collect();
+    names.sort();
+    names.dedup();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    names
+}
+
+/// Profile names become file and keyring entry names, so they are kept to
+/// letters, digits, `-` and `_`
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.len() > 40
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "Profile names are 1 to 40 letters, digits, dashes and underscores, not '{}'",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// The profile used last, or the default one
+pub fn last_profile() -> String {
+    fs::read_to_string(config_dir().join(LAST_PROFILE_FILE))
+        .map(|name| name.trim().to_string())
+        .ok()
+        .filter(|name| validate_profile_name(name).is_ok())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// Start in `profile` next time
+pub fn remember_profile(profile: &str) -> Result<()> {
+    fs::create_dir_all(config_dir())?;
+    fs::write(config_dir().join(LAST_PROFILE_FILE), profile)?;
+    Ok(())
+}
+
+fn config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("audio-assistant")
+}
+
+/// Where `profile` keeps its recordings, transcripts and summaries by
+/// default
+fn data_dir(profile: &str) -> PathBuf {
+    let base_dir = dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("audio-assistant");
+    if profile == DEFAULT_PROFILE {
+        base_dir
+    } else {
+        base_dir.join("profiles").join(profile)
     }
 }
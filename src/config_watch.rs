@@ -0,0 +1,22 @@
+use anyhow::Result;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+
+/// Call `on_change` whenever the file at `path` is written or replaced,
+/// until the watcher is dropped. The folder is watched rather than the
+/// file, as many editors save by renaming a new file over the old one.
+pub fn watch(path: PathBuf, on_change: impl Fn() + Send + 'static) -> Result<RecommendedWatcher> {
+    let folder = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            && event.paths.contains(&path)
+        {
+            on_change();
+        }
+    })?;
+    watcher.watch(&folder, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
@@ -0,0 +1,415 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::api_error::ApiError;
+use crate::assemblyai;
+use crate::config::Config;
+use crate::embeddings::EmbeddingBackend;
+use crate::google_speech;
+use crate::http;
+use crate::openai_api::{ApiEndpoint, AuthHeader};
+use crate::transcription::TranscriptionBackend;
+
+/// How long a test waits for a service to answer
+const TEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A service the config uses, and how to check it
+#[derive(Debug, Clone)]
+pub struct Target {
+    /// What it's used for, e.g. "Transcription"
+    pub purpose: String,
+    /// The key or token it takes, and the settings tab it's entered in
+    key: Option<(&'static str, &'static str)>,
+    service: Service,
+}
+
+#[derive(Debug, Clone)]
+enum Service {
+    /// An OpenAI-compatible API, whose models list should have `models`
+    OpenAi {
+        endpoint: ApiEndpoint,
+        models: Vec<String>,
+    },
+    AssemblyAi {
+        api_key: String,
+    },
+    Google {
+        api_key: String,
+    },
+    /// Runs on this machine; there's nothing to connect to
+    Local,
+    /// Its key isn't set; `required` when nothing works without it
+    NoKey {
+        required: bool,
+        fallback: String,
+    },
+}
+
+/// How a test went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warning,
+    Failed,
+}
+
+/// What testing a service found
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub purpose: String,
+    pub status: Status,
+    /// e.g. "Key accepted, 52 models available"
+    pub detail: String,
+    /// What to do about a failure or warning
+    pub guidance: Option<String>,
+}
+
+/// `{"data": [{"id": "whisper-1"}, ...]}` from `GET /models`
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<Model>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Model {
+    id: String,
+}
+
+/// The services `config` sends requests to, each once: summaries and
+/// OpenAI transcription share a test when they use the same endpoint
+pub fn targets(config: &Config) -> Vec<Target> {
+    let mut targets = Vec::new();
+    let openai_key = Some(("OpenAI API key", "General"));
+    let transcription = format!("Transcription ({})", config.transcription_backend.label());
+    match config.transcription_backend {
+        TranscriptionBackend::OpenAi if config.openai_api_key.is_empty() => {
+            targets.push(no_key(transcription, openai_key, true, ""));
+        }
+        TranscriptionBackend::OpenAi => {
+            // Azure lists models for the resource, not the deployment
+            let models = (config.api_auth_header == AuthHeader::Bearer)
+                .then(|| config.transcription_model.clone());
+            push_openai(
+                &mut targets,
+                transcription,
+                openai_key,
+                config.transcription_endpoint(),
+                models,
+            );
+        }
+        TranscriptionBackend::Local | TranscriptionBackend::Vosk => targets.push(Target {
+            purpose: transcription,
+            key: None,
+            service: Service::Local,
+        }),
+        TranscriptionBackend::AssemblyAi => {
+            let key = Some(("AssemblyAI API key", "Transcription"));
+            targets.push(if config.assemblyai_api_key.is_empty() {
+                no_key(transcription, key, true, "")
+            } else {
+                Target {
+                    purpose: transcription,
+                    key,
+                    service: Service::AssemblyAi {
+                        api_key: config.assemblyai_api_key.clone(),
+                    },
+                }
+            });
+        }
+        TranscriptionBackend::Google => {
+            let key = Some(("Google API key", "Transcription"));
+            targets.push(if config.google_api_key.is_empty() {
+                no_key(transcription, key, true, "")
+            } else {
+                Target {
+                    purpose: transcription,
+                    key,
+                    service: Service::Google {
+                        api_key: config.google_api_key.clone(),
+                    },
+                }
+            });
+        }
+        TranscriptionBackend::WhisperServer => {
+            // Servers may fetch a model on first use, so it isn't looked for
+            let endpoint = ApiEndpoint {
+                base_url: config.whisper_server_url.trim().to_string(),
+                api_version: String::new(),
+                auth: AuthHeader::Bearer,
+                api_key: config.whisper_server_token.clone(),
+            };
+            push_openai(
+                &mut targets,
+                transcription,
+                Some(("Whisper server token", "Transcription")),
+                endpoint,
+                None,
+            );
+        }
+    }
+
+    if !config.can_summarize() {
+        let fallback = if config.extractive_fallback {
+            "Summaries are picked from the transcript offline until one is set"
+        } else {
+            "Summaries are off until one is set"
+        };
+        targets.push(no_key("Summaries".to_string(), openai_key, false, fallback));
+    } else {
+        let models = (config.api_auth_header == AuthHeader::Bearer)
+            .then(|| config.summarization_model.clone());
+        push_openai(
+            &mut targets,
+            "Summaries".to_string(),
+            openai_key,
+            config.chat_endpoint(),
+            models,
+        );
+    }
+
+    if config.semantic_search && config.can_embed() {
+        let (endpoint, model) = config.embedding_endpoint();
+        let key = match config.embedding_backend {
+            EmbeddingBackend::OpenAi => openai_key,
+            EmbeddingBackend::Local => None,
+        };
+        let model = (endpoint.auth == AuthHeader::Bearer).then_some(model);
+        push_openai(
+            &mut targets,
+            "Semantic search".to_string(),
+            key,
+            endpoint,
+            model,
+        );
+    }
+    targets
+}
+
+fn no_key(
+    purpose: String,
+    key: Option<(&'static str, &'static str)>,
+    required: bool,
+    fallback: &str,
+) -> Target {
+    Target {
+        purpose,
+        key,
+        service: Service::NoKey {
+            required,
+            fallback: fallback.to_string(),
+        },
+    }
+}
+
+/// Add a test of `endpoint`, or fold it into an earlier one of the same
+fn push_openai(
+    targets: &mut Vec<Target>,
+    purpose: String,
+    key: Option<(&'static str, &'static str)>,
+    endpoint: ApiEndpoint,
+    model: Option<String>,
+) {
+    let same = targets.iter_mut().find(|target| match &target.service {
+        Service::OpenAi {
+            endpoint: other, ..
+        } => {
+            other.base_url.trim_end_matches('/') == endpoint.base_url.trim_end_matches('/')
+                && other.api_key == endpoint.api_key
+                && other.api_version == endpoint.api_version
+                && other.auth == endpoint.auth
+        }
+        _ => false,
+    });
+    if let Some(target) = same {
+        target.purpose = format!("{} and {}", target.purpose, purpose.to_lowercase());
+        if let Service::OpenAi { models, .. } = &mut target.service
+            && let Some(model) = model
+            && !models.contains(&model)
+        {
+            models.push(model);
+        }
+        return;
+    }
+    targets.push(Target {
+        purpose,
+        key,
+        service: Service::OpenAi {
+            endpoint,
+            models: model.into_iter().collect(),
+        },
+    });
+}
+
+/// Test every target at once, answering in their order
+pub async fn run(targets: Vec<Target>) -> Vec<Outcome> {
+    let tests: Vec<_> = targets
+        .into_iter()
+        .map(|target| tokio::spawn(test(target)))
+        .collect();
+    let mut outcomes = Vec::new();
+    for test in tests {
+        match test.await {
+            Ok(outcome) => outcomes.push(outcome),
+            Err(e) => eprintln!("Connection test panicked: {}", e),
+        }
+    }
+    outcomes
+}
+
+/// Make the cheapest authenticated request the service has: listing its
+/// models, or AssemblyAI's last transcript
+async fn test(target: Target) -> Outcome {
+    let client = http::client();
+    let (what, request) = match &target.service {
+        Service::Local => {
+            return outcome(
+                &target,
+                Status::Ok,
+                "Runs on this machine, no connection needed",
+            );
+        }
+        Service::NoKey { required, fallback } => {
+            let name = target.key.map_or("key", |(name, _)| name);
+            let status = if *required {
+                Status::Failed
+            } else {
+                Status::Warning
+            };
+            let mut outcome = outcome(&target, status, &format!("No {} set", name));
+            outcome.guidance = Some(match target.key {
+                Some((_, tab)) if fallback.is_empty() => {
+                    format!("Enter it in the {} tab", tab)
+                }
+                Some((_, tab)) => format!("{}; enter it in the {} tab", fallback, tab),
+                None => fallback.clone(),
+            });
+            return outcome;
+        }
+        Service::OpenAi { endpoint, .. } => ("Models list", endpoint.get(&client, "models")),
+        Service::AssemblyAi { api_key } => (
+            "AssemblyAI transcript list",
+            client
+                .get(format!("{}/transcript", assemblyai::API_BASE))
+                .query(&[("limit", "1")])
+                .header("Authorization", api_key),
+        ),
+        Service::Google { api_key } => (
+            "Google Speech-to-Text operations list",
+            client
+                .get(format!("{}/operations", google_speech::API_BASE))
+                .query(&[("pageSize", "1"), ("key", api_key.as_str())]),
+        ),
+    };
+
+    let response = match request.timeout(TEST_TIMEOUT).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            let error = ApiError::Network {
+                what: what.to_string(),
+                detail: e.to_string(),
+            };
+            return failed(&target, error);
+        }
+    };
+    if !response.status().is_success() {
+        let error = ApiError::from_response(what, response).await;
+        // Google answers a valid key with an error about the listing itself
+        if matches!(target.service, Service::Google { .. })
+            && matches!(&error, ApiError::Failed { status, .. } if status.is_client_error())
+        {
+            return outcome(&target, Status::Ok, "Key accepted");
+        }
+        return failed(&target, error);
+    }
+
+    let Service::OpenAi { models, endpoint } = &target.service else {
+        return outcome(&target, Status::Ok, "Key accepted");
+    };
+    let accepted = if endpoint.api_key.is_empty() {
+        "Reachable"
+    } else {
+        "Key accepted"
+    };
+    // Proxies don't all list models the same way
+    let Ok(list) = response.json::<ModelList>().await else {
+        return outcome(&target, Status::Ok, accepted);
+    };
+    let missing: Vec<&str> = models
+        .iter()
+        .filter(|model| {
+            !list.data.iter().any(|m| {
+                m.id == **model
+                    || m.id
+                        .strip_prefix(model.as_str())
+                        .is_some_and(|tag| tag.starts_with(':'))
+            })
+        })
+        .map(String::as_str)
+        .collect();
+    if missing.is_empty() {
+        return outcome(
+            &target,
+            Status::Ok,
+            &format!("{}, {} models available", accepted, list.data.len()),
+        );
+    }
+    let mut outcome = outcome(
+        &target,
+        Status::Warning,
+        &format!(
+            "{}, but {} isn't among its {} models",
+            accepted,
+            missing.join(" and "),
+            list.data.len()
+        ),
+    );
+    outcome.guidance =
+        Some("Check the model name, and that the key's project has access to it".to_string());
+    outcome
+}
+
+fn outcome(target: &Target, status: Status, detail: &str) -> Outcome {
+    Outcome {
+        purpose: target.purpose.clone(),
+        status,
+        detail: detail.to_string(),
+        guidance: None,
+    }
+}
+
+/// A failed test, with what to do about it for this service in particular
+fn failed(target: &Target, error: ApiError) -> Outcome {
+    let guidance = match (&error, &target.service) {
+        (ApiError::Auth { detail, .. }, Service::Google { .. })
+            if detail.contains("has not been used") || detail.contains("disabled") =>
+        {
+            Some(
+                "Enable the Cloud Speech-to-Text API for the key's project in the Google \
+                 Cloud console"
+                    .to_string(),
+            )
+        }
+        (ApiError::Auth { .. }, _) => Some(match target.key {
+            Some((name, tab)) => format!("Check the {} in the {} tab", name, tab),
+            None => "The server wants a key; check its settings".to_string(),
+        }),
+        (ApiError::Network { .. }, Service::OpenAi { endpoint, .. }) => Some(format!(
+            "Check the network connection, the proxy settings and that {} is the \
+             right URL and its server is running",
+            endpoint.base_url
+        )),
+        (ApiError::Failed { status, .. }, Service::OpenAi { endpoint, .. })
+            if *status == StatusCode::NOT_FOUND =>
+        {
+            Some(format!(
+                "The server answered, but has no models list at {}; check the base URL",
+                endpoint.base_url
+            ))
+        }
+        _ => error.guidance(),
+    };
+    let mut outcome = outcome(target, Status::Failed, &error.to_string());
+    outcome.guidance = guidance;
+    outcome
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::transcription::{
+    Transcript, TranscriptionContext, TranscriptionProvider, TranscriptionResult,
+};
+
+/// One entry of the correction dictionary, e.g. "acme corp" → "AcmeCorp"
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Correction {
+    /// What the engine writes; matched as whole words, ignoring case and
+    /// spacing
+    pub from: String,
+    /// What it should say
+    pub to: String,
+}
+
+/// Applies the correction dictionary to transcript text
+#[derive(Debug, Clone)]
+pub struct Corrector {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Corrector {
+    pub fn new(corrections: &[Correction]) -> Self {
+        let rules = corrections
+            .iter()
+            .filter_map(|c| Some((term_regex(&c.from)?, c.to.trim().to_string())))
+            .collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// `text` with every dictionary term replaced, in dictionary order
+    pub fn correct(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (regex, to) in &self.rules {
+            text = regex.replace_all(&text, regex::NoExpand(to)).into_owned();
+        }
+        text
+    }
+
+    /// Correct everything in `transcript` that holds text. Word timings are
+    /// corrected one word at a time, so multi-word terms are only fixed in
+    /// the text, utterances and segments.
+    pub fn correct_transcript(&self, transcript: &mut Transcript) {
+        transcript.text = self.correct(&transcript.text);
+        for utterance in &mut transcript.utterances {
+            utterance.text = self.correct(&utterance.text);
+        }
+        for segment in &mut transcript.segments {
+            segment.text = self.correct(&segment.text);
+        }
+        for word in &mut transcript.words {
+            word.word = self.correct(&word.word);
+        }
+    }
+
+    /// Correct a segment that was transcribed before the dictionary changed.
+    /// Returns whether anything changed.
+    pub fn correct_result(&self, result: &mut TranscriptionResult) -> bool {
+        let before = result.text.clone();
+        result.text = self.correct(&result.text);
+        for utterance in &mut result.utterances {
+            utterance.text = self.correct(&utterance.text);
+        }
+        for segment in &mut result.segments {
+            segment.text = self.correct(&segment.text);
+        }
+        for word in &mut result.words {
+            word.word = self.correct(&word.word);
+        }
+        result.text != before
+    }
+}
+
+/// `term` as a case-insensitive regex matching it as whole words with any
+/// whitespace between them, or `None` for an empty term
+fn term_regex(term: &str) -> Option<Regex> {
+    let words: Vec<String> = term.split_whitespace().map(regex::escape).collect();
+    if words.is_empty() {
+        return None;
+    }
+    // \b only works next to word characters ("C++" ends in symbols)
+    let boundary = |c: Option<char>| {
+        if c.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            r"\b"
+        } else {
+            ""
+        }
+    };
+    let term = term.trim();
+    let pattern = format!(
+        "{}{}{}",
+        boundary(term.chars().next()),
+        words.join(r"\s+"),
+        boundary(term.chars().next_back())
+    );
+    RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .ok()
+}
+
+/// Wraps an engine so the correction dictionary is applied to every
+/// transcript before it is redacted, cached, shown or saved
+pub struct CorrectingProvider {
+    inner: Arc<dyn TranscriptionProvider>,
+    corrector: Corrector,
+}
+
+impl CorrectingProvider {
+    pub fn new(inner: Arc<dyn TranscriptionProvider>, corrector: Corrector) -> Self {
+        Self { inner, corrector }
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for CorrectingProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        let mut transcript = self.inner.transcribe_audio(audio_file, context).await?;
+        self.corrector.correct_transcript(&mut transcript);
+        Ok(transcript)
+    }
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::summarization::TokenUsage;
+use crate::transcription::BilledAudio;
+
+/// USD per million tokens of a chat model
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TokenPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// List prices used to estimate what a session costs, in USD. Models
+/// missing here are still counted, just not priced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pricing {
+    /// Per minute of audio, by transcription model
+    pub transcription_per_minute: BTreeMap<String, f64>,
+    /// Per million tokens, by chat model
+    pub chat_per_million_tokens: BTreeMap<String, TokenPrice>,
+    /// Per million tokens, by embedding model
+    pub embedding_per_million_tokens: BTreeMap<String, f64>,
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        let transcription_per_minute = [
+            ("whisper-1", 0.006),
+            ("gpt-4o-transcribe", 0.006),
+            ("gpt-4o-mini-transcribe", 0.003),
+            ("google-latest_long", 0.024),
+        ];
+        let chat_per_million_tokens = [
+            ("gpt-4o-mini", 0.15, 0.60),
+            ("gpt-4o", 2.50, 10.00),
+            ("gpt-4.1-mini", 0.40, 1.60),
+            ("gpt-4.1", 2.00, 8.00),
+        ];
+        let embedding_per_million_tokens = [
+            ("text-embedding-3-small", 0.02),
+            ("text-embedding-3-large", 0.13),
+        ];
+
+        Self {
+            transcription_per_minute: transcription_per_minute
+                .into_iter()
+                .map(|(model, price)| (model.to_string(), price))
+                .collect(),
+            chat_per_million_tokens: chat_per_million_tokens
+                .into_iter()
+                .map(|(model, input, output)| (model.to_string(), TokenPrice { input, output }))
+                .collect(),
+            embedding_per_million_tokens: embedding_per_million_tokens
+                .into_iter()
+                .map(|(model, price)| (model.to_string(), price))
+                .collect(),
+        }
+    }
+}
+
+impl Pricing {
+    /// Estimated USD of `usage` on chat `model`, if it has a price
+    pub fn chat_usd(&self, model: &str, usage: &TokenUsage) -> Option<f64> {
+        let price = self.chat_per_million_tokens.get(model)?;
+        Some(
+            (usage.prompt_tokens as f64 * price.input
+                + usage.completion_tokens as f64 * price.output)
+                / 1_000_000.0,
+        )
+    }
+}
+
+/// One billable API call
+#[derive(Debug, Clone)]
+pub enum ApiUsage {
+    /// Audio sent for transcription
+    Audio(BilledAudio),
+    /// Tokens spent on a chat completion
+    Chat { model: String, usage: TokenUsage },
+    /// Tokens of text embedded for semantic search
+    Embedding { model: String, tokens: u64 },
+}
+
+/// API usage since the app started or the transcript was last cleared, with
+/// its estimated cost
+#[derive(Debug, Clone)]
+pub struct SessionCost {
+    pub started_at: chrono::DateTime<chrono::Local>,
+    pub audio_secs: f64,
+    pub transcription_usd: f64,
+    pub chat_requests: u32,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub chat_usd: f64,
+    /// Chat tokens by model
+    pub chat_by_model: BTreeMap<String, TokenUsage>,
+    pub embedding_tokens: u64,
+    pub embedding_usd: f64,
+    /// Models that were used but have no price configured
+    pub unpriced_models: BTreeSet<String>,
+}
+
+impl SessionCost {
+    pub fn new() -> Self {
+        Self {
+            started_at: chrono::Local::now(),
+            audio_secs: 0.0,
+            transcription_usd: 0.0,
+            chat_requests: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            chat_usd: 0.0,
+            chat_by_model: BTreeMap::new(),
+            embedding_tokens: 0,
+            embedding_usd: 0.0,
+            unpriced_models: BTreeSet::new(),
+        }
+    }
+
+    pub fn record(&mut self, usage: &ApiUsage, pricing: &Pricing) {
+        match usage {
+            ApiUsage::Audio(billed) => {
+                self.audio_secs += billed.secs;
+                match pricing.transcription_per_minute.get(&billed.model) {
+                    Some(per_minute) => self.transcription_usd += billed.secs / 60.0 * per_minute,
+                    None => {
+                        self.unpriced_models.insert(billed.model.clone());
+                    }
+                }
+            }
+            ApiUsage::Chat { model, usage } => {
+                self.chat_requests += 1;
+                self.prompt_tokens += usage.prompt_tokens;
+                self.completion_tokens += usage.completion_tokens;
+                self.chat_by_model
+                    .entry(model.clone())
+                    .or_default()
+                    .add(usage);
+                match pricing.chat_usd(model, usage) {
+                    Some(usd) => self.chat_usd += usd,
+                    None => {
+                        self.unpriced_models.insert(model.clone());
+                    }
+                }
+            }
+            ApiUsage::Embedding { model, tokens } => {
+                self.embedding_tokens += tokens;
+                match pricing.embedding_per_million_tokens.get(model) {
+                    Some(price) => self.embedding_usd += *tokens as f64 * price / 1_000_000.0,
+                    None => {
+                        self.unpriced_models.insert(model.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Nothing billable has been sent yet
+    pub fn is_empty(&self) -> bool {
+        self.audio_secs == 0.0 && self.chat_requests == 0 && self.embedding_tokens == 0
+    }
+
+    pub fn total_usd(&self) -> f64 {
+        self.transcription_usd + self.chat_usd + self.embedding_usd
+    }
+}
+
+impl Default for SessionCost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Multi-line report for the log and the clipboard
+impl std::fmt::Display for SessionCost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "API cost since {}: ~${:.4}",
+            self.started_at.format("%Y-%m-%d %H:%M"),
+            self.total_usd()
+        )?;
+        writeln!(
+            f,
+            "  Transcription: {:.1} min of audio, ~${:.4}",
+            self.audio_secs / 60.0,
+            self.transcription_usd
+        )?;
+        write!(
+            f,
+            "  Summaries: {} requests, {} prompt + {} completion tokens, ~${:.4}",
+            self.chat_requests, self.prompt_tokens, self.completion_tokens, self.chat_usd
+        )?;
+        if self.embedding_tokens > 0 {
+            write!(
+                f,
+                "\n  Search embeddings: {} tokens, ~${:.4}",
+                self.embedding_tokens, self.embedding_usd
+            )?;
+        }
+        if !self.unpriced_models.is_empty() {
+            let models: Vec<&str> = self.unpriced_models.iter().map(String::as_str).collect();
+            write!(
+                f,
+                "\n  Not priced (add them to `pricing`): {}",
+                models.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
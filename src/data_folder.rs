@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What moving the data folders did
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MoveReport {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Check that each folder of `moves` (from, to) can be moved before any
+/// is: a destination must be missing or empty, and not inside its source
+pub fn check_moves(moves: &[(PathBuf, PathBuf)]) -> Result<()> {
+    for (from, to) in moves {
+        if to.starts_with(from) {
+            anyhow::bail!(
+                "{:?} is inside {:?}, which it would be moved from",
+                to,
+                from
+            );
+        }
+        let occupied = fs::read_dir(to)
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(false);
+        if occupied {
+            anyhow::bail!("{:?} already exists and isn't empty", to);
+        }
+    }
+    Ok(())
+}
+
+/// Move each folder of `moves` to its destination: renamed when it's on
+/// the same disk, otherwise copied and then deleted. Missing folders are
+/// skipped. When one fails, those already moved are moved back.
+pub fn move_dirs(moves: &[(PathBuf, PathBuf)]) -> Result<MoveReport> {
+    check_moves(moves)?;
+    let mut report = MoveReport::default();
+    let mut moved: Vec<(&Path, &Path)> = Vec::new();
+    for (from, to) in moves {
+        if !from.is_dir() {
+            continue;
+        }
+        if let Err(e) = move_dir(from, to, &mut report) {
+            for (from, to) in moved.into_iter().rev() {
+                if let Err(e) = move_dir(to, from, &mut MoveReport::default()) {
+                    eprintln!("Couldn't move {:?} back to {:?}: {}", to, from, e);
+                }
+            }
+            return Err(e);
+        }
+        moved.push((from, to));
+    }
+    Ok(report)
+}
+
+fn move_dir(from: &Path, to: &Path, report: &mut MoveReport) -> Result<()> {
+    count(from, report)?;
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create folder {:?}", parent))?;
+    }
+    if to.is_dir() {
+        fs::remove_dir(to)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        println!("Moved {:?} to {:?}", from, to);
+        return Ok(());
+    }
+    // On another disk: the original stays until the copy is complete
+    if let Err(e) = copy_dir(from, to) {
+        let _ = fs::remove_dir_all(to);
+        return Err(e.context(format!("Failed to copy {:?} to {:?}", from, to)));
+    }
+    fs::remove_dir_all(from)
+        .with_context(|| format!("Copied {:?}, but failed to delete it", from))?;
+    println!("Copied {:?} to {:?}", from, to);
+    Ok(())
+}
+
+/// Add the files under `dir` to `report`
+fn count(dir: &Path, report: &mut MoveReport) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read folder {:?}", dir))? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            count(&entry.path(), report)?;
+        } else {
+            report.files += 1;
+            report.bytes += metadata.len();
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// STFT frame length in samples (32ms at 16kHz)
+const FRAME_LEN: usize = 512;
+const HOP_LEN: usize = FRAME_LEN / 2;
+
+/// How far above the noise floor a bin must be before it passes untouched
+const OVER_SUBTRACTION: f32 = 1.5;
+
+/// Spectral gate noise suppressor.
+///
+/// Tracks a per-frequency noise floor with minimum statistics and attenuates
+/// bins that do not rise clearly above it. The noise estimate carries over
+/// between chunks so steady background noise (fans, hum) is learned once.
+pub struct SpectralGate {
+    /// 0.0 leaves audio untouched, 1.0 removes as much noise as possible
+    strength: f32,
+    fft: Arc<dyn Fft<f32>>,
+    ifft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    noise: Vec<f32>,
+    gains: Vec<f32>,
+}
+
+impl SpectralGate {
+    pub fn new(strength: f32) -> Self {
+        let mut planner = FftPlanner::new();
+
+        // Square-root Hann on both analysis and synthesis sums to unity at 50% overlap
+        let window = (0..FRAME_LEN)
+            .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f32 / FRAME_LEN as f32).cos()).sqrt())
+            .collect();
+
+        Self {
+            strength: strength.clamp(0.0, 1.0),
+            fft: planner.plan_fft_forward(FRAME_LEN),
+            ifft: planner.plan_fft_inverse(FRAME_LEN),
+            window,
+            noise: Vec::new(),
+            gains: vec![1.0; FRAME_LEN],
+        }
+    }
+
+    /// Denoise a block of mono samples, returning a block of the same length
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        if samples.is_empty() || self.strength == 0.0 {
+            return samples.to_vec();
+        }
+
+        // Pad so every input sample is covered by two overlapping frames
+        let mut padded = vec![0.0; HOP_LEN];
+        padded.extend_from_slice(samples);
+        padded.resize(padded.len() + FRAME_LEN, 0.0);
+
+        let mut output = vec![0.0; padded.len()];
+        let mut spectrum = vec![Complex::new(0.0, 0.0); FRAME_LEN];
+        let floor_gain = 1.0 - self.strength;
+
+        let mut start = 0;
+        while start + FRAME_LEN <= padded.len() {
+            for (i, bin) in spectrum.iter_mut().enumerate() {
+                *bin = Complex::new(padded[start + i] * self.window[i], 0.0);
+            }
+            self.fft.process(&mut spectrum);
+
+            if self.noise.is_empty() {
+                self.noise = spectrum.iter().map(|c| c.norm()).collect();
+            }
+
+            for (k, bin) in spectrum.iter_mut().enumerate() {
+                let magnitude = bin.norm();
+
+                // Minimum statistics: drop quickly to quieter levels, rise slowly
+                let noise = &mut self.noise[k];
+                if magnitude < *noise {
+                    *noise = 0.5 * *noise + 0.5 * magnitude;
+                } else {
+                    *noise *= 1.002;
+                }
+
+                let target = if magnitude > f32::EPSILON {
+                    (1.0 - self.strength * OVER_SUBTRACTION * *noise / magnitude).max(floor_gain)
+                } else {
+                    floor_gain
+                };
+
+                // Smooth gains over time to avoid "musical noise" artefacts
+                let gain = &mut self.gains[k];
+                *gain = 0.6 * *gain + 0.4 * target;
+                *bin *= *gain;
+            }
+
+            self.ifft.process(&mut spectrum);
+            for (i, bin) in spectrum.iter().enumerate() {
+                output[start + i] += bin.re / FRAME_LEN as f32 * self.window[i];
+            }
+
+            start += HOP_LEN;
+        }
+
+        output[HOP_LEN..HOP_LEN + samples.len()].to_vec()
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio_capture::CLIP_LEVEL;
+
+/// Shortest run of digital silence counted as a dropout
+const DROPOUT_MIN_SECS: f32 = 0.05;
+
+/// Clipped samples a chunk may contain before it is flagged; the odd clipped
+/// peak on a plosive doesn't hurt transcription
+const CLIPPED_SAMPLES_WARNING: u64 = 20;
+
+/// Signal problems found in a chunk that can explain a garbled transcription
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudioDiagnostics {
+    /// Samples at or beyond full scale
+    pub clipped_samples: u64,
+    /// Runs of exact zeros long enough to be a gap in the stream rather than
+    /// quiet audio (device glitches, buffer underruns)
+    pub dropouts: u32,
+    /// Total length of those runs
+    pub dropout_secs: f32,
+}
+
+impl AudioDiagnostics {
+    pub fn from_samples(samples: &[f32], sample_rate: u32) -> Self {
+        let mut builder = DiagnosticsBuilder::new(sample_rate);
+        builder.push(samples);
+        builder.finish()
+    }
+
+    /// Why the chunk's audio looks degraded, if it does
+    pub fn warning(&self) -> Option<String> {
+        let mut problems = Vec::new();
+        if self.clipped_samples >= CLIPPED_SAMPLES_WARNING {
+            problems.push(format!("{} clipped samples", self.clipped_samples));
+        }
+        if self.dropouts > 0 {
+            problems.push(format!(
+                "{} dropout{} ({:.2}s)",
+                self.dropouts,
+                if self.dropouts == 1 { "" } else { "s" },
+                self.dropout_secs
+            ));
+        }
+        (!problems.is_empty()).then(|| problems.join(", "))
+    }
+}
+
+/// Collects diagnostics incrementally as a chunk is written
+pub struct DiagnosticsBuilder {
+    sample_rate: u32,
+    min_dropout_len: usize,
+    diagnostics: AudioDiagnostics,
+    zero_run: usize,
+}
+
+impl DiagnosticsBuilder {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            min_dropout_len: ((sample_rate as f32 * DROPOUT_MIN_SECS) as usize).max(1),
+            diagnostics: AudioDiagnostics::default(),
+            zero_run: 0,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            if sample.abs() >= CLIP_LEVEL {
+                self.diagnostics.clipped_samples += 1;
+            }
+
+            if sample == 0.0 {
+                self.zero_run += 1;
+            } else {
+                self.end_zero_run();
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> AudioDiagnostics {
+        self.end_zero_run();
+        self.diagnostics
+    }
+
+    fn end_zero_run(&mut self) {
+        if self.zero_run >= self.min_dropout_len {
+            self.diagnostics.dropouts += 1;
+            self.diagnostics.dropout_secs += self.zero_run as f32 / self.sample_rate as f32;
+        }
+        self.zero_run = 0;
+    }
+}
@@ -0,0 +1,88 @@
+/// Length of echo path the filter can model, in seconds
+const ECHO_TAIL_SECS: f32 = 0.128;
+
+/// NLMS adaptation step; smaller converges slower but is more stable
+const STEP_SIZE: f32 = 0.3;
+
+/// Geigel double-talk threshold: mic louder than this fraction of the recent
+/// reference peak is treated as local speech and adaptation pauses
+const DOUBLE_TALK_RATIO: f32 = 0.5;
+
+/// How long adaptation stays frozen after double talk was last detected
+const DOUBLE_TALK_HOLD_SECS: f32 = 0.1;
+
+/// Acoustic echo canceller.
+///
+/// A normalized LMS adaptive filter learns how the speaker signal (the
+/// reference) reaches the microphone and subtracts that estimate from the
+/// mic, leaving the local talker. Adaptation is frozen during double talk so
+/// the filter doesn't learn to cancel the local voice.
+pub struct EchoCanceller {
+    taps: usize,
+    weights: Vec<f32>,
+    /// Reference history stored twice so the newest `taps` samples are
+    /// always one contiguous slice ending at `pos + taps`
+    history: Vec<f32>,
+    pos: usize,
+    /// Sum of squares of the reference samples in the window
+    power: f32,
+    /// Decaying peak of the reference, for double-talk detection
+    reference_peak: f32,
+    peak_decay: f32,
+    hold_samples: usize,
+    /// Samples left before adaptation resumes after double talk
+    hold: usize,
+}
+
+impl EchoCanceller {
+    pub fn new(sample_rate: u32) -> Self {
+        let taps = ((sample_rate as f32 * ECHO_TAIL_SECS) as usize).max(1);
+
+        Self {
+            taps,
+            weights: vec![0.0; taps],
+            history: vec![0.0; taps * 2],
+            pos: 0,
+            power: 0.0,
+            reference_peak: 0.0,
+            // Falls to ~5% over one tail length
+            peak_decay: (-3.0 / taps as f32).exp(),
+            hold_samples: (sample_rate as f32 * DOUBLE_TALK_HOLD_SECS) as usize,
+            hold: 0,
+        }
+    }
+
+    /// Remove the echo of `reference` from one `mic` sample
+    pub fn process(&mut self, mic: f32, reference: f32) -> f32 {
+        self.pos = (self.pos + 1) % self.taps;
+        let dropped = self.history[self.pos];
+        self.history[self.pos] = reference;
+        self.history[self.pos + self.taps] = reference;
+        self.reference_peak = reference.abs().max(self.reference_peak * self.peak_decay);
+
+        let window = &self.history[self.pos + 1..self.pos + 1 + self.taps];
+        self.power = if self.pos == 0 {
+            // Re-sum once per wrap so rounding errors don't accumulate
+            window.iter().map(|x| x * x).sum()
+        } else {
+            (self.power + reference * reference - dropped * dropped).max(0.0)
+        };
+        let estimate: f32 = self.weights.iter().zip(window).map(|(w, x)| w * x).sum();
+        let error = mic - estimate;
+
+        if mic.abs() > DOUBLE_TALK_RATIO * self.reference_peak {
+            self.hold = self.hold_samples;
+        } else {
+            self.hold = self.hold.saturating_sub(1);
+        }
+
+        if self.hold == 0 && self.power > f32::EPSILON {
+            let gain = STEP_SIZE * error / (self.power + 1e-6);
+            for (w, x) in self.weights.iter_mut().zip(window) {
+                *w += gain * x;
+            }
+        }
+
+        error
+    }
+}
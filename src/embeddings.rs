@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+use crate::api_error::ApiError;
+use crate::http;
+use crate::openai_api::ApiEndpoint;
+use crate::retry::RetryPolicy;
+
+/// OpenAI's cheapest embedding model
+pub const DEFAULT_MODEL: &str = "text-embedding-3-small";
+
+/// Base URL of Ollama's OpenAI-compatible API on this machine
+pub const DEFAULT_LOCAL_URL: &str = "http://localhost:11434/v1";
+
+/// An embedding model Ollama pulls with `ollama pull nomic-embed-text`
+pub const DEFAULT_LOCAL_MODEL: &str = "nomic-embed-text";
+
+/// Segments embedded in one request
+pub const BATCH_SIZE: usize = 64;
+
+/// Vectors are appended here, one JSON object per line
+const INDEX_FILE: &str = "segments.jsonl";
+
+/// Where segment embeddings come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum EmbeddingBackend {
+    /// OpenAI's embeddings API, with the chat endpoint's URL and key
+    #[default]
+    OpenAi,
+    /// A server on your own machine or network with an OpenAI-compatible
+    /// `embeddings` route, such as Ollama; nothing leaves it
+    Local,
+}
+
+impl EmbeddingBackend {
+    pub const ALL: [EmbeddingBackend; 2] = [EmbeddingBackend::OpenAi, EmbeddingBackend::Local];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EmbeddingBackend::OpenAi => "OpenAI",
+            EmbeddingBackend::Local => "Local server (Ollama)",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+    #[serde(default)]
+    usage: Option<EmbeddingUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingUsage {
+    prompt_tokens: u64,
+}
+
+/// Turns text into vectors that lie close together when the texts mean
+/// similar things
+pub struct Embedder {
+    endpoint: ApiEndpoint,
+    client: Client,
+    model: String,
+    retry: RetryPolicy,
+}
+
+impl Embedder {
+    pub fn new(endpoint: ApiEndpoint, model: String, retry: RetryPolicy) -> Self {
+        Self {
+            endpoint,
+            client: http::client(),
+            model,
+            retry,
+        }
+    }
+
+    /// One vector per text, in order, and the tokens billed when the API
+    /// reports them
+    pub async fn embed(&self, texts: &[String]) -> Result<(Vec<Vec<f32>>, Option<u64>)> {
+        let request = EmbeddingRequest {
+            model: &self.model,
+            input: texts,
+        };
+        let (response, _) = self
+            .retry
+            .send("Embedding request", || {
+                self.endpoint
+                    .post(&self.client, "embeddings")
+                    .json(&request)
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(ApiError::from_response("Embedding request", response)
+                .await
+                .into());
+        }
+
+        let mut response: EmbeddingResponse = response
+            .json()
+            .await
+            .context("Failed to parse embedding response")?;
+        if response.data.len() != texts.len() {
+            anyhow::bail!(
+                "Asked for {} embeddings, got {}",
+                texts.len(),
+                response.data.len()
+            );
+        }
+        response.data.sort_by_key(|data| data.index);
+        let vectors = response
+            .data
+            .into_iter()
+            .map(|data| data.embedding)
+            .collect();
+        Ok((vectors, response.usage.map(|usage| usage.prompt_tokens)))
+    }
+}
+
+/// A segment's text and its vector, as kept on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddedSegment {
+    pub audio_file: PathBuf,
+    /// Vectors of different models can't be compared, so each is kept
+    /// with the model that made it
+    pub model: String,
+    pub recorded_at: chrono::DateTime<chrono::Utc>,
+    /// The text that was embedded, shown in search results
+    pub text: String,
+    pub vector: Vec<f32>,
+}
+
+/// The embedded segments of every session, loaded at startup and searched
+/// in memory
+pub struct EmbeddingIndex {
+    segments: Vec<EmbeddedSegment>,
+    /// Position in `segments` by audio file and model
+    positions: HashMap<(PathBuf, String), usize>,
+}
+
+impl EmbeddingIndex {
+    /// The index saved in `dir`; lines that don't parse are skipped, and a
+    /// segment embedded again replaces its earlier line
+    pub fn load(dir: &Path) -> Self {
+        let mut index = Self {
+            segments: Vec::new(),
+            positions: HashMap::new(),
+        };
+        let Ok(contents) = std::fs::read_to_string(dir.join(INDEX_FILE)) else {
+            return index;
+        };
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(segment) => index.insert(segment),
+                Err(e) => eprintln!("Skipping embedding index line: {}", e),
+            }
+        }
+        println!("Loaded {} segment embeddings", index.segments.len());
+        index
+    }
+
+    pub fn insert(&mut self, segment: EmbeddedSegment) {
+        let key = (segment.audio_file.clone(), segment.model.clone());
+        match self.positions.get(&key) {
+            Some(&position) => self.segments[position] = segment,
+            None => {
+                self.positions.insert(key, self.segments.len());
+                self.segments.push(segment);
+            }
+        }
+    }
+
+    /// Whether `model` has embedded exactly this `text` of the segment
+    pub fn is_current(&self, audio_file: &Path, model: &str, text: &str) -> bool {
+        self.positions
+            .get(&(audio_file.to_path_buf(), model.to_string()))
+            .is_some_and(|&position| self.segments[position].text == text)
+    }
+
+    /// Segments embedded with `model`
+    pub fn count(&self, model: &str) -> usize {
+        self.segments.iter().filter(|s| s.model == model).count()
+    }
+
+    /// The `limit` segments embedded with `model` closest in meaning to
+    /// `query`, best first, with their cosine similarity
+    pub fn search(&self, model: &str, query: &[f32], limit: usize) -> Vec<(f32, EmbeddedSegment)> {
+        let mut hits: Vec<(f32, &EmbeddedSegment)> = self
+            .segments
+            .iter()
+            .filter(|segment| segment.model == model)
+            .map(|segment| (cosine_similarity(query, &segment.vector), segment))
+            .collect();
+        hits.sort_by(|a, b| b.0.total_cmp(&a.0));
+        hits.into_iter()
+            .take(limit)
+            .map(|(score, segment)| (score, segment.clone()))
+            .collect()
+    }
+}
+
+/// Add `segments` to the index file in `dir`
+pub async fn append(dir: &Path, segments: &[EmbeddedSegment]) -> Result<()> {
+    let mut lines = String::new();
+    for segment in segments {
+        lines.push_str(&serde_json::to_string(segment)?);
+        lines.push('\n');
+    }
+    tokio::fs::create_dir_all(dir).await?;
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(INDEX_FILE))
+        .await?;
+    file.write_all(lines.as_bytes()).await?;
+    Ok(())
+}
+
+/// From -1 (opposite) to 1 (same direction); 0 for vectors of different
+/// lengths
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
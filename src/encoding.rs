@@ -0,0 +1,591 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::Command;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::resample::LinearResampler;
+
+/// Container/codec used for audio chunks on disk and for upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    #[default]
+    Wav,
+    Flac,
+    Opus,
+}
+
+impl AudioFormat {
+    pub const ALL: [AudioFormat; 3] = [AudioFormat::Wav, AudioFormat::Flac, AudioFormat::Opus];
+
+    /// File extension for chunks in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Opus => "ogg",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "WAV (uncompressed)",
+            AudioFormat::Flac => "FLAC (lossless)",
+            AudioFormat::Opus => "Ogg/Opus (requires opusenc)",
+        }
+    }
+}
+
+/// Sample encoding for WAV files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WavSampleFormat {
+    #[default]
+    Int16,
+    Int24,
+    Float32,
+}
+
+impl WavSampleFormat {
+    pub const ALL: [WavSampleFormat; 3] = [
+        WavSampleFormat::Int16,
+        WavSampleFormat::Int24,
+        WavSampleFormat::Float32,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WavSampleFormat::Int16 => "16-bit integer",
+            WavSampleFormat::Int24 => "24-bit integer",
+            WavSampleFormat::Float32 => "32-bit float",
+        }
+    }
+
+    pub fn bits_per_sample(&self) -> u16 {
+        match self {
+            WavSampleFormat::Int16 => 16,
+            WavSampleFormat::Int24 => 24,
+            WavSampleFormat::Float32 => 32,
+        }
+    }
+
+    /// Size of the header hound writes; anything but 16-bit PCM gets the
+    /// longer WAVE_FORMAT_EXTENSIBLE fmt chunk
+    pub fn header_bytes(&self) -> u64 {
+        match self {
+            WavSampleFormat::Int16 => 44,
+            WavSampleFormat::Int24 | WavSampleFormat::Float32 => 68,
+        }
+    }
+
+    pub fn spec(&self, sample_rate: u32) -> hound::WavSpec {
+        hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: self.bits_per_sample(),
+            sample_format: match self {
+                WavSampleFormat::Float32 => hound::SampleFormat::Float,
+                _ => hound::SampleFormat::Int,
+            },
+        }
+    }
+
+    /// Write one f32 sample (-1.0 to 1.0) in this format
+    pub fn write_sample<W>(
+        &self,
+        writer: &mut hound::WavWriter<W>,
+        sample: f32,
+    ) -> hound::Result<()>
+    where
+        W: std::io::Write + std::io::Seek,
+    {
+        match self {
+            WavSampleFormat::Int16 => writer.write_sample((sample * i16::MAX as f32) as i16),
+            WavSampleFormat::Int24 => {
+                writer.write_sample((sample.clamp(-1.0, 1.0) * I24_MAX as f32) as i32)
+            }
+            WavSampleFormat::Float32 => writer.write_sample(sample),
+        }
+    }
+}
+
+const I24_MAX: i32 = (1 << 23) - 1;
+
+/// Samples per FLAC frame
+const FLAC_BLOCK_SIZE: usize = 4096;
+const FLAC_BITS_PER_SAMPLE: u32 = 16;
+
+/// Encode 16-bit mono samples as a FLAC file.
+///
+/// Each frame uses the best of FLAC's fixed linear predictors (orders 0-4)
+/// with Rice-coded residuals, which typically halves the size of speech audio.
+pub fn write_flac_file(path: &Path, samples: &[i16], sample_rate: u32) -> Result<()> {
+    let stream = samples
+        .iter()
+        .map(|&sample| Ok::<_, std::convert::Infallible>(sample));
+    write_flac_stream(path, samples.len() as u64, sample_rate, stream)
+}
+
+/// Encode `total_samples` 16-bit mono samples read from `samples` as a
+/// FLAC file, a block at a time, so hours of audio aren't held in memory
+pub fn write_flac_stream<I, E>(
+    path: &Path,
+    total_samples: u64,
+    sample_rate: u32,
+    samples: I,
+) -> Result<()>
+where
+    I: IntoIterator<Item = std::result::Result<i16, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let file = fs::File::create(path).context("Failed to create FLAC file")?;
+    let mut out = BufWriter::new(file);
+    out.write_all(b"fLaC")?;
+
+    // STREAMINFO metadata block, flagged as the last metadata block
+    let mut info = BitWriter::new();
+    info.write(1, 1);
+    info.write(0, 7);
+    info.write(34, 24);
+    info.write(FLAC_BLOCK_SIZE as u64, 16);
+    info.write(FLAC_BLOCK_SIZE as u64, 16);
+    info.write(0, 24); // min frame size unknown
+    info.write(0, 24); // max frame size unknown
+    info.write(sample_rate as u64, 20);
+    info.write(0, 3); // channels - 1
+    info.write((FLAC_BITS_PER_SAMPLE - 1) as u64, 5);
+    info.write(total_samples, 36);
+    for _ in 0..16 {
+        info.write(0, 8); // MD5 not computed
+    }
+    out.write_all(&info.into_bytes())?;
+
+    let mut samples = samples.into_iter();
+    let mut block = Vec::with_capacity(FLAC_BLOCK_SIZE);
+    let mut frame_number = 0;
+    let mut written = 0;
+    loop {
+        block.clear();
+        for sample in samples.by_ref().take(FLAC_BLOCK_SIZE) {
+            block.push(sample?);
+        }
+        if block.is_empty() {
+            break;
+        }
+        encode_flac_frame(&mut out, &block, frame_number).context("Failed to write FLAC file")?;
+        frame_number += 1;
+        written += block.len() as u64;
+    }
+    // STREAMINFO was written with the count, so a short read would lie
+    if written != total_samples {
+        anyhow::bail!(
+            "Expected {} samples for the FLAC file, got {}",
+            total_samples,
+            written
+        );
+    }
+
+    out.flush().context("Failed to write FLAC file")?;
+    Ok(())
+}
+
+fn encode_flac_frame(
+    out: &mut impl Write,
+    block: &[i16],
+    frame_number: u64,
+) -> std::io::Result<()> {
+    let mut frame = BitWriter::new();
+
+    // Frame header
+    frame.write(0b11_1111_1111_1110, 14); // sync code
+    frame.write(0, 1);
+    frame.write(0, 1); // fixed block size
+    frame.write(0b0111, 4); // block size stored as 16 bits after the header
+    frame.write(0b0000, 4); // sample rate taken from STREAMINFO
+    frame.write(0b0000, 4); // mono
+    frame.write(0b100, 3); // 16 bits per sample
+    frame.write(0, 1);
+    frame.write_utf8_number(frame_number);
+    frame.write((block.len() - 1) as u64, 16);
+    let crc = crc8(frame.bytes());
+    frame.write(crc as u64, 8);
+
+    // Subframe
+    let samples: Vec<i32> = block.iter().map(|&s| s as i32).collect();
+    let (order, residuals) = best_fixed_prediction(&samples);
+    let rice_param = best_rice_parameter(&residuals);
+    let fixed_bits =
+        order as u64 * FLAC_BITS_PER_SAMPLE as u64 + 10 + rice_bits(&residuals, rice_param);
+    let verbatim_bits = samples.len() as u64 * FLAC_BITS_PER_SAMPLE as u64;
+
+    if fixed_bits < verbatim_bits {
+        frame.write(0, 1);
+        frame.write(0b001000 | order as u64, 6);
+        frame.write(0, 1); // no wasted bits
+        for &warmup in &samples[..order] {
+            frame.write_signed(warmup, FLAC_BITS_PER_SAMPLE);
+        }
+        frame.write(0b00, 2); // Rice coding with 4-bit parameters
+        frame.write(0, 4); // single partition
+        frame.write(rice_param as u64, 4);
+        for &residual in &residuals {
+            frame.write_rice(residual, rice_param);
+        }
+    } else {
+        frame.write(0, 1);
+        frame.write(0b000001, 6);
+        frame.write(0, 1);
+        for &sample in &samples {
+            frame.write_signed(sample, FLAC_BITS_PER_SAMPLE);
+        }
+    }
+
+    frame.align();
+    let crc = crc16(frame.bytes());
+    frame.write(crc as u64, 16);
+    out.write_all(&frame.into_bytes())
+}
+
+/// Residuals of the fixed predictor order (0-4) with the smallest total magnitude
+fn best_fixed_prediction(samples: &[i32]) -> (usize, Vec<i32>) {
+    let max_order = 4.min(samples.len().saturating_sub(1));
+
+    (0..=max_order)
+        .map(|order| {
+            let residuals: Vec<i32> = (order..samples.len())
+                .map(|i| {
+                    let s = |k: usize| samples[i - k];
+                    let prediction = match order {
+                        0 => 0,
+                        1 => s(1),
+                        2 => 2 * s(1) - s(2),
+                        3 => 3 * s(1) - 3 * s(2) + s(3),
+                        _ => 4 * s(1) - 6 * s(2) + 4 * s(3) - s(4),
+                    };
+                    s(0) - prediction
+                })
+                .collect();
+            (order, residuals)
+        })
+        .min_by_key(|(_, residuals)| {
+            residuals
+                .iter()
+                .map(|r| r.unsigned_abs() as u64)
+                .sum::<u64>()
+        })
+        .unwrap_or((0, Vec::new()))
+}
+
+fn best_rice_parameter(residuals: &[i32]) -> u32 {
+    (0..15)
+        .min_by_key(|&k| rice_bits(residuals, k))
+        .unwrap_or(0)
+}
+
+fn rice_bits(residuals: &[i32], k: u32) -> u64 {
+    residuals
+        .iter()
+        .map(|&r| (zigzag(r) >> k) as u64 + 1 + k as u64)
+        .sum()
+}
+
+fn zigzag(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// MSB-first bit writer for FLAC headers and subframes
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u8,
+    filled: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            current: 0,
+            filled: 0,
+        }
+    }
+
+    fn write(&mut self, value: u64, bits: u32) {
+        for i in (0..bits).rev() {
+            self.current = (self.current << 1) | ((value >> i) & 1) as u8;
+            self.filled += 1;
+            if self.filled == 8 {
+                self.bytes.push(self.current);
+                self.current = 0;
+                self.filled = 0;
+            }
+        }
+    }
+
+    fn write_signed(&mut self, value: i32, bits: u32) {
+        self.write(value as u64 & ((1 << bits) - 1), bits);
+    }
+
+    fn write_rice(&mut self, value: i32, k: u32) {
+        let folded = zigzag(value);
+        for _ in 0..(folded >> k) {
+            self.write(0, 1);
+        }
+        self.write(1, 1);
+        self.write(folded as u64, k);
+    }
+
+    /// Frame numbers use the same variable-length scheme as UTF-8
+    fn write_utf8_number(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write(value, 8);
+            return;
+        }
+
+        let mut continuation = Vec::new();
+        let mut rest = value;
+        let mut first_bits = 6;
+        while rest >= (1 << first_bits) {
+            continuation.push(0x80 | (rest & 0x3F));
+            rest >>= 6;
+            first_bits -= 1;
+        }
+
+        let len = continuation.len() + 1;
+        let prefix = (0xFFu64 << (8 - len)) & 0xFF;
+        self.write(prefix | rest, 8);
+        for byte in continuation.into_iter().rev() {
+            self.write(byte, 8);
+        }
+    }
+
+    /// Pad with zero bits to the next byte boundary
+    fn align(&mut self) {
+        while self.filled != 0 {
+            self.write(0, 1);
+        }
+    }
+
+    /// Bytes written so far (only complete bytes)
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+/// Encode a WAV file to Ogg/Opus with the `opusenc` command-line tool
+pub fn encode_opus_file(wav_path: &Path, output_path: &Path) -> Result<()> {
+    let status = Command::new("opusenc")
+        .args(["--quiet", "--bitrate", "24", "--speech"])
+        .arg(wav_path)
+        .arg(output_path)
+        .status()
+        .context("Failed to run opusenc. Install opus-tools or choose WAV/FLAC output.")?;
+
+    if !status.success() {
+        anyhow::bail!("opusenc exited with status {}", status);
+    }
+    Ok(())
+}
+
+/// Decode the `opusenc` output written for Opus chunks back to WAV
+pub fn decode_opus_file(opus_path: &Path, wav_path: &Path) -> Result<()> {
+    let status = Command::new("opusdec")
+        .args(["--quiet", "--rate", "48000"])
+        .arg(opus_path)
+        .arg(wav_path)
+        .status()
+        .context("Failed to run opusdec. Install opus-tools to play Opus chunks.")?;
+
+    if !status.success() {
+        anyhow::bail!("opusdec exited with status {}", status);
+    }
+    Ok(())
+}
+
+/// Decode an audio file to mono f32 samples, returning them with the sample rate.
+///
+/// WAV, FLAC, MP3 and Ogg/Vorbis are decoded in-process; Ogg/Opus chunks go
+/// through `opusdec` since symphonia has no Opus decoder.
+pub fn read_audio_file(path: &Path) -> Result<(Vec<f32>, u32)> {
+    let is_ogg = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ogg") || ext.eq_ignore_ascii_case("opus"));
+
+    match decode_with_symphonia(path) {
+        Ok(decoded) => Ok(decoded),
+        Err(e) if is_ogg => {
+            let wav_path = path.with_extension("decoded.wav");
+            decode_opus_file(path, &wav_path).with_context(|| format!("{}", e))?;
+            let result = decode_with_symphonia(&wav_path);
+            let _ = fs::remove_file(&wav_path);
+            result
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Decode an audio file to mono samples at `sample_rate`
+pub fn read_audio_resampled(path: &Path, sample_rate: u32) -> Result<Vec<f32>> {
+    let (samples, source_rate) = read_audio_file(path)?;
+    let mut resampled =
+        Vec::with_capacity(samples.len() * sample_rate as usize / source_rate.max(1) as usize + 1);
+    LinearResampler::new(source_rate, sample_rate).process(&samples, &mut resampled);
+    Ok(resampled)
+}
+
+/// Write mono `samples` as 16-bit WAV, the input local engines and speech
+/// APIs read most reliably
+pub fn write_speech_wav<W>(samples: &[f32], sample_rate: u32, writer: W) -> Result<()>
+where
+    W: std::io::Write + std::io::Seek,
+{
+    let format = WavSampleFormat::Int16;
+    let mut writer = hound::WavWriter::new(writer, format.spec(sample_rate))?;
+    for &sample in samples {
+        format.write_sample(&mut writer, sample.clamp(-1.0, 1.0))?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Length of an audio file in seconds, from its header when the container
+/// records it, so long files aren't decoded just to be measured
+pub fn audio_duration(path: &Path) -> Result<f64> {
+    let file = fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let from_header = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()
+        .and_then(|probed| {
+            let params = &probed.format.default_track()?.codec_params;
+            Some(params.n_frames? as f64 / params.sample_rate?.max(1) as f64)
+        });
+    match from_header {
+        Some(secs) => Ok(secs),
+        None => {
+            let (samples, sample_rate) = read_audio_file(path)?;
+            Ok(samples.len() as f64 / sample_rate.max(1) as f64)
+        }
+    }
+}
+
+fn decode_with_symphonia(path: &Path) -> Result<(Vec<f32>, u32)> {
+    let file = fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .context("Unsupported audio format")?;
+    let mut format = probed.format;
+
+    let track = format
+        .default_track()
+        .context("Audio file has no playable track")?;
+    let track_id = track.id;
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .context("Audio file has no sample rate")?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .context("Unsupported audio codec")?;
+
+    let mut samples = Vec::new();
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Skip corrupt packets rather than failing the whole file
+            Err(DecodeError::DecodeError(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let buffer =
+            buffer.get_or_insert_with(|| SampleBuffer::new(decoded.capacity() as u64, spec));
+        if buffer.capacity() < decoded.capacity() * channels {
+            *buffer = SampleBuffer::new(decoded.capacity() as u64, spec);
+        }
+        buffer.copy_interleaved_ref(decoded);
+
+        // Average interleaved frames down to mono
+        for frame in buffer.samples().chunks(channels) {
+            samples.push(frame.iter().sum::<f32>() / frame.len() as f32);
+        }
+    }
+
+    Ok((samples, sample_rate))
+}
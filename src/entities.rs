@@ -0,0 +1,157 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::summarization::{self, Summarizer, TokenUsage};
+use crate::transcription::TranscriptionResult;
+
+const INSTRUCTIONS: &str = "You list the people, organizations (companies, customers, teams), \
+products and dates (deadlines, days, times) mentioned in a stretch of a conversation \
+transcript. Reply with JSON only, in this format:
+
+{\"entities\": [{\"name\": \"as written in the transcript\", \"kind\": \"person\" | \
+\"organization\" | \"product\" | \"date\"}]}
+
+Use an empty array when nothing is mentioned. Leave out speaker labels such as \"Speaker 1\" or \
+\"Me\" unless the text itself mentions them.";
+
+/// What kind of thing an entity is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Person,
+    Organization,
+    Product,
+    Date,
+}
+
+impl EntityKind {
+    pub const ALL: [EntityKind; 4] = [
+        EntityKind::Person,
+        EntityKind::Organization,
+        EntityKind::Product,
+        EntityKind::Date,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntityKind::Person => "People",
+            EntityKind::Organization => "Organizations",
+            EntityKind::Product => "Products",
+            EntityKind::Date => "Dates",
+        }
+    }
+}
+
+/// Someone or something a segment mentions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entity {
+    pub name: String,
+    pub kind: EntityKind,
+}
+
+impl Entity {
+    /// The name in lower case, so "Acme Corp" and "acme corp" are one entity
+    pub fn key(&self) -> String {
+        key(&self.name)
+    }
+}
+
+fn key(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// An entity across the transcript
+#[derive(Debug, Clone)]
+pub struct IndexEntry {
+    pub entity: Entity,
+    /// Times the name appears in the text, at least one per segment that
+    /// mentions it
+    pub mentions: usize,
+    /// Segments that mention it
+    pub segments: usize,
+}
+
+/// List the entities mentioned in `text` with the chat model, returning the
+/// tokens spent along with them
+pub async fn extract(chat: &Summarizer, text: &str) -> Result<(Vec<Entity>, Option<TokenUsage>)> {
+    #[derive(Deserialize)]
+    struct Found {
+        #[serde(default)]
+        entities: Vec<FoundEntity>,
+    }
+
+    #[derive(Deserialize)]
+    struct FoundEntity {
+        name: String,
+        kind: String,
+    }
+
+    let reply = chat
+        .complete("Entity request", INSTRUCTIONS, text.to_string(), 0.0)
+        .await?;
+    let found: Found = summarization::parse_json(&reply.content)?;
+
+    let mut entities: Vec<Entity> = Vec::new();
+    for found in found.entities {
+        let kind = match found.kind.trim().to_lowercase().as_str() {
+            "person" => EntityKind::Person,
+            "organization" | "organisation" | "company" => EntityKind::Organization,
+            "product" => EntityKind::Product,
+            "date" | "time" => EntityKind::Date,
+            _ => continue,
+        };
+        let entity = Entity {
+            name: found.name.trim().to_string(),
+            kind,
+        };
+        if !entity.name.is_empty() && !entities.iter().any(|e| e.key() == entity.key()) {
+            entities.push(entity);
+        }
+    }
+    Ok((entities, reply.usage))
+}
+
+/// Whether `segment` mentions the entity with this key
+pub fn mentions(segment: &TranscriptionResult, entity_key: &str) -> bool {
+    segment
+        .entities
+        .iter()
+        .flatten()
+        .any(|entity| entity.key() == entity_key)
+}
+
+/// The entities of `segments`, most mentioned first, each under the name
+/// and kind it was first found with
+pub fn index<'a>(segments: impl IntoIterator<Item = &'a TranscriptionResult>) -> Vec<IndexEntry> {
+    let mut index: Vec<IndexEntry> = Vec::new();
+    for segment in segments {
+        let text = key(&segment.text);
+        for entity in segment.entities.iter().flatten() {
+            let entity_key = entity.key();
+            let mentions = text.matches(entity_key.as_str()).count().max(1);
+            match index
+                .iter_mut()
+                .find(|entry| entry.entity.key() == entity_key)
+            {
+                Some(entry) => {
+                    entry.mentions += mentions;
+                    entry.segments += 1;
+                }
+                None => index.push(IndexEntry {
+                    entity: entity.clone(),
+                    mentions,
+                    segments: 1,
+                }),
+            }
+        }
+    }
+    index.sort_by(|a, b| {
+        b.mentions
+            .cmp(&a.mentions)
+            .then_with(|| a.entity.name.cmp(&b.entity.name))
+    });
+    index
+}
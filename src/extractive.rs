@@ -0,0 +1,247 @@
+use anyhow::Result;
+use std::collections::HashSet;
+
+use crate::summarization::{ActionItem, SummaryResult};
+
+/// Shown as the model of summaries written here
+pub const MODEL: &str = "extractive (offline)";
+
+/// Sentences kept for the summary, at least and at most
+const MIN_SENTENCES: usize = 3;
+const MAX_SENTENCES: usize = 8;
+
+/// Action items picked out at most
+const MAX_ACTION_ITEMS: usize = 10;
+
+/// Sentences shorter than this many words say too little to rank
+const MIN_WORDS: usize = 4;
+
+/// PageRank's damping factor, and when to stop iterating
+const DAMPING: f64 = 0.85;
+const MAX_ITERATIONS: usize = 50;
+const CONVERGED: f64 = 1e-4;
+
+/// Words too common to tell sentences apart
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "all", "also", "and", "any", "are", "because", "been", "but", "can",
+    "could", "did", "does", "doing", "don't", "for", "from", "going", "got", "had", "has", "have",
+    "how", "i'm", "into", "it's", "its", "just", "know", "like", "mean", "more", "not", "now",
+    "okay", "one", "our", "out", "really", "right", "say", "see", "should", "some", "so", "that",
+    "that's", "the", "their", "them", "then", "there", "these", "they", "thing", "think", "this",
+    "those", "want", "was", "way", "well", "were", "what", "when", "where", "which", "who", "will",
+    "with", "would", "yeah", "yes", "you", "your",
+];
+
+/// Said by whoever takes the task on
+const FIRST_PERSON_CUES: &[&str] = &["i'll ", "i will ", "i'm going to ", "i can take "];
+
+/// Said of a task without naming who does it
+const TASK_CUES: &[&str] = &[
+    "we'll ",
+    "we will ",
+    "we need to ",
+    "we should ",
+    "need to ",
+    "let's ",
+    "action item",
+    "follow up",
+    "make sure ",
+    "to do:",
+    "todo",
+];
+
+/// A sentence of the transcript and who said it, when labeled
+struct Sentence {
+    speaker: Option<String>,
+    text: String,
+    words: HashSet<String>,
+}
+
+impl Sentence {
+    fn labeled(&self) -> String {
+        match &self.speaker {
+            Some(speaker) => format!("{}: {}", speaker, self.text),
+            None => self.text.clone(),
+        }
+    }
+}
+
+/// Summarize the transcript `segments` without a language model: the
+/// sentences most central to it (TextRank), in the order they were said,
+/// and sentences that sound like tasks as action items. Runs on a blocking
+/// thread, as long transcripts compare many sentence pairs.
+pub async fn summarize(segments: Vec<String>) -> Result<SummaryResult> {
+    Ok(tokio::task::spawn_blocking(move || extract(&segments)).await?)
+}
+
+fn extract(segments: &[String]) -> SummaryResult {
+    let sentences = sentences(segments);
+    let scores = rank(&sentences);
+
+    let count = (sentences.len() / 10).clamp(MIN_SENTENCES, MAX_SENTENCES);
+    let mut best: Vec<usize> = (0..sentences.len()).collect();
+    best.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+    best.truncate(count);
+    best.sort_unstable();
+    let mut summary = best
+        .iter()
+        .map(|&i| sentences[i].labeled())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if summary.is_empty() {
+        summary = "Too little was said to summarize.".to_string();
+    }
+
+    println!(
+        "Extractive summary of {} sentences from {}",
+        best.len(),
+        sentences.len()
+    );
+    SummaryResult {
+        summary,
+        action_items: action_items(&sentences),
+        minutes: None,
+        speakers: None,
+        talk_time: Vec::new(),
+        analysis: None,
+        original_text: segments.join("\n\n"),
+        timestamp: chrono::Utc::now(),
+        retries: 0,
+        model: MODEL.to_string(),
+        temperature: None,
+        usage: None,
+        title: None,
+        tags: Vec::new(),
+        glossary: Vec::new(),
+    }
+}
+
+/// The sentences of `segments`, one line at a time so "Speaker 1: ..."
+/// labels stay with what they said
+fn sentences(segments: &[String]) -> Vec<Sentence> {
+    let mut sentences = Vec::new();
+    for line in segments.iter().flat_map(|segment| segment.lines()) {
+        let (speaker, text) = split_label(line);
+        let mut start = 0;
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        for (i, &(at, c)) in chars.iter().enumerate() {
+            let ends = matches!(c, '.' | '!' | '?')
+                && chars
+                    .get(i + 1)
+                    .is_none_or(|&(_, next)| next.is_whitespace());
+            if ends || i + 1 == chars.len() {
+                let end = at + c.len_utf8();
+                push_sentence(&mut sentences, speaker, &text[start..end]);
+                start = end;
+            }
+        }
+    }
+    sentences
+}
+
+fn push_sentence(sentences: &mut Vec<Sentence>, speaker: Option<&str>, text: &str) {
+    let text = text.trim();
+    if text.split_whitespace().count() < MIN_WORDS {
+        return;
+    }
+    let words = text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|word| word.trim_matches('\'').to_lowercase())
+        .filter(|word| word.chars().count() > 2 && !STOPWORDS.contains(&word.as_str()))
+        .collect();
+    sentences.push(Sentence {
+        speaker: speaker.map(str::to_string),
+        text: text.to_string(),
+        words,
+    });
+}
+
+/// "Me · Speaker 2: hello" as the label and the text, when the line has a
+/// short label before a colon
+fn split_label(line: &str) -> (Option<&str>, &str) {
+    match line.split_once(": ") {
+        Some((label, text))
+            if !label.is_empty()
+                && label.chars().count() <= 40
+                && !label.contains(['.', '!', '?']) =>
+        {
+            (Some(label.trim()), text)
+        }
+        _ => (None, line),
+    }
+}
+
+/// TextRank: each sentence's PageRank in the graph of sentences linked by
+/// the words they share
+fn rank(sentences: &[Sentence]) -> Vec<f64> {
+    let n = sentences.len();
+    let mut weights: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let similarity = similarity(&sentences[i], &sentences[j]);
+            if similarity > 0.0 {
+                weights[i].push((j, similarity));
+                weights[j].push((i, similarity));
+            }
+        }
+    }
+    let totals: Vec<f64> = weights
+        .iter()
+        .map(|links| links.iter().map(|(_, w)| w).sum())
+        .collect();
+
+    let mut scores = vec![1.0; n];
+    for _ in 0..MAX_ITERATIONS {
+        let mut next = vec![1.0 - DAMPING; n];
+        for (i, links) in weights.iter().enumerate() {
+            for &(j, weight) in links {
+                next[i] += DAMPING * weight / totals[j] * scores[j];
+            }
+        }
+        let change: f64 = next.iter().zip(&scores).map(|(a, b)| (a - b).abs()).sum();
+        scores = next;
+        if change < CONVERGED {
+            break;
+        }
+    }
+    scores
+}
+
+/// Shared words over the log of the sentences' lengths, as in the TextRank
+/// paper, so long sentences don't win by length alone
+fn similarity(a: &Sentence, b: &Sentence) -> f64 {
+    let shared = a.words.intersection(&b.words).count();
+    let norm = (a.words.len() as f64).ln() + (b.words.len() as f64).ln();
+    if shared == 0 || norm <= 0.0 {
+        return 0.0;
+    }
+    shared as f64 / norm
+}
+
+/// Sentences that sound like someone taking on a task or the group
+/// agreeing one; first-person ones are owned by their speaker
+fn action_items(sentences: &[Sentence]) -> Vec<ActionItem> {
+    let mut items: Vec<ActionItem> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    for sentence in sentences {
+        let lower = format!(" {}", sentence.text.to_lowercase());
+        let first_person = FIRST_PERSON_CUES
+            .iter()
+            .any(|cue| lower.contains(&format!(" {}", cue)));
+        if !first_person && !TASK_CUES.iter().any(|cue| lower.contains(cue)) {
+            continue;
+        }
+        let item = ActionItem {
+            task: sentence.text.clone(),
+            owner: sentence.speaker.clone().filter(|_| first_person),
+            ..Default::default()
+        };
+        if seen.insert(item.key()) {
+            items.push(item);
+        }
+        if items.len() == MAX_ACTION_ITEMS {
+            break;
+        }
+    }
+    items
+}
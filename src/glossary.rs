@@ -0,0 +1,72 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::summarization::{self, Summarizer, TokenUsage};
+
+/// Characters of transcript sent for a glossary, about 15,000 tokens;
+/// terms usually come up early and again later
+const MAX_TRANSCRIPT_CHARS: usize = 60_000;
+
+const INSTRUCTIONS: &str = "You write a glossary for someone new to a team who reads the \
+transcript of one of its conversations, such as a meeting. List the acronyms, abbreviations, \
+jargon, internal project or tool names and other domain terms it uses that an outsider might \
+not know. Reply with JSON only, in this format:
+
+{\"terms\": [{\"term\": \"as written in the transcript\", \"explanation\": \"one short \
+sentence\"}]}
+
+Spell out acronyms in the explanation. When the transcript shows what a term means here, \
+explain it that way; otherwise give its usual meaning. Leave out everyday words and the names \
+of people. Use an empty array when there are no such terms.";
+
+/// A term used in the conversation and what it means
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlossaryTerm {
+    pub term: String,
+    pub explanation: String,
+}
+
+/// The acronyms and domain terms of `transcript`, explained by the chat
+/// model and sorted alphabetically, with the tokens spent on them
+pub async fn generate(
+    chat: &Summarizer,
+    transcript: &str,
+) -> Result<(Vec<GlossaryTerm>, Option<TokenUsage>)> {
+    #[derive(Deserialize)]
+    struct Found {
+        #[serde(default)]
+        terms: Vec<GlossaryTerm>,
+    }
+
+    let transcript: String = transcript.chars().take(MAX_TRANSCRIPT_CHARS).collect();
+    let reply = chat
+        .complete("Glossary request", INSTRUCTIONS, transcript, 0.0)
+        .await?;
+    let found: Found = summarization::parse_json(&reply.content)?;
+
+    let mut terms: Vec<GlossaryTerm> = Vec::new();
+    for found in found.terms {
+        let term = GlossaryTerm {
+            term: found.term.trim().to_string(),
+            explanation: found.explanation.trim().to_string(),
+        };
+        if !term.term.is_empty()
+            && !term.explanation.is_empty()
+            && !terms
+                .iter()
+                .any(|t| t.term.eq_ignore_ascii_case(&term.term))
+        {
+            terms.push(term);
+        }
+    }
+    terms.sort_by_key(|t| t.term.to_lowercase());
+    Ok((terms, reply.usage))
+}
+
+/// `terms` as a Markdown list, for exports
+pub fn to_markdown(terms: &[GlossaryTerm]) -> String {
+    terms
+        .iter()
+        .map(|t| format!("- **{}**: {}\n", t.term, t.explanation))
+        .collect()
+}
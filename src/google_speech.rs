@@ -0,0 +1,272 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::api_error::ApiError;
+use crate::encoding;
+use crate::http;
+use crate::retry::RetryPolicy;
+use crate::transcription::{
+    BilledAudio, TimedSegment, Transcript, TranscriptionContext, TranscriptionProvider, WordTiming,
+    normalize_language,
+};
+
+pub const API_BASE: &str = "https://speech.googleapis.com/v1";
+
+/// Audio is sent as 16 kHz mono 16-bit PCM
+const SAMPLE_RATE: u32 = 16_000;
+
+/// Synchronous recognition takes at most a minute of audio per request, so
+/// longer chunks are sent in pieces of this length
+const MAX_REQUEST_SECS: usize = 55;
+
+/// Recognition model for long-form audio such as meetings
+pub const MODEL: &str = "latest_long";
+
+/// Used when no language is forced; Google can't detect it by itself
+const DEFAULT_LANGUAGE: &str = "en-US";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecognizeRequest<'a> {
+    config: RecognitionConfig<'a>,
+    audio: RecognitionAudio,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecognitionConfig<'a> {
+    encoding: &'static str,
+    sample_rate_hertz: u32,
+    language_code: &'a str,
+    model: &'static str,
+    enable_automatic_punctuation: bool,
+    enable_word_time_offsets: bool,
+    /// Custom vocabulary to favour
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    speech_contexts: Vec<SpeechContext<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct SpeechContext<'a> {
+    phrases: &'a [String],
+}
+
+#[derive(Debug, Serialize)]
+struct RecognitionAudio {
+    /// Base64-encoded WAV
+    content: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecognizeResponse {
+    #[serde(default)]
+    results: Vec<RecognitionResult>,
+    /// Audio billed for, e.g. "15s"
+    total_billed_time: Option<String>,
+}
+
+/// One consecutive stretch of the audio
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecognitionResult {
+    #[serde(default)]
+    alternatives: Vec<Alternative>,
+    /// Where the stretch ends, e.g. "12.340s"
+    result_end_time: Option<String>,
+    language_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Alternative {
+    #[serde(default)]
+    transcript: String,
+    /// 0–1
+    confidence: Option<f64>,
+    #[serde(default)]
+    words: Vec<ApiWord>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiWord {
+    word: String,
+    start_time: String,
+    end_time: String,
+}
+
+/// Google Cloud Speech-to-Text, authenticated with an API key.
+///
+/// Each chunk is converted to 16 kHz WAV and recognized synchronously with
+/// the long-form model; the language has to be set, as Google doesn't
+/// detect it.
+pub struct GoogleSpeech {
+    api_key: String,
+    /// BCP-47 or ISO-639-1 code to transcribe as, or `None` for English
+    language: Option<String>,
+    retry: RetryPolicy,
+    client: Client,
+}
+
+impl GoogleSpeech {
+    pub fn new(api_key: String, language: Option<String>, retry: RetryPolicy) -> Self {
+        Self {
+            api_key,
+            language,
+            retry,
+            client: http::client(),
+        }
+    }
+
+    async fn recognize(
+        &self,
+        samples: &[f32],
+        vocabulary: &[String],
+        retries: &mut u32,
+    ) -> Result<RecognizeResponse> {
+        let mut wav = std::io::Cursor::new(Vec::new());
+        encoding::write_speech_wav(samples, SAMPLE_RATE, &mut wav)?;
+        let request = RecognizeRequest {
+            config: RecognitionConfig {
+                encoding: "LINEAR16",
+                sample_rate_hertz: SAMPLE_RATE,
+                language_code: self.language.as_deref().unwrap_or(DEFAULT_LANGUAGE),
+                model: MODEL,
+                enable_automatic_punctuation: true,
+                enable_word_time_offsets: true,
+                speech_contexts: if vocabulary.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![SpeechContext {
+                        phrases: vocabulary,
+                    }]
+                },
+            },
+            audio: RecognitionAudio {
+                content: BASE64.encode(wav.into_inner()),
+            },
+        };
+
+        let (response, retried) = self
+            .retry
+            .send("Google Speech-to-Text request", || {
+                self.client
+                    .post(format!("{}/speech:recognize", API_BASE))
+                    .query(&[("key", &self.api_key)])
+                    .json(&request)
+            })
+            .await?;
+        *retries += retried;
+
+        if !response.status().is_success() {
+            return Err(
+                ApiError::from_response("Google Speech-to-Text request", response)
+                    .await
+                    .into(),
+            );
+        }
+        response
+            .json()
+            .await
+            .context("Failed to parse Google Speech-to-Text response")
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for GoogleSpeech {
+    fn name(&self) -> &'static str {
+        "Google Speech-to-Text"
+    }
+
+    /// Google has no free-form prompt, so only the vocabulary is used
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        let path = audio_file.to_path_buf();
+        let samples =
+            tokio::task::spawn_blocking(move || encoding::read_audio_resampled(&path, SAMPLE_RATE))
+                .await??;
+
+        let mut transcript = Transcript::default();
+        let mut texts = Vec::new();
+        let mut billed_secs = 0.0;
+        let piece_len = MAX_REQUEST_SECS * SAMPLE_RATE as usize;
+        for (index, piece) in samples.chunks(piece_len).enumerate() {
+            let offset = (index * MAX_REQUEST_SECS) as f64;
+            let response = self
+                .recognize(piece, &context.vocabulary, &mut transcript.retries)
+                .await?;
+            billed_secs += response
+                .total_billed_time
+                .as_deref()
+                .and_then(parse_duration)
+                .unwrap_or(piece.len() as f64 / SAMPLE_RATE as f64);
+
+            let mut end_secs = offset;
+            for result in response.results {
+                // Each result runs from where the previous one ended
+                let start_secs = end_secs;
+                end_secs = result
+                    .result_end_time
+                    .as_deref()
+                    .and_then(parse_duration)
+                    .map_or(start_secs, |end| offset + end);
+                if transcript.language.is_none() {
+                    transcript.language = result.language_code.as_deref().map(normalize_language);
+                }
+                // The first alternative is the most likely
+                let Some(best) = result.alternatives.into_iter().next() else {
+                    continue;
+                };
+                let text = best.transcript.trim().to_string();
+                if !text.is_empty() {
+                    texts.push(text.clone());
+                    transcript.segments.push(TimedSegment {
+                        text,
+                        start_secs,
+                        end_secs,
+                        avg_logprob: best.confidence.filter(|c| *c > 0.0).map(f64::ln),
+                        no_speech_prob: None,
+                    });
+                }
+                for word in best.words {
+                    let text = word
+                        .word
+                        .trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')
+                        .to_string();
+                    if let (false, Some(start), Some(end)) = (
+                        text.is_empty(),
+                        parse_duration(&word.start_time),
+                        parse_duration(&word.end_time),
+                    ) {
+                        transcript.words.push(WordTiming {
+                            word: text,
+                            start_secs: offset + start,
+                            end_secs: offset + end,
+                        });
+                    }
+                }
+            }
+        }
+
+        transcript.text = texts.join(" ");
+        transcript.language = transcript.language.or_else(|| self.language.clone());
+        transcript.billed = Some(BilledAudio {
+            model: format!("google-{}", MODEL),
+            secs: billed_secs,
+        });
+        Ok(transcript)
+    }
+}
+
+/// "1.500s" → 1.5
+fn parse_duration(value: &str) -> Option<f64> {
+    value.trim().strip_suffix('s')?.parse().ok()
+}
@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Audio file extensions accepted by the Whisper API
+const AUDIO_EXTENSIONS: [&str; 10] = [
+    "wav", "flac", "mp3", "m4a", "mp4", "mpeg", "mpga", "ogg", "opus", "webm",
+];
+
+/// Progress of a single imported file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportStatus {
+    Queued,
+    Transcribing,
+    Summarizing,
+    Done,
+    Failed(String),
+}
+
+impl ImportStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, ImportStatus::Done | ImportStatus::Failed(_))
+    }
+}
+
+/// An audio file queued for transcription and summarization
+#[derive(Debug, Clone)]
+pub struct ImportJob {
+    /// Stable identifier used to route progress updates
+    pub id: usize,
+    pub path: PathBuf,
+    pub status: ImportStatus,
+    pub transcript: Option<String>,
+    pub summary: Option<String>,
+}
+
+impl ImportJob {
+    pub fn new(id: usize, path: PathBuf) -> Self {
+        Self {
+            id,
+            path,
+            status: ImportStatus::Queued,
+            transcript: None,
+            summary: None,
+        }
+    }
+}
+
+/// List the audio files directly inside `dir`, sorted by name
+pub fn scan_audio_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read folder {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && is_audio_file(path))
+        .collect();
+
+    files.sort();
+    Ok(files)
+}
+
+/// Whether the path has an extension Whisper can transcribe
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| {
+            AUDIO_EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(ext))
+        })
+}
@@ -0,0 +1,82 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::JoinHandle;
+
+type Job<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Runs jobs with at most `limit` in flight, starting them in the order they
+/// were submitted and delivering their outputs in that same order.
+///
+/// A job that finishes early waits for the ones submitted before it, so a
+/// slow upload holds back later results but never reorders them.
+pub struct OrderedQueue<T> {
+    jobs: mpsc::UnboundedSender<Job<T>>,
+    slots: Arc<Semaphore>,
+    limit: usize,
+}
+
+impl<T: Send + 'static> OrderedQueue<T> {
+    /// Start the queue on the current tokio runtime, sending outputs to `output`
+    pub fn new(limit: usize, output: Sender<T>) -> Self {
+        let limit = limit.max(1);
+        let slots = Arc::new(Semaphore::new(limit));
+        let (jobs, mut job_rx) = mpsc::unbounded_channel::<Job<T>>();
+        let (started_tx, mut started_rx) = mpsc::unbounded_channel::<JoinHandle<T>>();
+
+        // Start jobs one after another as slots free up
+        let dispatch_slots = Arc::clone(&slots);
+        tokio::spawn(async move {
+            while let Some(job) = job_rx.recv().await {
+                let Ok(permit) = Arc::clone(&dispatch_slots).acquire_owned().await else {
+                    break;
+                };
+                let handle = tokio::spawn(async move {
+                    let result = job.await;
+                    drop(permit);
+                    result
+                });
+                if started_tx.send(handle).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Deliver outputs in submission order
+        tokio::spawn(async move {
+            while let Some(handle) = started_rx.recv().await {
+                match handle.await {
+                    Ok(result) => {
+                        if output.send(result).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => eprintln!("Queued job failed: {}", e),
+                }
+            }
+        });
+
+        Self { jobs, slots, limit }
+    }
+
+    /// Queue a job behind the ones already submitted
+    pub fn submit<F>(&self, job: F)
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        if self.jobs.send(Box::pin(job)).is_err() {
+            eprintln!("Job queue has shut down; dropping job");
+        }
+    }
+
+    /// Jobs running right now
+    pub fn in_flight(&self) -> usize {
+        self.limit - self.slots.available_permits()
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::chunk_writer;
+use crate::encoding;
+use crate::http;
+use crate::transcription::{
+    self, Transcript, TranscriptionContext, TranscriptionProvider, normalize_language,
+};
+
+/// Sample rate whisper.cpp expects its input at
+const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// Where the ggml models for whisper.cpp are published
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Download progress is reported at most once per this many bytes
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+/// whisper.cpp model size; larger models are more accurate but slower
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WhisperModel {
+    Tiny,
+    #[default]
+    Base,
+    Small,
+    Medium,
+    Large,
+}
+
+impl WhisperModel {
+    pub const ALL: [WhisperModel; 5] = [
+        WhisperModel::Tiny,
+        WhisperModel::Base,
+        WhisperModel::Small,
+        WhisperModel::Medium,
+        WhisperModel::Large,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WhisperModel::Tiny => "Tiny (75 MB)",
+            WhisperModel::Base => "Base (142 MB)",
+            WhisperModel::Small => "Small (466 MB)",
+            WhisperModel::Medium => "Medium (1.5 GB)",
+            WhisperModel::Large => "Large v3 (2.9 GB)",
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            WhisperModel::Tiny => "ggml-tiny.bin",
+            WhisperModel::Base => "ggml-base.bin",
+            WhisperModel::Small => "ggml-small.bin",
+            WhisperModel::Medium => "ggml-medium.bin",
+            WhisperModel::Large => "ggml-large-v3.bin",
+        }
+    }
+
+    /// Where the model lives once downloaded into `models_dir`
+    pub fn path(&self, models_dir: &Path) -> PathBuf {
+        models_dir.join(self.file_name())
+    }
+
+    pub fn is_downloaded(&self, models_dir: &Path) -> bool {
+        self.path(models_dir).is_file()
+    }
+}
+
+/// Download `model` into `models_dir`, calling `progress` with the bytes
+/// received so far and the total size when the server reports it
+pub async fn download_model<F>(
+    model: WhisperModel,
+    models_dir: &Path,
+    progress: F,
+) -> Result<PathBuf>
+where
+    F: Fn(u64, Option<u64>),
+{
+    tokio::fs::create_dir_all(models_dir)
+        .await
+        .with_context(|| format!("Failed to create {:?}", models_dir))?;
+
+    let url = format!("{}/{}", MODEL_BASE_URL, model.file_name());
+    println!("Downloading whisper model from {}", url);
+    let mut response = http::client()
+        .get(&url)
+        .send()
+        .await
+        .context("Failed to start model download")?
+        .error_for_status()
+        .context("Model download failed")?;
+    let total = response.content_length();
+
+    // Written under a temporary name so an interrupted download is never
+    // mistaken for a usable model
+    let path = model.path(models_dir);
+    let partial = chunk_writer::partial_path(&path);
+    let mut file = tokio::fs::File::create(&partial).await?;
+    let mut received = 0;
+    let mut reported = 0;
+    while let Some(bytes) = response
+        .chunk()
+        .await
+        .context("Model download interrupted")?
+    {
+        file.write_all(&bytes).await?;
+        received += bytes.len() as u64;
+        if received - reported >= PROGRESS_STEP {
+            reported = received;
+            progress(received, total);
+        }
+    }
+    file.flush().await?;
+    drop(file);
+
+    tokio::fs::rename(&partial, &path).await?;
+    progress(received, total);
+    println!("Whisper model saved to {:?}", path);
+    Ok(path)
+}
+
+/// The part of whisper.cpp's `--output-json` file that says which language
+/// it transcribed
+#[derive(Debug, Deserialize)]
+struct JsonOutput {
+    result: Option<JsonResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonResult {
+    language: Option<String>,
+}
+
+/// Transcribes on this machine with the whisper.cpp command-line program, so
+/// audio never leaves the computer
+#[derive(Debug, Clone)]
+pub struct LocalWhisper {
+    /// `whisper-cli`, or `main` in older whisper.cpp builds
+    program: String,
+    model_path: PathBuf,
+    /// ISO-639-1 code to transcribe as, or `None` to auto-detect
+    language: Option<String>,
+    /// Translate to English with `--translate`
+    translate: bool,
+}
+
+impl LocalWhisper {
+    pub fn new(
+        program: String,
+        model_path: PathBuf,
+        language: Option<String>,
+        translate: bool,
+    ) -> Self {
+        Self {
+            program,
+            model_path,
+            language,
+            translate,
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for LocalWhisper {
+    fn name(&self) -> &'static str {
+        "whisper.cpp"
+    }
+
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        if !self.model_path.is_file() {
+            anyhow::bail!("Whisper model {:?} is not downloaded yet", self.model_path);
+        }
+
+        // whisper.cpp only reads 16 kHz WAV reliably, whatever the chunk format
+        let stem = transcription::temp_file_stem("whisper", audio_file);
+        let temp = |suffix: &str| std::env::temp_dir().join(format!("{}{}", stem, suffix));
+        // --output-file takes the name without the extension
+        let (base, input, json_path) = (temp(""), temp(".wav"), temp(".json"));
+        let (source, target) = (audio_file.to_path_buf(), input.clone());
+        tokio::task::spawn_blocking(move || write_whisper_input(&source, &target)).await??;
+
+        let mut command = Command::new(&self.program);
+        command
+            .arg("--model")
+            .arg(&self.model_path)
+            .arg("--file")
+            .arg(&input)
+            .arg("--language")
+            .arg(self.language.as_deref().unwrap_or("auto"))
+            .args(["--no-timestamps", "--no-prints", "--output-json"])
+            .arg("--output-file")
+            .arg(&base)
+            // A cancelled transcription stops whisper.cpp rather than
+            // leaving it running in the background
+            .kill_on_drop(true);
+        if self.translate {
+            command.arg("--translate");
+        }
+        if let Some(prompt) = context.prompt() {
+            command.arg("--prompt").arg(prompt);
+        }
+        let output = command.output().await;
+        let _ = tokio::fs::remove_file(&input).await;
+        let json = tokio::fs::read_to_string(&json_path).await;
+        let _ = tokio::fs::remove_file(&json_path).await;
+
+        let output = output.with_context(|| {
+            format!(
+                "Failed to run {}. Install whisper.cpp or set the path to whisper-cli.",
+                self.program
+            )
+        })?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "{} exited with status {}: {}",
+                self.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        // One line per segment
+        let text = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        // The language whisper.cpp detected is only reported in its log,
+        // which --no-prints silences, and in the JSON output
+        let detected = json
+            .ok()
+            .and_then(|json| serde_json::from_str::<JsonOutput>(&json).ok())
+            .and_then(|output| output.result?.language)
+            .filter(|language| !language.is_empty() && language != "auto");
+        Ok(Transcript {
+            text,
+            language: self
+                .language
+                .clone()
+                .or_else(|| detected.as_deref().map(normalize_language)),
+            translated: self.translate,
+            ..Default::default()
+        })
+    }
+}
+
+/// Decode a chunk in any supported format to 16 kHz mono 16-bit WAV
+fn write_whisper_input(audio_file: &Path, wav_path: &Path) -> Result<()> {
+    let samples = encoding::read_audio_resampled(audio_file, WHISPER_SAMPLE_RATE)?;
+    let file = std::fs::File::create(wav_path)
+        .with_context(|| format!("Failed to write audio to {:?}", wav_path))?;
+    encoding::write_speech_wav(&samples, WHISPER_SAMPLE_RATE, std::io::BufWriter::new(file))
+}
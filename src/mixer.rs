@@ -0,0 +1,82 @@
+use rtrb::Consumer;
+use std::collections::VecDeque;
+
+use crate::echo::EchoCanceller;
+
+/// How far one stream may run ahead of the other before the lagging one is
+/// assumed silent (WASAPI loopback delivers nothing while nothing plays)
+const MAX_LAG_SECS: f32 = 0.5;
+
+/// Mixes a microphone stream into the main (system audio) stream.
+///
+/// The two devices run on separate clocks and deliver in bursts, so samples
+/// are queued until both sides have them. With echo cancellation, the main
+/// stream is the reference for removing speaker audio picked up by the mic.
+pub struct MicMixer {
+    mic: Consumer<f32>,
+    mic_pending: VecDeque<f32>,
+    main_pending: VecDeque<f32>,
+    max_lag: usize,
+    canceller: Option<EchoCanceller>,
+}
+
+impl MicMixer {
+    pub fn new(mic: Consumer<f32>, sample_rate: u32, echo_cancellation: bool) -> Self {
+        Self {
+            mic,
+            mic_pending: VecDeque::new(),
+            main_pending: VecDeque::new(),
+            max_lag: (sample_rate as f32 * MAX_LAG_SECS) as usize,
+            canceller: echo_cancellation.then(|| EchoCanceller::new(sample_rate)),
+        }
+    }
+
+    /// Queue newly captured main-stream samples and append whatever can be
+    /// mixed with the microphone so far to `out`
+    pub fn mix(&mut self, main: &[f32], out: &mut Vec<f32>) {
+        self.main_pending.extend(main);
+        let available = self.mic.slots();
+        if let Ok(chunk) = self.mic.read_chunk(available) {
+            self.mic_pending.extend(chunk);
+        }
+
+        // A stream that has fallen too far behind gets silence for the gap,
+        // keeping the newest samples of both sides aligned
+        if self.main_pending.len() > self.mic_pending.len() + self.max_lag {
+            pad_front(&mut self.mic_pending, self.main_pending.len());
+        } else if self.mic_pending.len() > self.main_pending.len() + self.max_lag {
+            pad_front(&mut self.main_pending, self.mic_pending.len());
+        }
+
+        let ready = self.main_pending.len().min(self.mic_pending.len());
+        self.mix_pending(ready, out);
+    }
+
+    /// Mix everything still queued, padding the shorter side with silence
+    pub fn finish(mut self, out: &mut Vec<f32>) {
+        let ready = self.main_pending.len().max(self.mic_pending.len());
+        pad_front(&mut self.main_pending, ready);
+        pad_front(&mut self.mic_pending, ready);
+        self.mix_pending(ready, out);
+    }
+
+    fn mix_pending(&mut self, count: usize, out: &mut Vec<f32>) {
+        for (main, mic) in self
+            .main_pending
+            .drain(..count)
+            .zip(self.mic_pending.drain(..count))
+        {
+            let mic = match self.canceller.as_mut() {
+                Some(canceller) => canceller.process(mic, main),
+                None => mic,
+            };
+            out.push((main + mic).clamp(-1.0, 1.0));
+        }
+    }
+}
+
+fn pad_front(queue: &mut VecDeque<f32>, len: usize) {
+    while queue.len() < len {
+        queue.push_front(0.0);
+    }
+}
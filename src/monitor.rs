@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use cpal::traits::DeviceTrait;
+use cpal::{Device, SampleFormat, Stream, StreamConfig};
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::resample::LinearResampler;
+
+/// Audio queued before the monitor starts playing, absorbing the capture
+/// pipeline's 100ms delivery bursts
+const PREBUFFER_SECS: f32 = 0.15;
+
+/// Most audio the monitor queues; anything beyond is dropped so latency
+/// can't build up
+const MAX_BUFFER_SECS: f32 = 0.5;
+
+/// Feeding half of an input monitor, used from the capture pipeline
+pub struct MonitorFeed {
+    producer: Producer<f32>,
+    resampler: LinearResampler,
+    channels: usize,
+    mono: Vec<f32>,
+    resampled: Vec<f32>,
+}
+
+impl MonitorFeed {
+    /// Queue captured samples (interleaved if `channels` > 1) for playback
+    pub fn push(&mut self, samples: &[f32]) {
+        self.mono.clear();
+        for frame in samples.chunks(self.channels) {
+            self.mono
+                .push(frame.iter().sum::<f32>() / frame.len() as f32);
+        }
+
+        self.resampled.clear();
+        self.resampler.process(&self.mono, &mut self.resampled);
+
+        // Keep the newest audio when more arrives than fits (e.g. pre-roll)
+        let writable = self.producer.slots().min(self.resampled.len());
+        let newest = &self.resampled[self.resampled.len() - writable..];
+        if let Ok(chunk) = self.producer.write_chunk_uninit(writable) {
+            chunk.fill_from_iter(newest.iter().copied());
+        }
+    }
+}
+
+/// Open an output stream on `device` that plays whatever is pushed into the
+/// returned feed. `volume` holds the gain as f32 bits and may change live.
+pub fn open(
+    device: &Device,
+    sample_rate: u32,
+    channels: usize,
+    volume: Arc<AtomicU32>,
+) -> Result<(Stream, MonitorFeed)> {
+    let supported = device
+        .default_output_config()
+        .context("Failed to get monitor output config")?;
+    let config: StreamConfig = supported.config();
+    let output_rate = config.sample_rate.0;
+
+    let (producer, consumer) =
+        RingBuffer::<f32>::new((output_rate as f32 * MAX_BUFFER_SECS) as usize);
+    let prebuffer = (output_rate as f32 * PREBUFFER_SECS) as usize;
+
+    let stream = match supported.sample_format() {
+        SampleFormat::I8 => build_output::<i8>(device, &config, consumer, prebuffer, volume)?,
+        SampleFormat::I16 => build_output::<i16>(device, &config, consumer, prebuffer, volume)?,
+        SampleFormat::I32 => build_output::<i32>(device, &config, consumer, prebuffer, volume)?,
+        SampleFormat::U8 => build_output::<u8>(device, &config, consumer, prebuffer, volume)?,
+        SampleFormat::U16 => build_output::<u16>(device, &config, consumer, prebuffer, volume)?,
+        SampleFormat::F32 => build_output::<f32>(device, &config, consumer, prebuffer, volume)?,
+        SampleFormat::F64 => build_output::<f64>(device, &config, consumer, prebuffer, volume)?,
+        format => anyhow::bail!("Unsupported monitor sample format: {:?}", format),
+    };
+
+    let feed = MonitorFeed {
+        producer,
+        resampler: LinearResampler::new(sample_rate, output_rate),
+        channels: channels.max(1),
+        mono: Vec::new(),
+        resampled: Vec::new(),
+    };
+
+    Ok((stream, feed))
+}
+
+/// Build an output stream that plays queued mono audio on every channel
+fn build_output<T>(
+    device: &Device,
+    config: &StreamConfig,
+    mut consumer: Consumer<f32>,
+    prebuffer: usize,
+    volume: Arc<AtomicU32>,
+) -> Result<Stream>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let mut primed = false;
+
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            // Wait for a little audio to queue up again after running dry
+            if !primed && consumer.slots() >= prebuffer {
+                primed = true;
+            }
+
+            let gain = f32::from_bits(volume.load(Ordering::Relaxed));
+            for frame in data.chunks_mut(channels) {
+                let value = if primed {
+                    match consumer.pop() {
+                        Ok(sample) => sample * gain,
+                        Err(_) => {
+                            primed = false;
+                            0.0
+                        }
+                    }
+                } else {
+                    0.0
+                };
+                for out in frame.iter_mut() {
+                    *out = T::from_sample(value);
+                }
+            }
+        },
+        |err| eprintln!("Monitor stream error: {}", err),
+        None,
+    )?;
+
+    Ok(stream)
+}
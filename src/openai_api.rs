@@ -0,0 +1,69 @@
+use reqwest::{Client, Method, RequestBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Base URL of the public OpenAI API
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// How requests carry the API key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthHeader {
+    /// `Authorization: Bearer <key>` (OpenAI, LiteLLM and most proxies)
+    #[default]
+    Bearer,
+    /// `api-key: <key>` (Azure OpenAI)
+    ApiKey,
+}
+
+impl AuthHeader {
+    pub const ALL: [AuthHeader; 2] = [AuthHeader::Bearer, AuthHeader::ApiKey];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuthHeader::Bearer => "Authorization: Bearer",
+            AuthHeader::ApiKey => "api-key (Azure)",
+        }
+    }
+}
+
+/// Where OpenAI-compatible requests go: api.openai.com, an Azure OpenAI
+/// deployment or a proxy such as LiteLLM
+#[derive(Debug, Clone)]
+pub struct ApiEndpoint {
+    /// URL the operation path is appended to, e.g.
+    /// `https://my-resource.openai.azure.com/openai/deployments/whisper`
+    pub base_url: String,
+    /// Sent as the `api-version` query parameter when set (required by Azure)
+    pub api_version: String,
+    pub auth: AuthHeader,
+    pub api_key: String,
+}
+
+impl ApiEndpoint {
+    /// Start a POST to `path` (e.g. "chat/completions") with the URL and
+    /// authentication this endpoint needs
+    pub fn post(&self, client: &Client, path: &str) -> RequestBuilder {
+        self.request(client, Method::POST, path)
+    }
+
+    /// Start a GET to `path` (e.g. "models")
+    pub fn get(&self, client: &Client, path: &str) -> RequestBuilder {
+        self.request(client, Method::GET, path)
+    }
+
+    fn request(&self, client: &Client, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/{}", self.base_url.trim_end_matches('/'), path);
+        let mut request = client.request(method, url);
+        if !self.api_version.is_empty() {
+            request = request.query(&[("api-version", &self.api_version)]);
+        }
+        // Local proxies may not need a key at all
+        if self.api_key.is_empty() {
+            return request;
+        }
+        match self.auth {
+            AuthHeader::Bearer => request.bearer_auth(&self.api_key),
+            AuthHeader::ApiKey => request.header("api-key", &self.api_key),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+use crate::transcription::TranscriptionResult;
+
+/// Kinds of personal data the scrubber looks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PiiKind {
+    Email,
+    Phone,
+    CreditCard,
+    /// A match of one of the user's `pii_patterns`
+    Custom,
+}
+
+impl PiiKind {
+    /// What the match is replaced with in text that leaves the machine
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            PiiKind::Email => "[EMAIL]",
+            PiiKind::Phone => "[PHONE]",
+            PiiKind::CreditCard => "[CARD]",
+            PiiKind::Custom => "[PII]",
+        }
+    }
+}
+
+/// Personal data found in a text, as a byte range
+#[derive(Debug, Clone)]
+pub struct PiiMatch {
+    pub range: Range<usize>,
+    pub kind: PiiKind,
+}
+
+/// Finds emails, phone numbers, card numbers and custom patterns in
+/// transcript text so they can be replaced before the text is summarized or
+/// exported. The transcript itself keeps the original.
+#[derive(Debug, Clone)]
+pub struct PiiScrubber {
+    email: Regex,
+    phone: Regex,
+    card: Regex,
+    custom: Vec<Regex>,
+}
+
+impl PiiScrubber {
+    /// A scrubber for the built-in kinds plus `patterns` (case-insensitive
+    /// regular expressions)
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let custom = patterns
+            .iter()
+            .map(|pattern| pattern.trim())
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("Invalid PII pattern '{}'", pattern))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            email: Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b")?,
+            phone: Regex::new(
+                r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?)?\d{2,4}(?:[ .-]?\d{2,4}){1,4}\b",
+            )?,
+            card: Regex::new(r"\b(?:\d[ -]?){12,18}\d\b")?,
+            custom,
+        })
+    }
+
+    /// Personal data in `text`, in order and without overlaps
+    pub fn find(&self, text: &str) -> Vec<PiiMatch> {
+        let mut found: Vec<PiiMatch> = Vec::new();
+        let mut add = |regex: &Regex, kind: PiiKind, accept: fn(&str) -> bool| {
+            for m in regex.find_iter(text) {
+                if accept(m.as_str()) && (kind == PiiKind::Custom || stands_alone(text, m.range()))
+                {
+                    found.push(PiiMatch {
+                        range: m.range(),
+                        kind,
+                    });
+                }
+            }
+        };
+        // Earlier kinds win where matches overlap
+        for regex in &self.custom {
+            add(regex, PiiKind::Custom, |_| true);
+        }
+        add(&self.email, PiiKind::Email, |_| true);
+        add(&self.card, PiiKind::CreditCard, passes_luhn);
+        add(&self.phone, PiiKind::Phone, looks_like_phone);
+
+        let mut kept: Vec<PiiMatch> = Vec::new();
+        for m in found {
+            if !kept
+                .iter()
+                .any(|k| k.range.start < m.range.end && m.range.start < k.range.end)
+            {
+                kept.push(m);
+            }
+        }
+        kept.sort_by_key(|m| m.range.start);
+        kept
+    }
+
+    /// `text` with its personal data replaced by placeholders ("[EMAIL]")
+    pub fn scrub(&self, text: &str) -> String {
+        let mut scrubbed = String::with_capacity(text.len());
+        let mut last = 0;
+        for m in self.find(text) {
+            scrubbed.push_str(&text[last..m.range.start]);
+            scrubbed.push_str(m.kind.placeholder());
+            last = m.range.end;
+        }
+        scrubbed.push_str(&text[last..]);
+        scrubbed
+    }
+
+    /// A copy of `result` fit to leave the machine: its text, utterances
+    /// and phrases scrubbed. Word timings stay for subtitle timing; exports
+    /// take their words from the text.
+    pub fn scrub_result(&self, result: &TranscriptionResult) -> TranscriptionResult {
+        let mut result = result.clone();
+        result.text = self.scrub(&result.text);
+        for utterance in &mut result.utterances {
+            utterance.text = self.scrub(&utterance.text);
+        }
+        for segment in &mut result.segments {
+            segment.text = self.scrub(&segment.text);
+        }
+        result.revisions.clear();
+        result
+    }
+}
+
+/// Whether a match isn't just part of a longer token, such as the middle of
+/// an ISBN or a version number
+fn stands_alone(text: &str, range: Range<usize>) -> bool {
+    let before = text[..range.start].chars().next_back();
+    let mut after = text[range.end..].chars();
+    let joined = |c: char| c.is_alphanumeric() || matches!(c, '-' | '@' | '_');
+    !before.is_some_and(|c| joined(c) || c == '.')
+        && !after.next().is_some_and(|c| {
+            joined(c) || (c == '.' && after.next().is_some_and(|c| c.is_ascii_digit()))
+        })
+}
+
+/// Card numbers carry a Luhn check digit, which rules out most other long
+/// numbers
+fn passes_luhn(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2 == 1, d * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => d,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// A phone number has 7–15 digits and is written like one: with a country
+/// code, an area code in brackets, dashes or dots, or as one long run of
+/// digits. Plain numbers separated by spaces ("2023 2024") are left alone.
+fn looks_like_phone(number: &str) -> bool {
+    let digits = number.chars().filter(char::is_ascii_digit).count();
+    if !(7..=15).contains(&digits) {
+        return false;
+    }
+    number.contains(['+', '(', '-', '.']) || (digits >= 10 && !number.contains(' '))
+}
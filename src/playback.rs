@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, SampleFormat, Stream, StreamConfig};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::encoding;
+use crate::resample::LinearResampler;
+
+/// Extra time allowed past the expected end before playback is abandoned
+const PLAYBACK_GRACE: Duration = Duration::from_secs(2);
+
+/// Play mono samples through the default output device, blocking until done
+pub fn play_samples(samples: &[f32], sample_rate: u32) -> Result<()> {
+    play_samples_until(samples, sample_rate, &AtomicBool::new(false))
+}
+
+/// Decode and play an audio file from `start` on, stopping early once `stop`
+/// is set
+pub fn play_file(path: &Path, start: Duration, stop: &AtomicBool) -> Result<()> {
+    let (samples, sample_rate) = encoding::read_audio_file(path)?;
+    let skip = ((start.as_secs_f64() * sample_rate as f64) as usize).min(samples.len());
+    play_samples_until(&samples[skip..], sample_rate, stop)
+}
+
+/// Play mono samples, blocking until they finish or `stop` is set
+fn play_samples_until(samples: &[f32], sample_rate: u32, stop: &AtomicBool) -> Result<()> {
+    let host = cpal::default_host();
+    let device = host
+        .default_output_device()
+        .context("No default output device found for playback")?;
+    let supported = device
+        .default_output_config()
+        .context("Failed to get output device config")?;
+    let config: StreamConfig = supported.config();
+
+    let mut resampled = Vec::with_capacity(samples.len());
+    LinearResampler::new(sample_rate, config.sample_rate.0).process(samples, &mut resampled);
+    let expected = Duration::from_secs_f64(resampled.len() as f64 / config.sample_rate.0 as f64);
+
+    let finished = Arc::new(AtomicBool::new(false));
+    let done = Arc::clone(&finished);
+    let stream = match supported.sample_format() {
+        SampleFormat::I8 => build_output::<i8>(&device, &config, resampled, done)?,
+        SampleFormat::I16 => build_output::<i16>(&device, &config, resampled, done)?,
+        SampleFormat::I32 => build_output::<i32>(&device, &config, resampled, done)?,
+        SampleFormat::U8 => build_output::<u8>(&device, &config, resampled, done)?,
+        SampleFormat::U16 => build_output::<u16>(&device, &config, resampled, done)?,
+        SampleFormat::F32 => build_output::<f32>(&device, &config, resampled, done)?,
+        SampleFormat::F64 => build_output::<f64>(&device, &config, resampled, done)?,
+        format => anyhow::bail!("Unsupported output sample format: {:?}", format),
+    };
+    stream.play()?;
+
+    let started = Instant::now();
+    while !finished.load(Ordering::SeqCst) {
+        if stop.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        if started.elapsed() > expected + PLAYBACK_GRACE {
+            anyhow::bail!("Playback did not finish; the output device may be unavailable");
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    // Let the device play out what is already buffered
+    thread::sleep(Duration::from_millis(200));
+    Ok(())
+}
+
+/// Build an output stream that plays `samples` on every channel, then silence
+fn build_output<T>(
+    device: &Device,
+    config: &StreamConfig,
+    samples: Vec<f32>,
+    finished: Arc<AtomicBool>,
+) -> Result<Stream>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let mut position = 0;
+
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            for frame in data.chunks_mut(channels) {
+                let value = samples.get(position).copied().unwrap_or(0.0);
+                position += 1;
+                for out in frame.iter_mut() {
+                    *out = T::from_sample(value);
+                }
+            }
+
+            if position >= samples.len() {
+                finished.store(true, Ordering::SeqCst);
+            }
+        },
+        |err| eprintln!("Playback stream error: {}", err),
+        None,
+    )?;
+
+    Ok(stream)
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The presets that come with the app, kept as data in the same format as
+/// `analysis_presets` in the config file
+const BUILT_IN: &str = include_str!("analysis_presets.json");
+
+/// Keys every summary already has
+const RESERVED_KEYS: [&str; 2] = ["summary", "action_items"];
+
+/// A kind of meeting to analyze: the instructions for the model and the
+/// sections it fills in besides the summary and action items
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisPreset {
+    /// Kept in the config and in summaries written with it
+    pub id: String,
+    pub name: String,
+    /// One line for the preset picker
+    #[serde(default)]
+    pub description: String,
+    /// What the model is and what it writes; the JSON format is added
+    pub instructions: String,
+    pub sections: Vec<PresetSection>,
+}
+
+/// A list the model fills in, such as "Blockers"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetSection {
+    /// Its field in the model's JSON: lowercase letters, digits and `_`
+    pub key: String,
+    pub heading: String,
+    /// What one entry holds, shown to the model
+    pub description: String,
+    /// Shown as a numbered list rather than bullets
+    #[serde(default)]
+    pub numbered: bool,
+}
+
+impl AnalysisPreset {
+    /// The instructions with the JSON format the answer takes, summary
+    /// first
+    pub fn prompt(&self) -> String {
+        let mut format = String::from("{\n  \"summary\": \"Brief summary...\",\n");
+        for section in &self.sections {
+            format.push_str(&format!(
+                "  \"{}\": [\"{}\", ...],\n",
+                section.key, section.description
+            ));
+        }
+        format.push_str(
+            "  \"action_items\": [{\"task\": \"What to do\", \"owner\": \"Name or null\", \"due\": \"When or null\"}, ...]\n}",
+        );
+        format!(
+            "{}\n\nAlso extract the action items, each with who takes it on and by when if the \
+             conversation says. Return the result in the following JSON format, with the summary \
+             first:\n\n{}\n\nUse empty arrays for sections with nothing in them. Do not invent \
+             owners or dates.",
+            self.instructions.trim(),
+            format
+        )
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.id.trim().is_empty() || self.name.trim().is_empty() {
+            anyhow::bail!("Every analysis preset needs an id and a name");
+        }
+        let mut keys: Vec<&str> = Vec::new();
+        for section in &self.sections {
+            let key = section.key.as_str();
+            if key.is_empty()
+                || !key
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            {
+                anyhow::bail!(
+                    "Section key '{}' of preset '{}' may only have lowercase letters, digits \
+                     and underscores",
+                    key,
+                    self.id
+                );
+            }
+            if RESERVED_KEYS.contains(&key) || keys.contains(&key) {
+                anyhow::bail!("Section key '{}' of preset '{}' is taken", key, self.id);
+            }
+            keys.push(key);
+        }
+        Ok(())
+    }
+}
+
+/// The built-in presets followed by `custom` ones; a custom preset with a
+/// built-in's id replaces it
+pub fn all(custom: &[AnalysisPreset]) -> Vec<AnalysisPreset> {
+    let mut presets: Vec<AnalysisPreset> =
+        serde_json::from_str(BUILT_IN).expect("built-in analysis presets are valid JSON");
+    for preset in custom {
+        match presets.iter_mut().find(|p| p.id == preset.id) {
+            Some(built_in) => *built_in = preset.clone(),
+            None => presets.push(preset.clone()),
+        }
+    }
+    presets
+}
+
+/// Check `custom` presets, for config validation
+pub fn validate(custom: &[AnalysisPreset]) -> Result<()> {
+    let mut ids: Vec<&str> = Vec::new();
+    for preset in custom {
+        preset.validate()?;
+        if ids.contains(&preset.id.as_str()) {
+            anyhow::bail!("Analysis preset '{}' is defined more than once", preset.id);
+        }
+        ids.push(&preset.id);
+    }
+    Ok(())
+}
+
+/// What a preset's sections came out as, kept with the summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Analysis {
+    /// Id of the preset
+    pub preset: String,
+    /// Its name when the summary was written
+    pub name: String,
+    pub sections: Vec<AnalysisSection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisSection {
+    pub key: String,
+    pub heading: String,
+    #[serde(default)]
+    pub numbered: bool,
+    pub items: Vec<String>,
+}
+
+impl Analysis {
+    /// The sections of `preset` from the model's JSON answer, `fields` by
+    /// key; missing or malformed ones come out empty
+    pub fn from_answer(
+        preset: &AnalysisPreset,
+        fields: &HashMap<String, serde_json::Value>,
+    ) -> Self {
+        let sections = preset
+            .sections
+            .iter()
+            .map(|section| AnalysisSection {
+                key: section.key.clone(),
+                heading: section.heading.clone(),
+                numbered: section.numbered,
+                items: fields
+                    .get(&section.key)
+                    .and_then(|value| serde_json::from_value(value.clone()).ok())
+                    .unwrap_or_default(),
+            })
+            .collect();
+        Self {
+            preset: preset.id.clone(),
+            name: preset.name.clone(),
+            sections,
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use anyhow::{Context, Result};
+use std::mem::ManuallyDrop;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use windows::Win32::Foundation::{BOOL, CloseHandle, HANDLE, S_OK, WAIT_OBJECT_0};
+use windows::Win32::Media::Audio::{
+    AUDCLNT_BUFFERFLAGS_SILENT, AUDCLNT_SHAREMODE_SHARED, AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM,
+    AUDCLNT_STREAMFLAGS_EVENTCALLBACK, AUDCLNT_STREAMFLAGS_LOOPBACK,
+    AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY, AUDIOCLIENT_ACTIVATION_PARAMS,
+    AUDIOCLIENT_ACTIVATION_PARAMS_0, AUDIOCLIENT_ACTIVATION_TYPE_PROCESS_LOOPBACK,
+    AUDIOCLIENT_PROCESS_LOOPBACK_PARAMS, ActivateAudioInterfaceAsync, AudioSessionStateActive,
+    AudioSessionStateExpired, IActivateAudioInterfaceAsyncOperation,
+    IActivateAudioInterfaceCompletionHandler, IActivateAudioInterfaceCompletionHandler_Impl,
+    IAudioCaptureClient, IAudioClient, IAudioSessionControl2, IAudioSessionManager2,
+    IMMDeviceEnumerator, MMDeviceEnumerator, PROCESS_LOOPBACK_MODE_INCLUDE_TARGET_PROCESS_TREE,
+    VIRTUAL_AUDIO_DEVICE_PROCESS_LOOPBACK, WAVE_FORMAT_PCM, WAVEFORMATEX, eConsole, eRender,
+};
+use windows::Win32::System::Com::{
+    CLSCTX_ALL, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx, CoTaskMemFree,
+    CoUninitialize, IAgileObject, IAgileObject_Impl,
+};
+use windows::Win32::System::Threading::{
+    CreateEventW, OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    QueryFullProcessImageNameW, WaitForSingleObject,
+};
+use windows::Win32::System::Variant::VT_BLOB;
+use windows::core::{HRESULT, IUnknown, Interface, PCWSTR, PROPVARIANT, PWSTR, implement};
+
+use crate::app_capture::AudioApp;
+
+/// How long Windows may take to hand over the loopback client
+const ACTIVATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Audio the capture client buffers, in 100-nanosecond units (200 ms)
+const BUFFER_DURATION: i64 = 2_000_000;
+
+/// Applications with an audio session on the default output device, by
+/// process. Sessions that are open but silent count as paused.
+pub fn list_playing_apps() -> Result<Vec<AudioApp>> {
+    // On a thread of its own, so COM is set up the way this code expects
+    // whatever the calling thread (the UI's) did with it
+    thread::spawn(|| with_com(list_sessions))
+        .join()
+        .map_err(|_| anyhow::anyhow!("Listing audio sessions panicked"))?
+}
+
+fn list_sessions() -> Result<Vec<AudioApp>> {
+    let mut apps: Vec<AudioApp> = Vec::new();
+    unsafe {
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+        let device = enumerator
+            .GetDefaultAudioEndpoint(eRender, eConsole)
+            .context("No default output device")?;
+        let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None)?;
+        let sessions = manager.GetSessionEnumerator()?;
+        for index in 0..sessions.GetCount()? {
+            let session: IAudioSessionControl2 = sessions.GetSession(index)?.cast()?;
+            let state = session.GetState()?;
+            let pid = session.GetProcessId().unwrap_or(0);
+            if state == AudioSessionStateExpired
+                || pid == 0
+                || session.IsSystemSoundsSession() == S_OK
+            {
+                continue;
+            }
+            let corked = state != AudioSessionStateActive;
+            if let Some(app) = apps.iter_mut().find(|app| app.index == pid) {
+                app.corked &= corked;
+                continue;
+            }
+            apps.push(AudioApp {
+                index: pid,
+                name: process_name(pid).unwrap_or_else(|| format!("Process {}", pid)),
+                media: display_name(&session),
+                corked,
+            });
+        }
+    }
+    Ok(apps)
+}
+
+/// The session's own name, when the app set one rather than a resource
+/// reference such as "@%SystemRoot%\..."
+fn display_name(session: &IAudioSessionControl2) -> Option<String> {
+    let name = unsafe { session.GetDisplayName() }.ok()?;
+    if name.is_null() {
+        return None;
+    }
+    let text = unsafe { name.to_string() }.ok();
+    unsafe { CoTaskMemFree(Some(name.0 as *const _)) };
+    text.filter(|text| !text.is_empty() && !text.starts_with('@'))
+}
+
+/// "chrome" for a process running chrome.exe
+fn process_name(pid: u32) -> Option<String> {
+    unsafe {
+        let process =
+            OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, BOOL::from(false), pid).ok()?;
+        let mut path = [0u16; 1024];
+        let mut len = path.len() as u32;
+        let queried = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(path.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        queried.ok()?;
+        let path = String::from_utf16_lossy(&path[..len as usize]);
+        std::path::Path::new(&path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+    }
+}
+
+/// A process loopback capture; stops when dropped
+pub struct ProcessRecorder {
+    stop: Arc<AtomicBool>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl Drop for ProcessRecorder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Record what process `pid` and its children play, converted by Windows
+/// to `sample_rate` and `channels`, handing interleaved samples and their
+/// frame count to `on_samples`. Needs Windows 10 2004 or later.
+pub fn record<F>(
+    pid: u32,
+    sample_rate: u32,
+    channels: usize,
+    on_samples: F,
+) -> Result<ProcessRecorder>
+where
+    F: FnMut(&[f32], usize) + Send + 'static,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let (started_tx, started_rx) = mpsc::channel();
+    let reader = {
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            let outcome = with_com(|| {
+                let capture = match Capture::start(pid, sample_rate, channels) {
+                    Ok(capture) => capture,
+                    Err(e) => {
+                        let _ = started_tx.send(Err(e));
+                        return Ok(());
+                    }
+                };
+                let _ = started_tx.send(Ok(()));
+                capture.run(&stop, on_samples)
+            });
+            if let Err(e) = outcome {
+                eprintln!("Application capture failed: {:#}", e);
+            }
+            println!("Application capture ended");
+        })
+    };
+    match started_rx.recv() {
+        Ok(Ok(())) => Ok(ProcessRecorder {
+            stop,
+            reader: Some(reader),
+        }),
+        Ok(Err(e)) => {
+            let _ = reader.join();
+            Err(e)
+        }
+        Err(_) => {
+            let _ = reader.join();
+            anyhow::bail!("Application capture stopped before it started")
+        }
+    }
+}
+
+/// Run `f` with COM set up for this thread
+fn with_com<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+    unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }
+        .ok()
+        .context("Failed to set up COM")?;
+    let result = f();
+    unsafe { CoUninitialize() };
+    result
+}
+
+struct Capture {
+    client: IAudioClient,
+    capture: IAudioCaptureClient,
+    event: HANDLE,
+    channels: usize,
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.client.Stop();
+            let _ = CloseHandle(self.event);
+        }
+    }
+}
+
+impl Capture {
+    fn start(pid: u32, sample_rate: u32, channels: usize) -> Result<Self> {
+        let client = activate(pid)?;
+        let block_align = 2 * channels as u16;
+        let format = WAVEFORMATEX {
+            wFormatTag: WAVE_FORMAT_PCM as u16,
+            nChannels: channels as u16,
+            nSamplesPerSec: sample_rate,
+            nAvgBytesPerSec: sample_rate * block_align as u32,
+            nBlockAlign: block_align,
+            wBitsPerSample: 16,
+            cbSize: 0,
+        };
+        unsafe {
+            client
+                .Initialize(
+                    AUDCLNT_SHAREMODE_SHARED,
+                    AUDCLNT_STREAMFLAGS_LOOPBACK
+                        | AUDCLNT_STREAMFLAGS_EVENTCALLBACK
+                        | AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM
+                        | AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY,
+                    BUFFER_DURATION,
+                    0,
+                    &format,
+                    None,
+                )
+                .context("Failed to set up the loopback stream")?;
+            let event = CreateEventW(None, BOOL::from(false), BOOL::from(false), PCWSTR::null())?;
+            let capture = Self {
+                capture: client.GetService()?,
+                client,
+                event,
+                channels,
+            };
+            capture.client.SetEventHandle(capture.event)?;
+            capture.client.Start()?;
+            Ok(capture)
+        }
+    }
+
+    /// Hand over packets as they arrive until `stop` is set
+    fn run<F>(&self, stop: &AtomicBool, mut on_samples: F) -> Result<()>
+    where
+        F: FnMut(&[f32], usize),
+    {
+        let mut samples = Vec::new();
+        while !stop.load(Ordering::SeqCst) {
+            if unsafe { WaitForSingleObject(self.event, 100) } != WAIT_OBJECT_0 {
+                continue;
+            }
+            while unsafe { self.capture.GetNextPacketSize()? } > 0 {
+                let mut data = std::ptr::null_mut();
+                let mut frames = 0u32;
+                let mut flags = 0u32;
+                unsafe {
+                    self.capture
+                        .GetBuffer(&mut data, &mut frames, &mut flags, None, None)?;
+                }
+                let len = frames as usize * self.channels;
+                samples.clear();
+                if flags & AUDCLNT_BUFFERFLAGS_SILENT.0 as u32 != 0 || data.is_null() {
+                    samples.resize(len, 0.0);
+                } else {
+                    let pcm = unsafe { std::slice::from_raw_parts(data as *const i16, len) };
+                    samples.extend(pcm.iter().map(|&s| s as f32 / 32768.0));
+                }
+                unsafe { self.capture.ReleaseBuffer(frames)? };
+                on_samples(&samples, frames as usize);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tells `activate()` that Windows is done activating the client
+#[implement(IActivateAudioInterfaceCompletionHandler, IAgileObject)]
+struct Activated {
+    done: mpsc::Sender<()>,
+}
+
+impl IActivateAudioInterfaceCompletionHandler_Impl for Activated {
+    fn ActivateCompleted(
+        &self,
+        _operation: Option<&IActivateAudioInterfaceAsyncOperation>,
+    ) -> windows::core::Result<()> {
+        let _ = self.done.send(());
+        Ok(())
+    }
+}
+
+impl IAgileObject_Impl for Activated {}
+
+/// An audio client on the process loopback device for `pid`'s tree
+fn activate(pid: u32) -> Result<IAudioClient> {
+    let mut params = AUDIOCLIENT_ACTIVATION_PARAMS {
+        ActivationType: AUDIOCLIENT_ACTIVATION_TYPE_PROCESS_LOOPBACK,
+        Anonymous: AUDIOCLIENT_ACTIVATION_PARAMS_0 {
+            ProcessLoopbackParams: AUDIOCLIENT_PROCESS_LOOPBACK_PARAMS {
+                TargetProcessId: pid,
+                ProcessLoopbackMode: PROCESS_LOOPBACK_MODE_INCLUDE_TARGET_PROCESS_TREE,
+            },
+        },
+    };
+    // A VT_BLOB pointing at `params`, which Windows only reads; never
+    // cleared, as that would free `params`
+    let blob = windows::core::imp::PROPVARIANT {
+        Anonymous: windows::core::imp::PROPVARIANT_0 {
+            Anonymous: windows::core::imp::PROPVARIANT_0_0 {
+                vt: VT_BLOB.0,
+                wReserved1: 0,
+                wReserved2: 0,
+                wReserved3: 0,
+                Anonymous: windows::core::imp::PROPVARIANT_0_0_0 {
+                    blob: windows::core::imp::BLOB {
+                        cbSize: std::mem::size_of::<AUDIOCLIENT_ACTIVATION_PARAMS>() as u32,
+                        pBlobData: &mut params as *mut _ as *mut u8,
+                    },
+                },
+            },
+        },
+    };
+    let activation = ManuallyDrop::new(unsafe { PROPVARIANT::from_raw(blob) });
+
+    let (done, activated) = mpsc::channel();
+    let handler: IActivateAudioInterfaceCompletionHandler = Activated { done }.into();
+    let operation = unsafe {
+        ActivateAudioInterfaceAsync(
+            VIRTUAL_AUDIO_DEVICE_PROCESS_LOOPBACK,
+            &IAudioClient::IID,
+            Some(&*activation as *const PROPVARIANT),
+            &handler,
+        )
+    }
+    .context("Per-application capture needs Windows 10 version 2004 or later")?;
+    activated
+        .recv_timeout(ACTIVATION_TIMEOUT)
+        .context("Windows didn't start the application capture")?;
+
+    let mut result = HRESULT(0);
+    let mut client: Option<IUnknown> = None;
+    unsafe { operation.GetActivateResult(&mut result, &mut client)? };
+    result
+        .ok()
+        .with_context(|| format!("Couldn't capture process {}", pid))?;
+    Ok(client.context("Windows returned no audio client")?.cast()?)
+}
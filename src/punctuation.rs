@@ -0,0 +1,182 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::summarization::Summarizer;
+use crate::transcription::{Transcript, TranscriptionContext, TranscriptionProvider};
+
+/// How punctuation and capitalization are restored in transcripts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PunctuationMode {
+    /// Leave transcripts as the engine wrote them
+    #[default]
+    Off,
+    /// Capitalize sentence starts and "I" and end sentences with a full stop
+    Rules,
+    /// Ask a cheap chat model to punctuate, falling back to the rules
+    Llm,
+}
+
+impl PunctuationMode {
+    pub const ALL: [PunctuationMode; 3] = [
+        PunctuationMode::Off,
+        PunctuationMode::Rules,
+        PunctuationMode::Llm,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PunctuationMode::Off => "Off",
+            PunctuationMode::Rules => "Rules",
+            PunctuationMode::Llm => "Language model",
+        }
+    }
+}
+
+/// Words that open a question when they start an unpunctuated sentence
+const QUESTION_WORDS: [&str; 18] = [
+    "who", "what", "when", "where", "why", "how", "which", "is", "are", "was", "were", "do",
+    "does", "did", "can", "could", "would", "should",
+];
+
+const LLM_INSTRUCTIONS: &str = "You restore punctuation and capitalization in speech \
+transcripts. Add commas, full stops, question marks and capital letters where they belong. \
+Do not add, remove, reorder or correct any words. Reply with the transcript only.";
+
+/// `text` with the first letter of every sentence and the pronoun "I"
+/// capitalized and a final full stop (or question mark) added. Text that is
+/// already punctuated keeps its punctuation.
+pub fn restore(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let Some(first) = words.first() else {
+        return String::new();
+    };
+    let unpunctuated = !text.contains(['.', ',', '?', '!']);
+
+    let mut restored = String::with_capacity(text.len() + 1);
+    let mut sentence_start = true;
+    for word in &words {
+        if !restored.is_empty() {
+            restored.push(' ');
+        }
+        let bare = word.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '\'');
+        let is_pronoun = matches!(
+            bare.replace('’', "'").as_str(),
+            "i" | "i'm" | "i'll" | "i've" | "i'd"
+        );
+        if sentence_start || is_pronoun {
+            restored.push_str(&capitalize(word));
+        } else {
+            restored.push_str(word);
+        }
+        sentence_start = word.ends_with(['.', '?', '!']);
+    }
+
+    if restored.ends_with(|c: char| c.is_alphanumeric() || c == '\'') {
+        let asks = QUESTION_WORDS.contains(&first.to_lowercase().as_str());
+        restored.push(if unpunctuated && asks { '?' } else { '.' });
+    }
+    restored
+}
+
+/// `word` with its first letter in upper case
+fn capitalize(word: &str) -> String {
+    match word.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((i, c)) => format!(
+            "{}{}{}",
+            &word[..i],
+            c.to_uppercase(),
+            &word[i + c.len_utf8()..]
+        ),
+        None => word.to_string(),
+    }
+}
+
+/// The words of `text` in lower case without punctuation, to check that a
+/// rewrite only changed punctuation and case
+fn bare_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Wraps an engine so its transcripts are punctuated and capitalized before
+/// corrections, redaction and the cache see them. Meant for engines that
+/// write lowercase text without punctuation, such as Vosk.
+pub struct PunctuatingProvider {
+    inner: Arc<dyn TranscriptionProvider>,
+    /// Chat model for `PunctuationMode::Llm`; the rules are used without one
+    chat: Option<Summarizer>,
+}
+
+impl PunctuatingProvider {
+    pub fn new(inner: Arc<dyn TranscriptionProvider>, chat: Option<Summarizer>) -> Self {
+        Self { inner, chat }
+    }
+
+    /// Punctuation from the chat model, or from the rules when it fails or
+    /// changes the words themselves
+    async fn restore_with_chat(&self, chat: &Summarizer, text: &str) -> String {
+        let reply = chat
+            .complete(
+                "Punctuation request",
+                LLM_INSTRUCTIONS,
+                text.to_string(),
+                0.0,
+            )
+            .await;
+        match reply {
+            Ok(reply) if bare_words(&reply.content) == bare_words(text) => {
+                reply.content.trim().to_string()
+            }
+            Ok(_) => {
+                eprintln!("Punctuation model changed the words; using the rules instead");
+                restore(text)
+            }
+            Err(e) => {
+                eprintln!(
+                    "Punctuation request failed, using the rules instead: {:#}",
+                    e
+                );
+                restore(text)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for PunctuatingProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    /// With the chat model only the full text is rewritten; utterances and
+    /// phrase timings get the rules, which keep them aligned with the audio
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        let mut transcript = self.inner.transcribe_audio(audio_file, context).await?;
+        // Engines that punctuate are left alone, so the setting can stay on
+        // when switching between them
+        if transcript.text.trim().is_empty() || transcript.text.contains(['.', '?', '!']) {
+            return Ok(transcript);
+        }
+        transcript.text = match &self.chat {
+            Some(chat) => self.restore_with_chat(chat, &transcript.text).await,
+            None => restore(&transcript.text),
+        };
+        for utterance in &mut transcript.utterances {
+            utterance.text = restore(&utterance.text);
+        }
+        for segment in &mut transcript.segments {
+            segment.text = restore(&segment.text);
+        }
+        Ok(transcript)
+    }
+}
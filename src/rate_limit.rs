@@ -0,0 +1,213 @@
+use reqwest::header::HeaderMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// What an API allowed as of its last response, from one family of
+/// `x-ratelimit-*` headers
+#[derive(Debug, Clone, Copy)]
+struct Budget {
+    /// Requests (or tokens) allowed per window, if reported
+    limit: Option<u64>,
+    /// Left in the current window, less the requests started since
+    remaining: u64,
+    resets_at: Instant,
+}
+
+impl Budget {
+    /// Read the `x-ratelimit-{limit,remaining,reset}-{kind}` headers;
+    /// `None` unless both the remaining count and the reset are there
+    fn from_headers(headers: &HeaderMap, kind: &str, now: Instant) -> Option<Self> {
+        let header = |name: &str| {
+            headers
+                .get(format!("x-ratelimit-{}-{}", name, kind))?
+                .to_str()
+                .ok()
+                .map(str::trim)
+        };
+        Some(Self {
+            limit: header("limit").and_then(|v| v.parse().ok()),
+            remaining: header("remaining")?.parse().ok()?,
+            resets_at: now + parse_reset(header("reset")?)?,
+        })
+    }
+
+    /// When the budget is used up, the time it refills
+    fn exhausted_until(&self, now: Instant) -> Option<Instant> {
+        (self.remaining == 0 && self.resets_at > now).then_some(self.resets_at)
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    requests: Option<Budget>,
+    tokens: Option<Budget>,
+    /// Requests held back right now
+    waiting: usize,
+}
+
+impl State {
+    /// Count a request against the budget, forgetting one whose window
+    /// has passed
+    fn spend(&mut self, now: Instant) {
+        if let Some(requests) = &mut self.requests {
+            if requests.resets_at <= now {
+                self.requests = None;
+            } else {
+                requests.remaining = requests.remaining.saturating_sub(1);
+            }
+        }
+    }
+
+    fn blocked_until(&self, now: Instant) -> Option<Instant> {
+        [self.requests, self.tokens]
+            .iter()
+            .flatten()
+            .filter_map(|budget| budget.exhausted_until(now))
+            .max()
+    }
+}
+
+/// Counts a request as held back while it lives, so a wait that is
+/// cancelled is uncounted too
+struct Waiting<'a>(&'a Mutex<State>);
+
+impl<'a> Waiting<'a> {
+    fn new(state: &'a Mutex<State>) -> Self {
+        state.lock().unwrap().waiting += 1;
+        Self(state)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.lock().unwrap().waiting -= 1;
+    }
+}
+
+/// Where the limiter stands, for the status bar
+#[derive(Debug, Clone, Copy)]
+pub enum Throttle {
+    /// The budget is used up; `waiting` requests start when it resets in
+    /// `resumes_in`
+    Paused {
+        resumes_in: Duration,
+        waiting: usize,
+    },
+    /// Requests go out; `remaining` of `limit` are left until the reset
+    Open { remaining: u64, limit: Option<u64> },
+}
+
+/// Paces requests by the rate limits OpenAI reports on every response
+/// (`x-ratelimit-remaining-requests`, `x-ratelimit-reset-requests` and the
+/// `-tokens` pair), so uploads wait for the window to reset instead of
+/// running into 429s and backing off.
+///
+/// Clones share the same budget; the app keeps one for all transcription
+/// requests because the limits apply to the API key, not to one request.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    state: Arc<Mutex<State>>,
+    /// Wakes held requests when a response reports a fresh budget
+    updated: Arc<Notify>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait until the budget allows another request, then count it as
+    /// spent until the next response reports the real figures
+    pub async fn acquire(&self, what: &str) {
+        let mut waiting = None;
+        loop {
+            let until = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                match state.blocked_until(now) {
+                    Some(until) => until,
+                    None => {
+                        state.spend(now);
+                        return;
+                    }
+                }
+            };
+            if waiting.is_none() {
+                waiting = Some(Waiting::new(&self.state));
+                println!(
+                    "Rate limit reached; holding {} for up to {:.1}s",
+                    what,
+                    until
+                        .saturating_duration_since(Instant::now())
+                        .as_secs_f32()
+                );
+            }
+            tokio::select! {
+                _ = tokio::time::sleep_until(until.into()) => {}
+                _ = self.updated.notified() => {}
+            }
+        }
+    }
+
+    /// Take the limits from a response; ones it doesn't report are kept
+    pub fn observe(&self, headers: &HeaderMap) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        if let Some(requests) = Budget::from_headers(headers, "requests", now) {
+            state.requests = Some(requests);
+        }
+        if let Some(tokens) = Budget::from_headers(headers, "tokens", now) {
+            state.tokens = Some(tokens);
+        }
+        drop(state);
+        self.updated.notify_waiters();
+    }
+
+    /// `None` until a response has reported limits, and again once the
+    /// reported window has passed
+    pub fn throttle(&self) -> Option<Throttle> {
+        let now = Instant::now();
+        let state = self.state.lock().unwrap();
+        if let Some(until) = state.blocked_until(now) {
+            return Some(Throttle::Paused {
+                resumes_in: until - now,
+                waiting: state.waiting,
+            });
+        }
+        let requests = state.requests.filter(|budget| budget.resets_at > now)?;
+        Some(Throttle::Open {
+            remaining: requests.remaining,
+            limit: requests.limit,
+        })
+    }
+}
+
+/// A reset time as OpenAI writes it: "1s", "6m0s", "20ms", "1h2m3.5s"
+fn parse_reset(value: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let secs_per_unit = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += number * secs_per_unit;
+        rest = &rest[unit_len..];
+    }
+    total.is_finite().then(|| Duration::from_secs_f64(total))
+}
@@ -0,0 +1,300 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use futures_util::{SinkExt, StreamExt};
+use reqwest::Url;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio_tungstenite::Connector;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+
+use crate::http;
+use crate::openai_api::{ApiEndpoint, AuthHeader};
+use crate::resample::LinearResampler;
+
+/// The realtime API takes 16-bit mono PCM at 24 kHz
+const SAMPLE_RATE: u32 = 24_000;
+
+/// How long to wait for the last utterances after the audio ends
+const FINISH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a streaming session reports while audio is spoken
+#[derive(Debug, Clone)]
+pub enum RealtimeEvent {
+    /// The text of utterance `item_id` so far; it grows until completed
+    Partial { item_id: String, text: String },
+    /// The final text of utterance `item_id`, spoken `started_secs` into
+    /// the session for `duration_secs`
+    Completed {
+        item_id: String,
+        text: String,
+        started_secs: f64,
+        duration_secs: f64,
+    },
+    /// Utterance `item_id` could not be transcribed
+    Failed { item_id: String, error: String },
+}
+
+/// Settings of a streaming session
+#[derive(Debug, Clone)]
+pub struct RealtimeSettings {
+    pub endpoint: ApiEndpoint,
+    /// "gpt-4o-transcribe", "gpt-4o-mini-transcribe" or "whisper-1"
+    pub model: String,
+    /// ISO-639-1 code to transcribe as, or `None` to auto-detect
+    pub language: Option<String>,
+    pub prompt: Option<String>,
+    /// How long connecting may take, proxy handshake included
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ServerEvent {
+    #[serde(rename = "input_audio_buffer.speech_started")]
+    SpeechStarted {
+        item_id: String,
+        audio_start_ms: u64,
+    },
+    #[serde(rename = "input_audio_buffer.speech_stopped")]
+    SpeechStopped { item_id: String, audio_end_ms: u64 },
+    #[serde(rename = "input_audio_buffer.committed")]
+    Committed { item_id: String },
+    #[serde(rename = "conversation.item.input_audio_transcription.delta")]
+    Delta { item_id: String, delta: String },
+    #[serde(rename = "conversation.item.input_audio_transcription.completed")]
+    Completed { item_id: String, transcript: String },
+    #[serde(rename = "conversation.item.input_audio_transcription.failed")]
+    Failed { item_id: String, error: ApiError },
+    #[serde(rename = "error")]
+    Error { error: ApiError },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    #[serde(default)]
+    message: String,
+}
+
+/// Stream `audio` (mono samples at `sample_rate`) to the OpenAI Realtime
+/// API and report transcripts through `on_event` as they are spoken. The
+/// server detects where utterances end, so text arrives within a second or
+/// two instead of once per chunk.
+///
+/// Runs until `audio` closes, then waits briefly for the utterances still
+/// being transcribed.
+pub async fn stream<F>(
+    settings: RealtimeSettings,
+    sample_rate: u32,
+    mut audio: UnboundedReceiver<Vec<f32>>,
+    on_event: F,
+) -> Result<()>
+where
+    F: Fn(RealtimeEvent),
+{
+    let request = connect_request(&settings.endpoint)?;
+    // The same proxy and certificates as every other request
+    let network = http::settings();
+    let url = Url::parse(&request.uri().to_string())?;
+    let stream = network
+        .connect(&url, settings.timeout)
+        .await
+        .context("Failed to connect to the realtime API")?;
+    let connector = Connector::NativeTls(network.tls_connector()?);
+    let (socket, _) =
+        tokio_tungstenite::client_async_tls_with_config(request, stream, None, Some(connector))
+            .await
+            .context("Failed to connect to the realtime API")?;
+    let (mut sink, mut events) = socket.split();
+
+    let session = serde_json::json!({
+        "type": "transcription_session.update",
+        "session": {
+            "input_audio_format": "pcm16",
+            "input_audio_transcription": {
+                "model": settings.model,
+                "language": settings.language,
+                "prompt": settings.prompt.unwrap_or_default(),
+            },
+            "turn_detection": { "type": "server_vad" },
+        },
+    });
+    sink.send(Message::Text(session.to_string()))
+        .await
+        .context("Failed to start the realtime session")?;
+    println!("Streaming audio to the realtime API");
+
+    let mut resampler = LinearResampler::new(sample_rate, SAMPLE_RATE);
+    let mut resampled = Vec::new();
+    let mut streamed_samples = 0u64;
+    let mut partials: HashMap<String, String> = HashMap::new();
+    let mut spans: HashMap<String, (u64, Option<u64>)> = HashMap::new();
+    let mut pending: HashSet<String> = HashSet::new();
+    let mut finish_by: Option<tokio::time::Instant> = None;
+
+    loop {
+        let deadline = async {
+            match finish_by {
+                Some(at) => tokio::time::sleep_until(at).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            samples = audio.recv(), if finish_by.is_none() => match samples {
+                Some(samples) => {
+                    resampled.clear();
+                    resampler.process(&samples, &mut resampled);
+                    streamed_samples += resampled.len() as u64;
+                    let append = serde_json::json!({
+                        "type": "input_audio_buffer.append",
+                        "audio": BASE64.encode(pcm16(&resampled)),
+                    });
+                    sink.send(Message::Text(append.to_string()))
+                        .await
+                        .context("Lost the connection to the realtime API")?;
+                }
+                None => {
+                    // Transcribe what was said since the last pause too
+                    let commit = serde_json::json!({ "type": "input_audio_buffer.commit" });
+                    let _ = sink.send(Message::Text(commit.to_string())).await;
+                    finish_by = Some(tokio::time::Instant::now() + FINISH_TIMEOUT);
+                }
+            },
+            message = events.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        return Err(e).context("Lost the connection to the realtime API");
+                    }
+                };
+                let event = match serde_json::from_str::<ServerEvent>(&text) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        eprintln!("Unexpected realtime event ({}): {}", e, text);
+                        continue;
+                    }
+                };
+                match event {
+                    ServerEvent::SpeechStarted { item_id, audio_start_ms } => {
+                        spans.insert(item_id, (audio_start_ms, None));
+                    }
+                    ServerEvent::SpeechStopped { item_id, audio_end_ms } => {
+                        if let Some((_, end)) = spans.get_mut(&item_id) {
+                            *end = Some(audio_end_ms);
+                        }
+                    }
+                    ServerEvent::Committed { item_id } => {
+                        pending.insert(item_id);
+                    }
+                    ServerEvent::Delta { item_id, delta } => {
+                        let text = partials.entry(item_id.clone()).or_default();
+                        text.push_str(&delta);
+                        on_event(RealtimeEvent::Partial {
+                            item_id,
+                            text: text.clone(),
+                        });
+                    }
+                    ServerEvent::Completed { item_id, transcript } => {
+                        pending.remove(&item_id);
+                        partials.remove(&item_id);
+                        let (start_ms, end_ms) = spans.remove(&item_id).unwrap_or_default();
+                        // Speech cut off by stopping runs to the end of the audio
+                        let end_ms = end_ms
+                            .unwrap_or(streamed_samples * 1000 / SAMPLE_RATE as u64)
+                            .max(start_ms);
+                        on_event(RealtimeEvent::Completed {
+                            item_id,
+                            text: transcript.trim().to_string(),
+                            started_secs: start_ms as f64 / 1000.0,
+                            duration_secs: (end_ms - start_ms) as f64 / 1000.0,
+                        });
+                    }
+                    ServerEvent::Failed { item_id, error } => {
+                        pending.remove(&item_id);
+                        partials.remove(&item_id);
+                        spans.remove(&item_id);
+                        on_event(RealtimeEvent::Failed {
+                            item_id,
+                            error: error.message,
+                        });
+                    }
+                    // Committing after a pause leaves nothing to commit,
+                    // which the server reports as an error
+                    ServerEvent::Error { error } if finish_by.is_some() => {
+                        println!("Realtime session: {}", error.message);
+                    }
+                    ServerEvent::Error { error } => {
+                        anyhow::bail!("Realtime API error: {}", error.message);
+                    }
+                    ServerEvent::Other => {}
+                }
+                if finish_by.is_some() && pending.is_empty() {
+                    break;
+                }
+            }
+            _ = deadline => {
+                eprintln!(
+                    "Gave up waiting for {} realtime transcripts",
+                    pending.len()
+                );
+                break;
+            }
+        }
+    }
+
+    let _ = sink.send(Message::Close(None)).await;
+    println!("Realtime session ended");
+    Ok(())
+}
+
+/// WebSocket handshake for `endpoint`: its base URL with a ws(s) scheme,
+/// authenticated the same way as its HTTP requests
+fn connect_request(
+    endpoint: &ApiEndpoint,
+) -> Result<tokio_tungstenite::tungstenite::handshake::client::Request> {
+    let mut url = Url::parse(&format!(
+        "{}/realtime",
+        endpoint.base_url.trim_end_matches('/')
+    ))
+    .context("Invalid transcription base URL")?;
+    let scheme = if url.scheme() == "http" { "ws" } else { "wss" };
+    url.set_scheme(scheme)
+        .map_err(|_| anyhow::anyhow!("Can't stream to {}", endpoint.base_url))?;
+    url.query_pairs_mut().append_pair("intent", "transcription");
+    if !endpoint.api_version.is_empty() {
+        url.query_pairs_mut()
+            .append_pair("api-version", &endpoint.api_version);
+    }
+
+    let mut request = url.as_str().into_client_request()?;
+    let headers = request.headers_mut();
+    headers.insert("OpenAI-Beta", HeaderValue::from_static("realtime=v1"));
+    // Local proxies may not need a key at all
+    if !endpoint.api_key.is_empty() {
+        let (name, value) = match endpoint.auth {
+            AuthHeader::Bearer => ("Authorization", format!("Bearer {}", endpoint.api_key)),
+            AuthHeader::ApiKey => ("api-key", endpoint.api_key.clone()),
+        };
+        headers.insert(
+            name,
+            HeaderValue::from_str(&value).context("Invalid API key")?,
+        );
+    }
+    Ok(request)
+}
+
+/// Samples as little-endian 16-bit PCM
+fn pcm16(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|&s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::chunk_writer::{ChunkMetadata, PARTIAL_EXTENSION};
+use crate::diagnostics::AudioDiagnostics;
+use crate::encoding::{self, AudioFormat};
+use crate::vad;
+use crate::waveform::{WAVEFORM_POINTS, Waveform};
+
+/// Chunks in `dir` left behind by an earlier run without a transcription,
+/// e.g. because the app crashed or quit before Whisper answered.
+///
+/// A WAV chunk that was still being written when the app crashed has its
+/// header repaired and is included too.
+pub fn find_orphaned_chunks(
+    dir: &Path,
+    transcribed: &HashSet<PathBuf>,
+) -> Vec<(PathBuf, ChunkMetadata)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_chunk_file(path))
+        .collect();
+    paths.sort();
+
+    let mut orphans = Vec::new();
+    for path in paths {
+        let path = if path.extension().is_some_and(|ext| ext == PARTIAL_EXTENSION) {
+            match recover_partial_wav(&path) {
+                Ok(recovered) => {
+                    println!("Recovered partial audio chunk: {:?}", recovered);
+                    recovered
+                }
+                Err(e) => {
+                    eprintln!("Could not recover {:?}: {}", path, e);
+                    continue;
+                }
+            }
+        } else {
+            path
+        };
+        if transcribed.contains(&path) {
+            continue;
+        }
+
+        match load_metadata(&path) {
+            Ok(metadata) => orphans.push((path, metadata)),
+            Err(e) => eprintln!("Skipping unreadable audio chunk {:?}: {}", path, e),
+        }
+    }
+
+    orphans
+}
+
+/// Whether the path is a chunk written by `ChunkWriter`, finished or not
+fn is_chunk_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let name = name
+        .strip_suffix(&format!(".{}", PARTIAL_EXTENSION))
+        .unwrap_or(name);
+
+    name.starts_with("chunk_")
+        && AudioFormat::ALL
+            .iter()
+            .any(|format| name.ends_with(&format!(".{}", format.extension())))
+}
+
+/// The chunk's sidecar metadata, or metadata measured from the audio when
+/// the sidecar is missing (recovered partial chunks)
+fn load_metadata(path: &Path) -> Result<ChunkMetadata> {
+    if let Some(metadata) = fs::read_to_string(ChunkMetadata::sidecar_path(path))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+    {
+        return Ok(metadata);
+    }
+
+    let (samples, sample_rate) = encoding::read_audio_file(path)?;
+    let (recorded_at, sequence) = parse_chunk_name(path);
+    let metadata = ChunkMetadata {
+        sequence,
+        device_name: "Recovered".to_string(),
+        source: None,
+        speaker: None,
+        sample_rate,
+        duration_secs: samples.len() as f64 / sample_rate as f64,
+        peak: samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs())),
+        rms: vad::rms(&samples),
+        recorded_at,
+        waveform: Waveform::from_samples(&samples, WAVEFORM_POINTS),
+        diagnostics: AudioDiagnostics::from_samples(&samples, sample_rate),
+        gain: 1.0,
+    };
+    if let Err(e) = fs::write(
+        ChunkMetadata::sidecar_path(path),
+        serde_json::to_string_pretty(&metadata)?,
+    ) {
+        eprintln!("Error writing chunk metadata: {}", e);
+    }
+
+    Ok(metadata)
+}
+
+/// Start time and sequence number from a `chunk_<unix time>_<sequence>...` name
+fn parse_chunk_name(path: &Path) -> (DateTime<Utc>, u64) {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let mut parts = name.split(['_', '.']).skip(1);
+    let recorded_at = parts
+        .next()
+        .and_then(|secs| secs.parse().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .unwrap_or_else(Utc::now);
+    let sequence = parts.next().and_then(|seq| seq.parse().ok()).unwrap_or(0);
+
+    (recorded_at, sequence)
+}
+
+/// Give a WAV file that was never finalized valid RIFF and data sizes (hound
+/// only writes them on finalize), then move it to its finished name
+fn recover_partial_wav(partial: &Path) -> Result<PathBuf> {
+    let path = partial.with_extension("");
+    if path.extension().is_none_or(|ext| ext != "wav") {
+        anyhow::bail!("only WAV chunks can be recovered");
+    }
+
+    let mut file = OpenOptions::new().read(true).write(true).open(partial)?;
+    let file_len = file.metadata()?.len();
+    let mut header = Vec::new();
+    (&mut file).take(128).read_to_end(&mut header)?;
+
+    let data_pos = header
+        .windows(4)
+        .position(|id| id == b"data")
+        .context("no data chunk in WAV header")? as u64;
+    let block_align = header
+        .get(32..34)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]).max(1) as u64)
+        .context("truncated WAV header")?;
+    let data_start = data_pos + 8;
+
+    // Drop a trailing partial sample
+    let data_len = file_len.saturating_sub(data_start) / block_align * block_align;
+    if data_len == 0 {
+        drop(file);
+        fs::remove_file(partial)?;
+        anyhow::bail!("no audio was written");
+    }
+
+    file.set_len(data_start + data_len)?;
+    file.seek(SeekFrom::Start(4))?;
+    file.write_all(&((data_start + data_len - 8) as u32).to_le_bytes())?;
+    file.seek(SeekFrom::Start(data_pos + 4))?;
+    file.write_all(&(data_len as u32).to_le_bytes())?;
+    drop(file);
+
+    fs::rename(partial, &path)?;
+    Ok(path)
+}
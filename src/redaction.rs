@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use regex::{Captures, Regex, RegexBuilder};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::transcription::{Transcript, TranscriptionContext, TranscriptionProvider};
+
+/// What a match of a sensitive-word pattern is replaced with
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Common English swear words; each also matches with the usual endings
+/// ("fucking", "shitty"), but not inside other words or names
+const PROFANITY: [&str; 15] = [
+    "fuck",
+    "shit",
+    "bullshit",
+    "bitch",
+    "bastard",
+    "asshole",
+    "cunt",
+    "piss",
+    "crap",
+    "damn",
+    "goddamn",
+    "motherfuck",
+    "wank",
+    "twat",
+    "bollocks",
+];
+
+/// Masks profanity and user-defined sensitive words in transcripts
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    profanity: Option<Regex>,
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// A redactor for `patterns` (case-insensitive regular expressions),
+    /// masking profanity too when `profanity` is set
+    pub fn new(profanity: bool, patterns: &[String]) -> Result<Self> {
+        let profanity = profanity
+            .then(|| {
+                RegexBuilder::new(&format!(
+                    r"\b(?:{})(?:s|es|ed|er|ers|ing|in|y|ty)?\b",
+                    PROFANITY.join("|")
+                ))
+                .case_insensitive(true)
+                .build()
+            })
+            .transpose()?;
+        let patterns = patterns
+            .iter()
+            .map(|pattern| pattern.trim())
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("Invalid redaction pattern '{}'", pattern))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            profanity,
+            patterns,
+        })
+    }
+
+    /// Whether there is anything to mask
+    pub fn is_active(&self) -> bool {
+        self.profanity.is_some() || !self.patterns.is_empty()
+    }
+
+    /// `text` with sensitive words replaced by "[REDACTED]" and swear words
+    /// masked after their first letter ("s***")
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for pattern in &self.patterns {
+            text = pattern.replace_all(&text, REDACTED).into_owned();
+        }
+        if let Some(profanity) = &self.profanity {
+            text = profanity
+                .replace_all(&text, |caps: &Captures| mask(&caps[0]))
+                .into_owned();
+        }
+        text
+    }
+
+    /// Redact everything in `transcript` that holds text. Word timings are
+    /// redacted one word at a time, so patterns spanning several words only
+    /// mask them in the text, utterances and segments.
+    pub fn redact_transcript(&self, transcript: &mut Transcript) {
+        transcript.text = self.redact(&transcript.text);
+        for utterance in &mut transcript.utterances {
+            utterance.text = self.redact(&utterance.text);
+        }
+        for segment in &mut transcript.segments {
+            segment.text = self.redact(&segment.text);
+        }
+        for word in &mut transcript.words {
+            word.word = self.redact(&word.word);
+        }
+    }
+}
+
+/// "fucking" → "f******"
+fn mask(word: &str) -> String {
+    let mut chars = word.chars();
+    let first = chars.next().map(String::from).unwrap_or_default();
+    first + &"*".repeat(chars.count())
+}
+
+/// Wraps an engine so its transcripts are redacted before anything else
+/// sees them: the display, saved JSON, exports, summaries and the
+/// transcription cache only ever hold the masked text.
+pub struct RedactingProvider {
+    inner: Arc<dyn TranscriptionProvider>,
+    redactor: Redactor,
+}
+
+impl RedactingProvider {
+    pub fn new(inner: Arc<dyn TranscriptionProvider>, redactor: Redactor) -> Self {
+        Self { inner, redactor }
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for RedactingProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        let mut transcript = self.inner.transcribe_audio(audio_file, context).await?;
+        self.redactor.redact_transcript(&mut transcript);
+        Ok(transcript)
+    }
+}
@@ -0,0 +1,54 @@
+/// Streaming linear-interpolation resampler.
+///
+/// Keeps the last input sample and fractional read position between calls so
+/// audio callbacks of any size produce a continuous output stream.
+#[derive(Debug, Clone)]
+pub struct LinearResampler {
+    /// Input samples consumed per output sample
+    step: f64,
+    /// Read position, where 0.0 is `prev` and 1.0 is the first new sample
+    pos: f64,
+    prev: f32,
+}
+
+impl LinearResampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            step: from_rate as f64 / to_rate as f64,
+            pos: 1.0,
+            prev: 0.0,
+        }
+    }
+
+    /// Whether input and output rates are the same
+    pub fn is_passthrough(&self) -> bool {
+        self.step == 1.0
+    }
+
+    /// Resample `input` and append the result to `output`
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.is_passthrough() {
+            output.extend_from_slice(input);
+            return;
+        }
+        if input.is_empty() {
+            return;
+        }
+
+        let len = input.len();
+        let prev = self.prev;
+        let at = |i: usize| if i == 0 { prev } else { input[i - 1] };
+
+        while self.pos < len as f64 {
+            let index = self.pos.floor() as usize;
+            let frac = (self.pos - index as f64) as f32;
+            let a = at(index);
+            let b = at(index + 1);
+            output.push(a + (b - a) * frac);
+            self.pos += self.step;
+        }
+
+        self.pos -= len as f64;
+        self.prev = input[len - 1];
+    }
+}
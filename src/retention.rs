@@ -0,0 +1,132 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Files this recent are never deleted, so chunks still waiting for
+/// transcription and files being written survive any quota
+const MIN_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Limits on what one directory may keep
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RetentionPolicy {
+    pub max_bytes: Option<u64>,
+    pub max_age: Option<Duration>,
+}
+
+/// Disk space used by the files in one directory
+#[derive(Debug, Clone)]
+pub struct DirUsage {
+    pub dir: PathBuf,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// What a cleanup pass removed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanupReport {
+    pub deleted_files: usize,
+    pub freed_bytes: u64,
+}
+
+impl CleanupReport {
+    fn add(&mut self, other: CleanupReport) {
+        self.deleted_files += other.deleted_files;
+        self.freed_bytes += other.freed_bytes;
+    }
+}
+
+struct FileEntry {
+    path: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+/// Files directly inside `dir`, oldest first
+fn list_files(dir: &Path) -> Result<Vec<FileEntry>> {
+    let mut files: Vec<FileEntry> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read folder {:?}", dir))?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| FileEntry {
+                path: entry.path(),
+                bytes: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            })
+        })
+        .collect();
+
+    files.sort_by_key(|file| file.modified);
+    Ok(files)
+}
+
+/// Current size of a directory's files (zero if it doesn't exist yet)
+pub fn usage(dir: &Path) -> DirUsage {
+    let files = list_files(dir).unwrap_or_default();
+    DirUsage {
+        dir: dir.to_path_buf(),
+        files: files.len(),
+        bytes: files.iter().map(|file| file.bytes).sum(),
+    }
+}
+
+/// Delete files in `dir` older than the policy's age limit, then the oldest
+/// remaining ones until the directory fits its quota
+pub fn enforce(dir: &Path, policy: &RetentionPolicy) -> Result<CleanupReport> {
+    let files = list_files(dir)?;
+    let mut total: u64 = files.iter().map(|file| file.bytes).sum();
+    let now = SystemTime::now();
+    let mut report = CleanupReport::default();
+
+    for file in files {
+        let age = now.duration_since(file.modified).unwrap_or_default();
+        if age < MIN_AGE {
+            continue;
+        }
+        let expired = policy.max_age.is_some_and(|max_age| age > max_age);
+        let over_quota = policy.max_bytes.is_some_and(|max_bytes| total > max_bytes);
+        if !expired && !over_quota {
+            continue;
+        }
+
+        match fs::remove_file(&file.path) {
+            Ok(()) => {
+                total -= file.bytes;
+                report.deleted_files += 1;
+                report.freed_bytes += file.bytes;
+            }
+            Err(e) => eprintln!("Failed to delete {:?}: {}", file.path, e),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Apply each directory's policy, continuing past directories that fail
+pub fn enforce_all(targets: &[(PathBuf, RetentionPolicy)]) -> CleanupReport {
+    let mut report = CleanupReport::default();
+    for (dir, policy) in targets {
+        match enforce(dir, policy) {
+            Ok(cleaned) => report.add(cleaned),
+            Err(e) => eprintln!("Retention cleanup failed: {}", e),
+        }
+    }
+    report
+}
+
+/// Human-readable size, e.g. "12.3 MB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
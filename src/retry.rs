@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::Duration;
+
+use crate::api_error::ApiError;
+use crate::rate_limit::RateLimiter;
+
+/// A request kept failing in a way that may clear up by itself (network
+/// down, rate limited, server errors), as opposed to being rejected
+#[derive(Debug, thiserror::Error)]
+#[error("{error} (gave up after {attempts} attempts)")]
+pub struct RetriesExhausted {
+    pub attempts: u32,
+    /// How the last attempt failed
+    pub error: ApiError,
+}
+
+/// Whether `error` came from requests that ran out of retries, so trying
+/// again later may succeed. A used-up quota is retried like a rate limit
+/// but won't clear up by waiting.
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<RetriesExhausted>()
+            .is_some_and(|exhausted| !matches!(exhausted.error, ApiError::QuotaExceeded { .. }))
+    })
+}
+
+/// How long API requests may take and how failed ones are retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Tries in total, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubles after each one
+    pub base_delay: Duration,
+    /// Longest wait between tries, including one a server asks for
+    pub max_delay: Duration,
+    /// Longest a single attempt may take, upload included, before it is
+    /// abandoned and retried like any other timeout; `None` waits forever
+    pub timeout: Option<Duration>,
+    /// Holds attempts back while the API's reported rate limit is used up
+    pub rate_limit: Option<RateLimiter>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            timeout: Some(Duration::from_secs(120)),
+            rate_limit: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The same policy, pacing attempts by `rate_limit`
+    pub fn with_rate_limit(self, rate_limit: RateLimiter) -> Self {
+        Self {
+            rate_limit: Some(rate_limit),
+            ..self
+        }
+    }
+
+    /// Send the request `build` makes, retrying rate limits (429), server
+    /// errors (5xx), timeouts and connection failures.
+    ///
+    /// `build` is called again for every attempt because multipart bodies
+    /// can't be cloned. Returns the response with the number of retries it
+    /// took; other error statuses are returned for the caller to report.
+    pub async fn send<F>(&self, what: &str, build: F) -> Result<(Response, u32)>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        self.try_send(what, || Ok(build())).await
+    }
+
+    /// Like `send`, for requests whose body is prepared per attempt in a way
+    /// that can fail, such as opening the file it streams. A failure to
+    /// build is returned right away rather than retried.
+    pub async fn try_send<F>(&self, what: &str, build: F) -> Result<(Response, u32)>
+    where
+        F: Fn() -> Result<RequestBuilder>,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let mut request = build()?;
+            if let Some(timeout) = self.timeout {
+                request = request.timeout(timeout);
+            }
+            if let Some(rate_limit) = &self.rate_limit {
+                rate_limit.acquire(what).await;
+            }
+            let outcome = request.send().await;
+            if let (Some(rate_limit), Ok(response)) = (&self.rate_limit, &outcome) {
+                rate_limit.observe(response.headers());
+            }
+            let (reason, retry_after) = match &outcome {
+                Ok(response) if is_retryable_status(response.status()) => {
+                    (response.status().to_string(), retry_after(response))
+                }
+                Err(e) if e.is_timeout() || e.is_connect() => (e.to_string(), None),
+                _ => {
+                    return outcome
+                        .map(|response| (response, attempt - 1))
+                        .with_context(|| format!("Failed to send {}", what));
+                }
+            };
+
+            if attempt >= max_attempts {
+                let error = match outcome {
+                    Ok(response) => ApiError::from_response(what, response).await,
+                    Err(_) => ApiError::Network {
+                        what: what.to_string(),
+                        detail: reason,
+                    },
+                };
+                return Err(RetriesExhausted {
+                    attempts: attempt,
+                    error,
+                }
+                .into());
+            }
+
+            let delay = self.delay(attempt, retry_after);
+            eprintln!(
+                "{} failed ({}), retrying in {:.1}s (attempt {}/{})",
+                what,
+                reason,
+                delay.as_secs_f32(),
+                attempt + 1,
+                max_attempts
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Wait before retry number `attempt`: what the server asked for, or
+    /// exponential backoff with jitter so parallel chunks don't retry in step
+    fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let delay = retry_after.unwrap_or_else(|| {
+            let backoff = self
+                .base_delay
+                .saturating_mul(1 << (attempt - 1).min(16))
+                .min(self.max_delay);
+            // Somewhere between half and all of the backoff
+            let jitter = RandomState::new().hash_one(attempt) as f64 / u64::MAX as f64;
+            backoff.mul_f64(0.5 + 0.5 * jitter)
+        });
+        delay.min(self.max_delay)
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// A `Retry-After` header given in seconds (the HTTP-date form is ignored)
+pub fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    let secs: f64 = value.trim().parse().ok()?;
+    (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs))
+}
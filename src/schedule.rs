@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveTime};
+use tokio::task::JoinHandle;
+
+/// When a recording should start and/or stop automatically
+#[derive(Debug, Clone)]
+pub struct RecordingSchedule {
+    pub start_at: Option<DateTime<Local>>,
+    pub stop_at: Option<DateTime<Local>>,
+}
+
+impl RecordingSchedule {
+    /// Build a schedule from the UI inputs.
+    ///
+    /// `start` and `stop` are wall-clock times ("14:00"), empty to leave unset.
+    /// Without a stop time, `stop_after_mins` (if non-zero) ends the recording
+    /// that long after it starts.
+    pub fn from_inputs(start: &str, stop: &str, stop_after_mins: u32) -> Result<Self> {
+        let now = Local::now();
+
+        let start_at = match start.trim() {
+            "" => None,
+            time => Some(next_occurrence(parse_time_of_day(time)?, now)),
+        };
+        let begins = start_at.unwrap_or(now);
+
+        let stop_at = match stop.trim() {
+            "" if stop_after_mins > 0 => Some(begins + Duration::minutes(stop_after_mins as i64)),
+            "" => None,
+            time => Some(next_occurrence(parse_time_of_day(time)?, begins)),
+        };
+
+        if start_at.is_none() && stop_at.is_none() {
+            anyhow::bail!("Set a start time, a stop time or a duration");
+        }
+
+        Ok(Self { start_at, stop_at })
+    }
+
+    /// Run the schedule on a background task, calling `on_start` and `on_stop`
+    /// when their times arrive. Abort the returned handle to cancel it.
+    pub fn spawn<S, E>(&self, on_start: S, on_stop: E) -> JoinHandle<()>
+    where
+        S: FnOnce() + Send + 'static,
+        E: FnOnce() + Send + 'static,
+    {
+        let start_at = self.start_at;
+        let stop_at = self.stop_at;
+
+        tokio::spawn(async move {
+            if let Some(start_at) = start_at {
+                sleep_until(start_at).await;
+                on_start();
+            }
+            if let Some(stop_at) = stop_at {
+                sleep_until(stop_at).await;
+                on_stop();
+            }
+        })
+    }
+}
+
+/// Parse "HH:MM" (or "HH:MM:SS") as a time of day
+fn parse_time_of_day(input: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(input, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M:%S"))
+        .with_context(|| format!("Invalid time '{}', expected HH:MM", input))
+}
+
+/// The first time after `after` that the clock shows `time`
+fn next_occurrence(time: NaiveTime, after: DateTime<Local>) -> DateTime<Local> {
+    let mut date = after.date_naive();
+    loop {
+        if let Some(candidate) = date.and_time(time).and_local_timezone(Local).earliest()
+            && candidate > after
+        {
+            return candidate;
+        }
+        date = date.succ_opt().expect("date out of range");
+    }
+}
+
+async fn sleep_until(when: DateTime<Local>) {
+    if let Ok(delay) = (when - Local::now()).to_std() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Format a countdown as H:MM:SS or M:SS
+pub fn format_remaining(remaining: Duration) -> String {
+    let secs = remaining.num_seconds().max(0);
+    let (hours, minutes, seconds) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
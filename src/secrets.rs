@@ -0,0 +1,43 @@
+use anyhow::Result;
+
+/// Service the keys are stored under, each as its own entry
+const SERVICE: &str = "audio-assistant";
+
+/// The secret stored as `name` in the OS credential store (Keychain, Windows
+/// Credential Manager or the Secret Service), or None when there is none
+pub fn load(name: &str) -> Result<Option<String>> {
+    match keyring::Entry::new(SERVICE, name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Store `secret` as `name`; an empty one deletes the entry
+pub fn store(name: &str, secret: &str) -> Result<()> {
+    let entry = keyring::Entry::new(SERVICE, name)?;
+    if !secret.is_empty() {
+        entry.set_password(secret)?;
+        return Ok(());
+    }
+    match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `secret` hidden but for its last four characters, "sk-…abcd", so the
+/// user can tell which key is set; short ones are hidden entirely
+pub fn mask(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() < 12 {
+        return "•".repeat(8);
+    }
+    let prefix: String = if secret.starts_with("sk-") {
+        "sk-".to_string()
+    } else {
+        String::new()
+    };
+    let last: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", prefix, last)
+}
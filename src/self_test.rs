@@ -0,0 +1,108 @@
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::audio_capture::{AudioCapture, InputLevel};
+
+/// How long the capture self-test records
+pub const SELF_TEST_DURATION: Duration = Duration::from_secs(3);
+
+/// Peak below which captured audio counts as silent (about -70 dBFS); even a
+/// quiet room gives a live microphone more noise than this
+const SILENT_PEAK: f32 = 0.0003;
+
+/// Fraction of the expected samples that must arrive for the stream to count
+/// as flowing
+const MIN_DELIVERED: f32 = 0.5;
+
+/// What the self-test found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTestVerdict {
+    /// Audio with some signal in it arrived
+    Flowing,
+    /// The stream delivered little or nothing
+    Stalled,
+    /// Only exact zeros arrived
+    DigitalSilence,
+    /// Samples arrived but stayed below the noise floor of a live input
+    NearSilence,
+}
+
+/// Result of recording a few seconds from the selected input
+#[derive(Debug, Clone)]
+pub struct SelfTestReport {
+    pub device: String,
+    pub verdict: SelfTestVerdict,
+    pub level: InputLevel,
+    /// Seconds of audio that arrived out of `SELF_TEST_DURATION`
+    pub received_secs: f32,
+}
+
+impl SelfTestReport {
+    pub fn from_samples(device: String, samples: &[f32], sample_rate: u32) -> Self {
+        let received_secs = samples.len() as f32 / sample_rate.max(1) as f32;
+        let level = InputLevel::measure(samples);
+
+        let verdict = if received_secs < SELF_TEST_DURATION.as_secs_f32() * MIN_DELIVERED {
+            SelfTestVerdict::Stalled
+        } else if level.peak == 0.0 {
+            SelfTestVerdict::DigitalSilence
+        } else if level.peak < SILENT_PEAK {
+            SelfTestVerdict::NearSilence
+        } else {
+            SelfTestVerdict::Flowing
+        };
+
+        Self {
+            device,
+            verdict,
+            level,
+            received_secs,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.verdict == SelfTestVerdict::Flowing
+    }
+
+    /// One-line result with advice for the likely cause when it failed
+    pub fn summary(&self) -> String {
+        let level = format!(
+            "peak {:.1} dBFS, RMS {:.1} dBFS",
+            20.0 * self.level.peak.max(1e-6).log10(),
+            20.0 * self.level.rms.max(1e-6).log10()
+        );
+        match self.verdict {
+            SelfTestVerdict::Flowing => {
+                format!("Audio is flowing from {} ({})", self.device, level)
+            }
+            SelfTestVerdict::Stalled => format!(
+                "Only {:.1}s of {}s arrived from {}: the device may be in use, \
+                 disconnected or, for loopback capture, nothing is playing",
+                self.received_secs,
+                SELF_TEST_DURATION.as_secs(),
+                self.device
+            ),
+            SelfTestVerdict::DigitalSilence => format!(
+                "{} delivered only digital silence: the source is muted or this \
+                 is the wrong device",
+                self.device
+            ),
+            SelfTestVerdict::NearSilence => format!(
+                "{} is almost silent ({}): check that the right device is selected \
+                 and not muted, and that something is playing or someone is speaking",
+                self.device, level
+            ),
+        }
+    }
+}
+
+/// Record `SELF_TEST_DURATION` from `capture` and check that real audio arrived
+pub fn run(capture: &mut AudioCapture, sample_rate: u32) -> Result<SelfTestReport> {
+    let samples = capture.record_samples(SELF_TEST_DURATION)?;
+    let device = capture
+        .stream_info()
+        .map(|info| info.device_name.clone())
+        .unwrap_or_else(|| "the input".to_string());
+
+    Ok(SelfTestReport::from_samples(device, &samples, sample_rate))
+}
@@ -0,0 +1,73 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::summarization::{self, Summarizer, TokenUsage};
+use crate::transcription::TranscriptionResult;
+
+const INSTRUCTIONS: &str = "You rate the sentiment and tone of a stretch of a conversation \
+transcript, such as part of a customer call. Reply with JSON only, in this format:
+
+{\"score\": -1 to 1, \"tension\": 0 to 1, \"tone\": \"one to three words\"}
+
+score is the sentiment, from -1 (angry, upset, negative) through 0 (neutral) to 1 (happy, \
+positive). tension is how heated it is, from 0 (calm) to 1 (confrontational). tone describes it, \
+e.g. \"frustrated\", \"friendly\" or \"matter-of-fact\".";
+
+/// How a segment comes across, for reviewing calls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sentiment {
+    /// From -1 (negative) through 0 (neutral) to 1 (positive)
+    pub score: f32,
+    /// From 0 (calm) to 1 (heated)
+    pub tension: f32,
+    /// A word or two, e.g. "frustrated"
+    pub tone: String,
+}
+
+impl Sentiment {
+    /// "frustrated, -0.6, tension 0.8"
+    pub fn describe(&self) -> String {
+        format!(
+            "{}, {:+.1}, tension {:.1}",
+            self.tone, self.score, self.tension
+        )
+    }
+}
+
+/// Rate the sentiment of `text` with the chat model, returning the tokens
+/// spent along with it
+pub async fn analyze(chat: &Summarizer, text: &str) -> Result<(Sentiment, Option<TokenUsage>)> {
+    let reply = chat
+        .complete("Sentiment request", INSTRUCTIONS, text.to_string(), 0.0)
+        .await?;
+    let mut sentiment: Sentiment = summarization::parse_json(&reply.content)?;
+    sentiment.score = sentiment.score.clamp(-1.0, 1.0);
+    sentiment.tension = sentiment.tension.clamp(0.0, 1.0);
+    sentiment.tone = sentiment.tone.trim().to_lowercase();
+    Ok((sentiment, reply.usage))
+}
+
+/// Average score and number of rated segments per speaker, in the order
+/// they first spoke. Segments without a speaker count as "Everyone".
+pub fn by_speaker<'a>(
+    segments: impl IntoIterator<Item = &'a TranscriptionResult>,
+) -> Vec<(String, f32, usize)> {
+    let mut speakers: Vec<(String, f32, usize)> = Vec::new();
+    for segment in segments {
+        let Some(sentiment) = &segment.sentiment else {
+            continue;
+        };
+        let speaker = segment.speaker().unwrap_or_else(|| "Everyone".to_string());
+        match speakers.iter_mut().find(|(name, _, _)| *name == speaker) {
+            Some((_, total, count)) => {
+                *total += sentiment.score;
+                *count += 1;
+            }
+            None => speakers.push((speaker, sentiment.score, 1)),
+        }
+    }
+    for (_, total, count) in &mut speakers {
+        *total /= *count as f32;
+    }
+    speakers
+}
@@ -0,0 +1,87 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::summarization::{self, Summarizer, TokenUsage};
+
+/// Segments transcribed before a session is titled
+pub const TITLE_AFTER_SEGMENTS: usize = 3;
+
+/// Characters of transcript sent for a title; the start of a long session
+/// says enough about it
+const MAX_TRANSCRIPT_CHARS: usize = 12_000;
+
+/// Tags kept at most
+const MAX_TAGS: usize = 5;
+
+const INSTRUCTIONS: &str = "You name recorded conversations, such as meetings and calls, from \
+their transcript. Reply with JSON only, in this format:
+
+{\"title\": \"a short descriptive title, at most eight words\", \"tags\": [\"3 to 5 topic tags\"]}
+
+Tags are one or two lowercase words each, e.g. \"kubernetes\", \"hiring\" or \"q3 budget\". \
+Don't put quotes or a date in the title.";
+
+/// A short name for a session and the topics it covers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTitle {
+    pub title: String,
+    pub tags: Vec<String>,
+}
+
+impl SessionTitle {
+    /// The title in lowercase ASCII words joined by dashes, for file names:
+    /// "kubernetes-migration-planning"
+    pub fn slug(&self) -> String {
+        slug(&self.title)
+    }
+}
+
+/// `title` in lowercase ASCII words joined by dashes, at most 50 characters
+pub fn slug(title: &str) -> String {
+    let words: Vec<String> = title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let mut slug = String::new();
+    for word in words {
+        if slug.len() + word.len() + 1 > 50 {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word);
+    }
+    slug
+}
+
+/// Title and tag the session `transcript` comes from with the chat model,
+/// returning the tokens spent along with them
+pub async fn generate(
+    chat: &Summarizer,
+    transcript: &str,
+) -> Result<(SessionTitle, Option<TokenUsage>)> {
+    let transcript: String = transcript.chars().take(MAX_TRANSCRIPT_CHARS).collect();
+    let reply = chat
+        .complete("Session title request", INSTRUCTIONS, transcript, 0.3)
+        .await?;
+    let found: SessionTitle = summarization::parse_json(&reply.content)?;
+
+    let title = found
+        .title
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .to_string();
+    if title.is_empty() {
+        anyhow::bail!("The model gave the session no title");
+    }
+    let mut tags: Vec<String> = Vec::new();
+    for tag in found.tags {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) && tags.len() < MAX_TAGS {
+            tags.push(tag);
+        }
+    }
+    Ok((SessionTitle { title, tags }, reply.usage))
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+use crate::chunk_writer::{self, ChunkMetadata};
+
+/// Manifest of spooled chunks, kept next to them in the chunks directory
+const MANIFEST_NAME: &str = "pending_uploads.json";
+
+/// How long a connectivity probe waits for the API host to answer
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A chunk waiting for the network to come back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpooledChunk {
+    pub audio_file: PathBuf,
+    pub metadata: ChunkMetadata,
+    pub queued_at: DateTime<Utc>,
+    /// Why the chunk couldn't be transcribed
+    pub last_error: String,
+}
+
+/// Chunks whose transcription failed because the API was unreachable,
+/// persisted so they survive a restart and are retried in recording order
+#[derive(Debug)]
+pub struct UploadSpool {
+    manifest: PathBuf,
+    chunks: Vec<SpooledChunk>,
+}
+
+impl UploadSpool {
+    /// Load the spool for `chunks_dir`, forgetting chunks whose audio is gone
+    pub fn load(chunks_dir: &Path) -> Self {
+        let manifest = chunks_dir.join(MANIFEST_NAME);
+        let mut chunks: Vec<SpooledChunk> = fs::read_to_string(&manifest)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        chunks.retain(|chunk| chunk.audio_file.exists());
+
+        Self { manifest, chunks }
+    }
+
+    pub fn chunks(&self) -> &[SpooledChunk] {
+        &self.chunks
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn contains(&self, audio_file: &Path) -> bool {
+        self.chunks
+            .iter()
+            .any(|chunk| chunk.audio_file == audio_file)
+    }
+
+    /// Add a chunk, keeping the spool in recording order
+    pub fn push(
+        &mut self,
+        audio_file: PathBuf,
+        metadata: ChunkMetadata,
+        error: String,
+    ) -> Result<()> {
+        if !self.contains(&audio_file) {
+            let position = self
+                .chunks
+                .partition_point(|chunk| chunk.metadata.recorded_at <= metadata.recorded_at);
+            self.chunks.insert(
+                position,
+                SpooledChunk {
+                    audio_file,
+                    metadata,
+                    queued_at: Utc::now(),
+                    last_error: error,
+                },
+            );
+        }
+        self.save()
+    }
+
+    /// Empty the spool, returning its chunks for another attempt
+    pub fn take_all(&mut self) -> Result<Vec<SpooledChunk>> {
+        let chunks = std::mem::take(&mut self.chunks);
+        self.save()?;
+        Ok(chunks)
+    }
+
+    /// Write the manifest, or remove it once nothing is waiting
+    fn save(&self) -> Result<()> {
+        if self.chunks.is_empty() {
+            if self.manifest.exists() {
+                fs::remove_file(&self.manifest).context("Failed to remove upload manifest")?;
+            }
+            return Ok(());
+        }
+
+        // Replaced in one step so a crash never leaves half a manifest
+        let partial = chunk_writer::partial_path(&self.manifest);
+        fs::write(&partial, serde_json::to_string_pretty(&self.chunks)?)
+            .context("Failed to write upload manifest")?;
+        fs::rename(&partial, &self.manifest).context("Failed to write upload manifest")?;
+        Ok(())
+    }
+}
+
+/// Whether the host behind `url` accepts connections, as a cheap check that
+/// the network is back before retrying spooled chunks
+pub async fn is_reachable(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host, port))).await,
+        Ok(Ok(_))
+    )
+}
@@ -0,0 +1,175 @@
+use crate::transcription::TranscriptionResult;
+
+/// Most words shown in one cue when phrase timings don't split them sooner
+const MAX_CUE_WORDS: usize = 12;
+
+/// Shortest time a cue stays on screen
+const MIN_CUE_SECS: f64 = 0.5;
+
+/// How long a cue stays up when the length of its audio is unknown
+const FALLBACK_CUE_SECS: f64 = 5.0;
+
+/// One subtitle, timed from the start of its audio file until `to_srt`
+/// shifts it onto the session's clock
+struct Cue {
+    start_secs: f64,
+    end_secs: f64,
+    text: String,
+}
+
+/// SRT subtitles for a set of transcript segments, timed from when the
+/// earliest one was recorded
+pub fn to_srt(results: &[&TranscriptionResult]) -> String {
+    let Some(session_start) = results.iter().map(|r| r.recorded_at()).min() else {
+        return String::new();
+    };
+
+    let mut cues: Vec<Cue> = results
+        .iter()
+        .flat_map(|result| {
+            let offset = (result.recorded_at() - session_start).num_milliseconds() as f64 / 1000.0;
+            cues_for(result).into_iter().map(move |cue| Cue {
+                start_secs: cue.start_secs + offset,
+                end_secs: cue.end_secs.max(cue.start_secs + MIN_CUE_SECS) + offset,
+                text: cue.text,
+            })
+        })
+        .collect();
+    cues.sort_by(|a, b| a.start_secs.total_cmp(&b.start_secs));
+
+    cues.iter()
+        .enumerate()
+        .map(|(i, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                format_timestamp(cue.start_secs),
+                format_timestamp(cue.end_secs),
+                cue.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Cues for one segment from the most precise timings its engine reported
+fn cues_for(result: &TranscriptionResult) -> Vec<Cue> {
+    let speaker = result.speaker();
+    let label = |text: &str| match &speaker {
+        Some(speaker) => format!("{}: {}", speaker, text),
+        None => text.to_string(),
+    };
+    let duration = result
+        .metadata
+        .as_ref()
+        .map_or(FALLBACK_CUE_SECS, |m| m.duration_secs);
+
+    if !result.words.is_empty() {
+        return word_cues(result)
+            .into_iter()
+            .map(|cue| Cue {
+                text: label(&cue.text),
+                ..cue
+            })
+            .collect();
+    }
+
+    if !result.segments.is_empty() {
+        return result
+            .segments
+            .iter()
+            .map(|s| Cue {
+                start_secs: s.start_secs,
+                end_secs: s.end_secs,
+                text: label(&s.text),
+            })
+            .collect();
+    }
+
+    if !result.utterances.is_empty() {
+        return result
+            .utterances
+            .iter()
+            .enumerate()
+            .map(|(i, u)| Cue {
+                start_secs: u.start_secs,
+                end_secs: result
+                    .utterances
+                    .get(i + 1)
+                    .map_or(duration, |next| next.start_secs),
+                text: label(&format!("{}: {}", u.speaker, u.text)),
+            })
+            .collect();
+    }
+
+    vec![Cue {
+        start_secs: 0.0,
+        end_secs: duration,
+        text: label(&result.text),
+    }]
+}
+
+/// Cues built from the punctuated text and its word timings, breaking at
+/// phrase boundaries and every `MAX_CUE_WORDS` words
+fn word_cues(result: &TranscriptionResult) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut current: Option<Cue> = None;
+    let mut words_in_cue = 0;
+    let mut segment = 0;
+
+    for (token, word) in result.timed_words() {
+        if let Some(word) = word {
+            let mut new_phrase = false;
+            while result
+                .segments
+                .get(segment)
+                .is_some_and(|s| s.end_secs <= word.start_secs)
+            {
+                segment += 1;
+                new_phrase = true;
+            }
+            if new_phrase || words_in_cue >= MAX_CUE_WORDS {
+                cues.extend(current.take());
+                words_in_cue = 0;
+            }
+        }
+
+        match current.as_mut() {
+            Some(cue) => {
+                cue.text.push(' ');
+                cue.text.push_str(token);
+                if let Some(word) = word {
+                    cue.end_secs = word.end_secs;
+                }
+            }
+            None => {
+                // Words that couldn't be matched continue from the last cue
+                let start = word.map_or_else(
+                    || cues.last().map_or(0.0, |cue: &Cue| cue.end_secs),
+                    |w| w.start_secs,
+                );
+                current = Some(Cue {
+                    start_secs: start,
+                    end_secs: word.map_or(start, |w| w.end_secs),
+                    text: token.to_string(),
+                });
+            }
+        }
+        words_in_cue += 1;
+    }
+
+    cues.extend(current);
+    cues
+}
+
+/// SRT time, e.g. "00:01:02,345"
+fn format_timestamp(secs: f64) -> String {
+    let millis = (secs.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
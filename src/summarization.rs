@@ -1,13 +1,57 @@
 use anyhow::{Context, Result};
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::api_error::ApiError;
+use crate::glossary::GlossaryTerm;
+use crate::http;
+use crate::openai_api::ApiEndpoint;
+use crate::presets::{Analysis, AnalysisPreset};
+use crate::retry::RetryPolicy;
+use crate::session_title;
+use crate::tokens;
+use crate::transcription::TalkTime;
 
 #[derive(Debug, Serialize)]
 struct ChatRequest {
     model: String,
     messages: Vec<ChatMessage>,
     temperature: f32,
+    /// Send the answer as server-sent events while it is written
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+    /// Longest answer the model may write; left out for the model's default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+}
+
+/// Holds the answer to a JSON schema (structured outputs), so it can't come
+/// back wrapped in Markdown or with fields missing
+#[derive(Debug, Clone, Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    json_schema: JsonSchema,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct JsonSchema {
+    name: &'static str,
+    strict: bool,
+    schema: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    /// Report the tokens used in a last event, for the cost estimate
+    include_usage: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,6 +63,30 @@ struct ChatMessage {
 #[derive(Debug, Deserialize)]
 struct ChatResponse {
     choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+/// Tokens a chat completion was billed for
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+
+    /// "1200 prompt + 350 completion tokens"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} prompt + {} completion tokens",
+            self.prompt_tokens, self.completion_tokens
+        )
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,139 +94,1434 @@ struct ChatChoice {
     message: ChatMessage,
 }
 
+/// One `data:` event of a streamed chat completion
+#[derive(Debug, Deserialize)]
+struct ChatChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    /// Only in the last event, with `include_usage`
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: ChunkDelta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChunkDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+const SUMMARY_INSTRUCTIONS: &str = r#"You are an AI assistant that summarizes conversations and extracts action items.
+
+Your task:
+1. Provide a concise summary of the conversation
+2. Extract any action items, tasks, or to-dos mentioned, each with who takes it on and by when if the conversation says
+3. Return the result in the following JSON format:
+
+{
+  "summary": "Brief summary of the conversation...",
+  "action_items": [{"task": "What to do", "owner": "Name or null", "due": "When or null"}, ...]
+}
+
+If there are no action items, return an empty array. Do not invent owners or dates."#;
+
+/// For questions asked in the chat panel; the transcript follows
+const QUESTION_INSTRUCTIONS: &str = "You answer questions about a conversation from its \
+transcript, which follows. Answer from the transcript only, and when it doesn't say, say so \
+rather than guessing. Keep answers short, and name who said what where it helps.
+
+Transcript:
+
+";
+
+/// Added to the instructions when summaries of the parts of a conversation
+/// too long for one request are combined
+const COMBINE_INSTRUCTIONS: &str = "
+
+The conversation was too long to read at once, so you are given summaries of its consecutive \
+parts instead, as JSON in the format above. Combine them into one summary of the whole \
+conversation in the same format: merge points that carry on from one part to the next, list \
+each action item once, and leave out those a later part says are done.";
+
+/// Sampling temperature of summaries unless configured otherwise
+pub const SUMMARY_TEMPERATURE: f32 = 0.3;
+
+/// Highest sampling temperature the chat API takes
+pub const MAX_TEMPERATURE: f32 = 2.0;
+
+/// Smallest answer length that fits a summary in JSON
+pub const MIN_MAX_TOKENS: u32 = 256;
+
+/// Tokens kept free in the context window for the model's answer, unless
+/// its length is capped
+const REPLY_TOKENS: usize = 4_096;
+
+/// Smallest window of transcript sent in one request, however small the
+/// context window is said to be
+const MIN_WINDOW_TOKENS: usize = 1_000;
+
+/// Between the segments of a transcript sent to the model
+const SEGMENT_SEPARATOR: &str = "\n\n--- Next segment ---\n\n";
+
+/// Added to the instructions when an earlier summary is brought up to date
+const UPDATE_INSTRUCTIONS: &str = "
+
+You are updating an earlier summary. You are given the summary so far as JSON and the part of \
+the conversation that came after it. Return the complete updated JSON in the same format: work \
+the new part into the summary, keep the earlier entries unless the new part settles or changes \
+them, and add new ones.";
+
+const MINUTES_INSTRUCTIONS: &str = r#"You are an AI assistant that writes minutes of meetings from their transcripts.
+
+Your task:
+1. Summarize the meeting in a few sentences
+2. List the attendees named or heard (speaker labels count), the agenda topics in the order they came up, the decisions made and the risks or open concerns raised
+3. Extract the action items, each with its owner and due date when the transcript says them
+4. Return the result in the following JSON format, with the summary first:
+
+{
+  "summary": "Brief summary of the meeting...",
+  "attendees": ["Name", ...],
+  "agenda": ["Topic", ...],
+  "decisions": ["Decision", ...],
+  "risks": ["Risk", ...],
+  "action_items": [{"task": "What to do", "owner": "Name or null", "due": "When or null"}, ...]
+}
+
+Use empty arrays for sections with nothing in them. Do not invent owners, dates or attendees."#;
+
+const SPEAKERS_INSTRUCTIONS: &str = r#"You are an AI assistant that summarizes conversations speaker by speaker.
+
+Your task:
+1. Provide a concise summary of the conversation
+2. For each speaker, list what they contributed in short points, such as what they committed to, proposed, decided, asked or raised as a concern, e.g. "Committed to sending the deck by Friday" or "Raised a concern about the migration timeline"
+3. Extract any action items, each with who takes it on and by when if the conversation says
+4. Return the result in the following JSON format, with the summary first:
+
+{
+  "summary": "Brief summary of the conversation...",
+  "speakers": [{"speaker": "Name or label", "contributions": ["Point", ...]}, ...],
+  "action_items": [{"task": "What to do", "owner": "Name or null", "due": "When or null"}, ...]
+}
+
+Name speakers by their speaker labels in the transcript (the text before the colon, e.g. "Speaker 1" or "Me"), or by their name when the conversation makes clear who a label is. Leave out speakers who said nothing of substance. Do not invent owners or dates."#;
+
+/// What summaries are made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryMode {
+    /// A short summary and the action items
+    #[default]
+    Summary,
+    /// Meeting minutes: attendees, agenda, decisions, risks and action
+    /// items with owners and due dates
+    Minutes,
+    /// What each speaker contributed, their talk time, and the action
+    /// items
+    Speakers,
+}
+
+impl SummaryMode {
+    pub const ALL: [SummaryMode; 3] = [
+        SummaryMode::Summary,
+        SummaryMode::Minutes,
+        SummaryMode::Speakers,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SummaryMode::Summary => "Summary",
+            SummaryMode::Minutes => "Meeting minutes",
+            SummaryMode::Speakers => "By speaker",
+        }
+    }
+
+    /// The JSON of the instructions as a strict schema. The summary comes
+    /// first so it streams in before the lists.
+    fn response_format(&self) -> ResponseFormat {
+        let strings = serde_json::json!({"type": "array", "items": {"type": "string"}});
+        let action_items = serde_json::json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "task": {"type": "string"},
+                    "owner": {"type": ["string", "null"]},
+                    "due": {"type": ["string", "null"]},
+                },
+                "required": ["task", "owner", "due"],
+                "additionalProperties": false,
+            },
+        });
+        let (name, schema) = match self {
+            SummaryMode::Summary => (
+                "summary",
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "summary": {"type": "string"},
+                        "action_items": action_items,
+                    },
+                    "required": ["summary", "action_items"],
+                    "additionalProperties": false,
+                }),
+            ),
+            SummaryMode::Minutes => (
+                "meeting_minutes",
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "summary": {"type": "string"},
+                        "attendees": strings,
+                        "agenda": strings,
+                        "decisions": strings,
+                        "risks": strings,
+                        "action_items": action_items,
+                    },
+                    "required": ["summary", "attendees", "agenda", "decisions", "risks", "action_items"],
+                    "additionalProperties": false,
+                }),
+            ),
+            SummaryMode::Speakers => (
+                "speaker_summary",
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "summary": {"type": "string"},
+                        "speakers": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "speaker": {"type": "string"},
+                                    "contributions": strings,
+                                },
+                                "required": ["speaker", "contributions"],
+                                "additionalProperties": false,
+                            },
+                        },
+                        "action_items": action_items,
+                    },
+                    "required": ["summary", "speakers", "action_items"],
+                    "additionalProperties": false,
+                }),
+            ),
+        };
+        ResponseFormat {
+            kind: "json_schema",
+            json_schema: JsonSchema {
+                name,
+                strict: true,
+                schema,
+            },
+        }
+    }
+}
+
+/// The sections of meeting minutes besides the summary itself
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeetingMinutes {
+    pub attendees: Vec<String>,
+    pub agenda: Vec<String>,
+    pub decisions: Vec<String>,
+    pub risks: Vec<String>,
+}
+
+/// What one speaker contributed, for summaries written by speaker
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeakerNotes {
+    pub speaker: String,
+    pub contributions: Vec<String>,
+}
+
+/// Whether an action item has been dealt with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionStatus {
+    #[default]
+    Open,
+    Done,
+}
+
+/// A task from a summary, with who takes it on and by when if that was
+/// said, and whether it is done
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionItem {
+    pub task: String,
+    pub owner: Option<String>,
+    /// As said in the meeting, e.g. "Friday" or "end of March"
+    pub due: Option<String>,
+    /// Ticked off in the summary panel; kept in the saved summary
+    pub status: ActionStatus,
+}
+
+impl ActionItem {
+    pub fn is_done(&self) -> bool {
+        self.status == ActionStatus::Done
+    }
+
+    /// The task in lower case without punctuation, to recognize it in
+    /// another summary
+    pub fn key(&self) -> String {
+        self.task
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn owner(&self) -> Option<&str> {
+        self.owner
+            .as_deref()
+            .map(str::trim)
+            .filter(|o| !o.is_empty())
+    }
+
+    pub fn due(&self) -> Option<&str> {
+        self.due.as_deref().map(str::trim).filter(|d| !d.is_empty())
+    }
+
+    /// "Send the deck (Priya, due Friday)"
+    pub fn describe(&self) -> String {
+        let details = match (self.owner(), self.due()) {
+            (Some(owner), Some(due)) => format!(" ({}, due {})", owner, due),
+            (Some(owner), None) => format!(" ({})", owner),
+            (None, Some(due)) => format!(" (due {})", due),
+            (None, None) => String::new(),
+        };
+        format!("{}{}", self.task, details)
+    }
+}
+
+/// An action item as saved: an object, or a plain line in summaries saved
+/// before action items had owners and statuses
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedActionItem {
+    Item(ActionItem),
+    Line(String),
+}
+
+fn action_items<'de, D>(deserializer: D) -> std::result::Result<Vec<ActionItem>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let saved = Vec::<SavedActionItem>::deserialize(deserializer)?;
+    Ok(saved
+        .into_iter()
+        .map(|item| match item {
+            SavedActionItem::Item(item) => item,
+            SavedActionItem::Line(task) => ActionItem {
+                task,
+                ..Default::default()
+            },
+        })
+        .collect())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SummaryResult {
     pub summary: String,
-    pub action_items: Vec<String>,
+    #[serde(deserialize_with = "action_items")]
+    pub action_items: Vec<ActionItem>,
+    /// The other sections, for summaries written as meeting minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minutes: Option<MeetingMinutes>,
+    /// Each speaker's contributions, for summaries written by speaker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speakers: Option<Vec<SpeakerNotes>>,
+    /// Talk time per speaker across the session, for summaries written by
+    /// speaker
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub talk_time: Vec<TalkTime>,
+    /// The sections of the analysis preset it was written with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<Analysis>,
+    /// The text summarized; for an update, only what was added
     pub original_text: String,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// API requests that failed and were retried before this succeeded
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32,
+    /// Chat model that wrote the summary
+    #[serde(default)]
+    pub model: String,
+    /// Sampling temperature it was written with; summaries saved before
+    /// it was kept have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Tokens the request was billed for, when the API reports them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+    /// Title of the session it summarizes, once it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Topic tags of the session
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Acronyms and domain terms of the session, explained
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub glossary: Vec<GlossaryTerm>,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+impl SummaryResult {
+    /// The summary in the JSON form the model writes, to hand back to it
+    /// for an update
+    fn to_json(&self) -> String {
+        if let Some(analysis) = &self.analysis {
+            let mut json = serde_json::Map::new();
+            json.insert("summary".to_string(), self.summary.clone().into());
+            for section in &analysis.sections {
+                json.insert(section.key.clone(), section.items.clone().into());
+            }
+            json.insert(
+                "action_items".to_string(),
+                serde_json::to_value(&self.action_items).unwrap_or_default(),
+            );
+            return serde_json::to_string_pretty(&json).unwrap_or_default();
+        }
+        if let Some(speakers) = &self.speakers {
+            let json = serde_json::json!({
+                "summary": self.summary,
+                "speakers": speakers,
+                "action_items": self.action_items,
+            });
+            return serde_json::to_string_pretty(&json).unwrap_or_default();
+        }
+        let json = match &self.minutes {
+            Some(minutes) => serde_json::json!({
+                "summary": self.summary,
+                "attendees": minutes.attendees,
+                "agenda": minutes.agenda,
+                "decisions": minutes.decisions,
+                "risks": minutes.risks,
+                "action_items": self.action_items,
+            }),
+            None => serde_json::json!({
+                "summary": self.summary,
+                "action_items": self.action_items,
+            }),
+        };
+        serde_json::to_string_pretty(&json).unwrap_or_default()
+    }
+
+    /// The kind of summary this is
+    pub fn mode(&self) -> SummaryMode {
+        if self.minutes.is_some() {
+            SummaryMode::Minutes
+        } else if self.speakers.is_some() {
+            SummaryMode::Speakers
+        } else {
+            SummaryMode::Summary
+        }
+    }
+
+    /// Id of the analysis preset it was written with, if any
+    pub fn preset(&self) -> Option<&str> {
+        self.analysis
+            .as_ref()
+            .map(|analysis| analysis.preset.as_str())
+    }
+
+    /// Mark the action items that were done in `earlier` summaries (newest
+    /// first) done here too, so a new summary doesn't reopen them
+    pub fn keep_statuses<'a>(&mut self, earlier: impl IntoIterator<Item = &'a SummaryResult>) {
+        // The newest summary with the item decides
+        let mut done = HashMap::new();
+        for summary in earlier {
+            for item in &summary.action_items {
+                done.entry(item.key()).or_insert(item.is_done());
+            }
+        }
+        for item in &mut self.action_items {
+            if done.get(&item.key()) == Some(&true) {
+                item.status = ActionStatus::Done;
+            }
+        }
+    }
+
+    /// Name of the file the summary is saved in, the same every time so
+    /// ticking off an action item saves over it
+    fn file_name(&self) -> String {
+        let timestamp = self.timestamp.format("%Y%m%d_%H%M%S");
+        match self.title.as_deref().map(session_title::slug) {
+            Some(slug) if !slug.is_empty() => format!("summary_{}_{}.json", timestamp, slug),
+            _ => format!("summary_{}.json", timestamp),
+        }
+    }
+
+    /// Whether the title or a tag contains `filter`, ignoring case
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+        filter.is_empty()
+            || self
+                .title
+                .as_ref()
+                .is_some_and(|title| title.to_lowercase().contains(&filter))
+            || self.tags.iter().any(|tag| tag.contains(&filter))
+    }
+
+    /// Save the summary to `output_dir`
+    pub async fn save(&self, output_dir: &Path) -> Result<PathBuf> {
+        let filepath = output_dir.join(self.file_name());
+        let json = serde_json::to_string_pretty(self)?;
+        tokio::fs::write(&filepath, json).await?;
+        Ok(filepath)
+    }
+
+    /// The summary, and with minutes their sections, as Markdown for
+    /// exports
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("{}\n\n", self.summary.trim());
+        let list = |markdown: &mut String, heading: &str, items: &[String]| {
+            if items.is_empty() {
+                return;
+            }
+            markdown.push_str(&format!("### {}\n\n", heading));
+            for item in items {
+                markdown.push_str(&format!("- {}\n", item));
+            }
+            markdown.push('\n');
+        };
+        if let Some(minutes) = &self.minutes {
+            if !minutes.attendees.is_empty() {
+                markdown.push_str(&format!(
+                    "**Attendees:** {}\n\n",
+                    minutes.attendees.join(", ")
+                ));
+            }
+            list(&mut markdown, "Agenda", &minutes.agenda);
+            list(&mut markdown, "Decisions", &minutes.decisions);
+            list(&mut markdown, "Risks", &minutes.risks);
+        }
+        if let Some(analysis) = &self.analysis {
+            for section in &analysis.sections {
+                if section.numbered && !section.items.is_empty() {
+                    markdown.push_str(&format!("### {}\n\n", section.heading));
+                    for (i, item) in section.items.iter().enumerate() {
+                        markdown.push_str(&format!("{}. {}\n", i + 1, item));
+                    }
+                    markdown.push('\n');
+                } else {
+                    list(&mut markdown, &section.heading, &section.items);
+                }
+            }
+        }
+        if let Some(speakers) = &self.speakers {
+            for notes in speakers {
+                list(
+                    &mut markdown,
+                    &format!("{} said", notes.speaker),
+                    &notes.contributions,
+                );
+            }
+        }
+        if !self.talk_time.is_empty() {
+            let total: f64 = self.talk_time.iter().map(|t| t.secs).sum();
+            markdown.push_str("### Talk Time\n\n");
+            for talk in &self.talk_time {
+                markdown.push_str(&format!("- {}: {}\n", talk.speaker, talk.describe(total)));
+            }
+            markdown.push('\n');
+        }
+        if !self.action_items.is_empty() {
+            // Task lists, ticked where done
+            markdown.push_str("### Action Items\n\n");
+            for item in &self.action_items {
+                let tick = if item.is_done() { 'x' } else { ' ' };
+                markdown.push_str(&format!("- [{}] {}\n", tick, item.describe()));
+            }
+            markdown.push('\n');
+        }
+        markdown
+    }
+
+    /// "gpt-4o-mini at 0.3", to tell versions apart
+    pub fn settings(&self) -> String {
+        match self.temperature {
+            Some(temperature) => format!("{} at {:.1}", self.model, temperature),
+            None => self.model.clone(),
+        }
+    }
+}
+
+/// A run of text two summary versions share, or that only one has
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Word by word, what changed from the Markdown of `old` to that of `new`
+pub fn compare(old: &SummaryResult, new: &SummaryResult) -> Vec<Change> {
+    let (old, new) = (old.to_markdown(), new.to_markdown());
+    let diff = similar::TextDiff::from_words(old.as_str(), new.as_str());
+    let mut changes: Vec<Change> = Vec::new();
+    for change in diff.iter_all_changes() {
+        let text = change.value();
+        match (changes.last_mut(), change.tag()) {
+            (Some(Change::Same(run)), similar::ChangeTag::Equal)
+            | (Some(Change::Removed(run)), similar::ChangeTag::Delete)
+            | (Some(Change::Added(run)), similar::ChangeTag::Insert) => run.push_str(text),
+            (_, similar::ChangeTag::Equal) => changes.push(Change::Same(text.to_string())),
+            (_, similar::ChangeTag::Delete) => changes.push(Change::Removed(text.to_string())),
+            (_, similar::ChangeTag::Insert) => changes.push(Change::Added(text.to_string())),
+        }
+    }
+    changes
+}
+
+/// How a summary is coming along, for the summary panel
+#[derive(Debug, Clone)]
+pub enum SummaryProgress {
+    /// The transcript is too long for one request: `done` of `total`
+    /// windows of it are summarized, or with all of them done, their
+    /// summaries are being combined
+    Parts { done: usize, total: usize },
+    /// The summary text written so far
+    Draft(String),
+}
+
+/// Tokens and retries of the requests that led up to the final one
+#[derive(Debug, Default)]
+struct Spent {
+    usage: Option<TokenUsage>,
+    retries: u32,
+}
+
+impl Spent {
+    fn add(&mut self, reply: &ChatReply) {
+        self.retries += reply.retries;
+        if let Some(usage) = &reply.usage {
+            self.usage
+                .get_or_insert_with(TokenUsage::default)
+                .add(usage);
+        }
+    }
+
+    /// Count them in the summary the final request wrote
+    fn charge(mut self, result: &mut SummaryResult) {
+        self.retries += result.retries;
+        if let Some(usage) = &result.usage {
+            self.usage
+                .get_or_insert_with(TokenUsage::default)
+                .add(usage);
+        }
+        result.retries = self.retries;
+        result.usage = self.usage;
+    }
+}
+
+/// A question asked about the transcript and the model's answer
+#[derive(Debug, Clone)]
+pub struct QuestionTurn {
+    pub question: String,
+    pub answer: String,
+}
+
+/// A chat model's answer to one request
+#[derive(Debug, Clone)]
+pub struct ChatReply {
+    pub content: String,
+    pub usage: Option<TokenUsage>,
+    /// API requests that failed and were retried first
+    pub retries: u32,
 }
 
 pub struct Summarizer {
-    api_key: String,
+    endpoint: ApiEndpoint,
     client: Client,
     model: String,
+    retry: RetryPolicy,
+    mode: SummaryMode,
+    /// Context window of the model; 0 goes by its name
+    context_tokens: usize,
+    /// Sampling temperature of summary requests
+    temperature: f32,
+    /// Longest answer to a request; `None` leaves it to the model
+    max_tokens: Option<u32>,
+    /// Sections to fill in instead of the mode's
+    preset: Option<AnalysisPreset>,
 }
 
 impl Summarizer {
-    pub fn new(api_key: String, model: String) -> Self {
+    pub fn new(endpoint: ApiEndpoint, model: String, retry: RetryPolicy) -> Self {
         Self {
-            api_key,
-            client: Client::new(),
+            endpoint,
+            client: http::client(),
             model,
+            retry,
+            mode: SummaryMode::Summary,
+            context_tokens: 0,
+            temperature: SUMMARY_TEMPERATURE,
+            max_tokens: None,
+            preset: None,
+        }
+    }
+
+    /// The same summarizer, for a model that reads `tokens` at once (0 to
+    /// go by the model's name)
+    pub fn with_context_tokens(self, tokens: usize) -> Self {
+        Self {
+            context_tokens: tokens,
+            ..self
+        }
+    }
+
+    /// The same summarizer, writing summaries at `temperature` instead of
+    /// `SUMMARY_TEMPERATURE`
+    pub fn with_temperature(self, temperature: f32) -> Self {
+        Self {
+            temperature,
+            ..self
+        }
+    }
+
+    /// The same summarizer, with answers of at most `tokens` (0 to leave
+    /// it to the model)
+    pub fn with_max_tokens(self, tokens: u32) -> Self {
+        Self {
+            max_tokens: (tokens > 0).then_some(tokens),
+            ..self
+        }
+    }
+
+    /// The same summarizer, writing summaries of the form `mode`
+    pub fn with_mode(self, mode: SummaryMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// The same summarizer, filling in the sections of `preset` instead of
+    /// those of the mode, when one is given
+    pub fn with_preset(self, preset: Option<AnalysisPreset>) -> Self {
+        Self { preset, ..self }
+    }
+
+    fn instructions(&self) -> String {
+        if let Some(preset) = &self.preset {
+            return preset.prompt();
+        }
+        match self.mode {
+            SummaryMode::Summary => SUMMARY_INSTRUCTIONS,
+            SummaryMode::Minutes => MINUTES_INSTRUCTIONS,
+            SummaryMode::Speakers => SPEAKERS_INSTRUCTIONS,
+        }
+        .to_string()
+    }
+
+    /// The JSON schema answers are held to: the preset's sections, or the
+    /// mode's
+    fn response_format(&self) -> ResponseFormat {
+        let Some(preset) = &self.preset else {
+            return self.mode.response_format();
+        };
+        let mut properties = serde_json::Map::new();
+        let mut required = vec!["summary".to_string()];
+        properties.insert("summary".to_string(), serde_json::json!({"type": "string"}));
+        for section in &preset.sections {
+            properties.insert(
+                section.key.clone(),
+                serde_json::json!({"type": "array", "items": {"type": "string"}}),
+            );
+            required.push(section.key.clone());
+        }
+        properties.insert(
+            "action_items".to_string(),
+            serde_json::json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "task": {"type": "string"},
+                        "owner": {"type": ["string", "null"]},
+                        "due": {"type": ["string", "null"]},
+                    },
+                    "required": ["task", "owner", "due"],
+                    "additionalProperties": false,
+                },
+            }),
+        );
+        required.push("action_items".to_string());
+        ResponseFormat {
+            kind: "json_schema",
+            json_schema: JsonSchema {
+                name: "analysis",
+                strict: true,
+                schema: serde_json::json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                    "additionalProperties": false,
+                }),
+            },
         }
     }
 
     /// Generate summary and extract action items from transcribed text
     pub async fn summarize(&self, text: &str) -> Result<SummaryResult> {
         println!("Generating summary for text of length: {}", text.len());
+        let (instructions, request, spent) = self.condense(&[text.to_string()], &|_| {}).await?;
+        let reply = self
+            .complete_summary("Summarization request", &instructions, request)
+            .await?;
+        let mut result = self.summary_result(text, reply)?;
+        spent.charge(&mut result);
+        Ok(result)
+    }
 
-        let system_prompt = r#"You are an AI assistant that summarizes conversations and extracts action items.
+    /// Like `summarize` for the segments of a conversation, streaming the
+    /// answer and reporting to `on_progress` as the summary comes along
+    async fn summarize_streaming<F>(
+        &self,
+        segments: &[String],
+        on_progress: F,
+    ) -> Result<SummaryResult>
+    where
+        F: Fn(SummaryProgress) + Send + Sync,
+    {
+        let text = segments.join(SEGMENT_SEPARATOR);
+        println!("Generating summary for text of length: {}", text.len());
+        let (instructions, request, spent) = self.condense(segments, &on_progress).await?;
+        let reply = self
+            .complete_streaming(
+                "Summarization request",
+                &instructions,
+                request,
+                Some(self.response_format()),
+                |content| {
+                    if let Some(summary) = partial_summary(content) {
+                        on_progress(SummaryProgress::Draft(summary));
+                    }
+                },
+            )
+            .await?;
+        let mut result = self.summary_result(&text, reply)?;
+        spent.charge(&mut result);
+        Ok(result)
+    }
 
-Your task:
-1. Provide a concise summary of the conversation
-2. Extract any action items, tasks, or to-dos mentioned
-3. Return the result in the following JSON format:
+    /// The instructions and request for summarizing `segments`. When they
+    /// don't fit in the model's context window, consecutive windows of them
+    /// are summarized first, and those summaries combined in rounds until
+    /// they fit (map-reduce); the request is then to combine them.
+    async fn condense(
+        &self,
+        segments: &[String],
+        on_progress: &(dyn Fn(SummaryProgress) + Send + Sync),
+    ) -> Result<(String, String, Spent)> {
+        let mut spent = Spent::default();
+        let window = self.window_tokens(&self.instructions());
+        let mut windows = self.windows(segments, SEGMENT_SEPARATOR, window);
+        if windows.len() <= 1 {
+            let text = segments.join(SEGMENT_SEPARATOR);
+            return Ok((self.instructions(), summary_request(&text), spent));
+        }
 
-{
-  "summary": "Brief summary of the conversation...",
-  "action_items": ["Action item 1", "Action item 2", ...]
-}
+        let total = windows.len();
+        println!(
+            "Transcript is over the {} tokens of one request; summarizing it in {} parts",
+            window, total
+        );
+        let mut parts = Vec::with_capacity(total);
+        for (index, text) in windows.drain(..).enumerate() {
+            on_progress(SummaryProgress::Parts { done: index, total });
+            let reply = self
+                .complete_summary(
+                    "Summarization request",
+                    &self.instructions(),
+                    part_request(index, total, &text),
+                )
+                .await
+                .with_context(|| format!("Summary of part {} of {} failed", index + 1, total))?;
+            spent.add(&reply);
+            parts.push(reply.content);
+        }
+        on_progress(SummaryProgress::Parts { done: total, total });
 
-If there are no action items, return an empty array."#;
+        // Summaries of parts that still don't fit together are combined a
+        // group at a time
+        let instructions = format!("{}{}", self.instructions(), COMBINE_INSTRUCTIONS);
+        let window = self.window_tokens(&instructions);
+        loop {
+            let groups = self.windows(&numbered(&parts), "\n\n", window);
+            if groups.len() <= 1 || groups.len() == parts.len() {
+                return Ok((instructions, combine_request(&numbered(&parts)), spent));
+            }
+            println!(
+                "Combining {} part summaries in {} groups",
+                parts.len(),
+                groups.len()
+            );
+            let mut combined = Vec::with_capacity(groups.len());
+            for group in groups {
+                let reply = self
+                    .complete_summary(
+                        "Summarization request",
+                        &instructions,
+                        format!("Summaries of consecutive parts, in order:\n\n{}", group),
+                    )
+                    .await
+                    .context("Combining part summaries failed")?;
+                spent.add(&reply);
+                combined.push(reply.content);
+            }
+            parts = combined;
+        }
+    }
 
-        let user_prompt = format!(
-            "Please summarize the following conversation and extract any action items:\n\n{}",
-            text
-        );
+    /// Tokens of transcript that fit in one request with `instructions`,
+    /// leaving room for the answer
+    fn window_tokens(&self, instructions: &str) -> usize {
+        let context = tokens::context_window(&self.model, self.context_tokens);
+        let overhead = tokens::count(&self.model, instructions)
+            + tokens::count(&self.model, &summary_request(""))
+            + self
+                .max_tokens
+                .map_or(REPLY_TOKENS, |tokens| tokens as usize);
+        // A tenth spare, as the count is of the text alone
+        (context.saturating_sub(overhead) * 9 / 10).max(MIN_WINDOW_TOKENS)
+    }
 
-        let request = ChatRequest {
-            model: self.model.clone(),
-            messages: vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: system_prompt.to_string(),
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: user_prompt,
-                },
-            ],
-            temperature: 0.3,
+    /// `pieces` joined by `separator` into as few texts as fit in `window`
+    /// tokens each, in order. A piece longer than a window is cut up
+    /// between words.
+    fn windows(&self, pieces: &[String], separator: &str, window: usize) -> Vec<String> {
+        let mut sized: Vec<(String, usize)> = Vec::new();
+        for piece in pieces {
+            let piece_tokens = tokens::count(&self.model, piece);
+            if piece_tokens <= window {
+                sized.push((piece.clone(), piece_tokens));
+                continue;
+            }
+            let words: Vec<&str> = piece.split_whitespace().collect();
+            // Words per window, going by this piece's tokens per word
+            let per_window = (words.len() * window / piece_tokens).max(1);
+            for chunk in words.chunks(per_window) {
+                let text = chunk.join(" ");
+                let text_tokens = tokens::count(&self.model, &text);
+                sized.push((text, text_tokens));
+            }
+        }
+
+        let separator_tokens = tokens::count(&self.model, separator);
+        let mut windows: Vec<(String, usize)> = Vec::new();
+        for (text, text_tokens) in sized {
+            match windows.last_mut() {
+                Some((joined, joined_tokens))
+                    if *joined_tokens + separator_tokens + text_tokens <= window =>
+                {
+                    joined.push_str(separator);
+                    joined.push_str(&text);
+                    *joined_tokens += separator_tokens + text_tokens;
+                }
+                _ => windows.push((text, text_tokens)),
+            }
+        }
+        windows.into_iter().map(|(text, _)| text).collect()
+    }
+
+    /// The summary and action items in the model's JSON answer
+    fn summary_result(&self, text: &str, reply: ChatReply) -> Result<SummaryResult> {
+        // Parse the JSON response from GPT
+        #[derive(Deserialize)]
+        struct GptOutput {
+            summary: String,
+            #[serde(deserialize_with = "action_items")]
+            action_items: Vec<ActionItem>,
+            #[serde(flatten)]
+            minutes: MeetingMinutes,
+            #[serde(default)]
+            speakers: Vec<SpeakerNotes>,
+        }
+
+        let output: GptOutput = parse_json(&reply.content)?;
+        let (summary, action_items) = (output.summary, output.action_items);
+        // A preset takes the place of the mode's sections
+        let mode = self.preset.is_none().then_some(self.mode);
+        let minutes = (mode == Some(SummaryMode::Minutes)).then_some(output.minutes);
+        let speakers = (mode == Some(SummaryMode::Speakers)).then_some(output.speakers);
+        let analysis = match &self.preset {
+            Some(preset) => Some(Analysis::from_answer(preset, &parse_json(&reply.content)?)),
+            None => None,
         };
 
-        let response = self
-            .client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&request)
-            .send()
+        println!("Summary generated: {}", summary);
+        println!("Action items found: {}", action_items.len());
+
+        Ok(SummaryResult {
+            summary,
+            action_items,
+            minutes,
+            speakers,
+            talk_time: Vec::new(),
+            analysis,
+            original_text: text.to_string(),
+            timestamp: chrono::Utc::now(),
+            retries: reply.retries,
+            model: self.model.clone(),
+            temperature: Some(self.temperature),
+            usage: reply.usage,
+            title: None,
+            tags: Vec::new(),
+            glossary: Vec::new(),
+        })
+    }
+
+    /// Send `system` instructions and the `user` message to the chat model
+    /// and return its answer
+    pub async fn complete(
+        &self,
+        what: &str,
+        system: &str,
+        user: String,
+        temperature: f32,
+    ) -> Result<ChatReply> {
+        self.complete_with(what, system, user, temperature, None)
             .await
-            .context("Failed to send summarization request")?;
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!(
-                "OpenAI API request failed with status {}: {}",
-                status,
-                error_text
-            );
-        }
+    /// `complete` held to the JSON schema of summaries of this mode
+    async fn complete_summary(&self, what: &str, system: &str, user: String) -> Result<ChatReply> {
+        let format = self.response_format();
+        self.complete_with(what, system, user, self.temperature, Some(format))
+            .await
+    }
+
+    async fn complete_with(
+        &self,
+        what: &str,
+        system: &str,
+        user: String,
+        temperature: f32,
+        format: Option<ResponseFormat>,
+    ) -> Result<ChatReply> {
+        let request = self.chat_request(prompt(system, user), temperature, false, format);
+        let (response, retries) = self.send(what, request).await?;
 
         let chat_response: ChatResponse = response
             .json()
             .await
             .context("Failed to parse chat response")?;
 
-        let content = &chat_response
+        let content = chat_response
             .choices
-            .first()
+            .into_iter()
+            .next()
             .context("No response from GPT")?
             .message
             .content;
 
-        // Parse the JSON response from GPT
-        #[derive(Deserialize)]
-        struct GptOutput {
-            summary: String,
-            action_items: Vec<String>,
-        }
+        Ok(ChatReply {
+            content,
+            usage: chat_response.usage,
+            retries,
+        })
+    }
+
+    /// Like `complete` for summaries, with the answer in `format` streamed
+    /// as server-sent events; `on_content` gets the answer so far after
+    /// every piece of it
+    async fn complete_streaming<F>(
+        &self,
+        what: &str,
+        system: &str,
+        user: String,
+        format: Option<ResponseFormat>,
+        on_content: F,
+    ) -> Result<ChatReply>
+    where
+        F: Fn(&str) + Send + Sync,
+    {
+        let temperature = self.temperature;
+        self.stream_messages(what, prompt(system, user), temperature, format, on_content)
+            .await
+    }
 
-        let gpt_output: GptOutput =
-            serde_json::from_str(content).context("Failed to parse GPT JSON output")?;
+    /// Stream the answer to a whole conversation of `messages`
+    async fn stream_messages<F>(
+        &self,
+        what: &str,
+        messages: Vec<ChatMessage>,
+        temperature: f32,
+        format: Option<ResponseFormat>,
+        on_content: F,
+    ) -> Result<ChatReply>
+    where
+        F: Fn(&str) + Send + Sync,
+    {
+        let request = self.chat_request(messages, temperature, true, format);
+        let (mut response, retries) = self.send(what, request).await?;
 
-        println!("Summary generated: {}", gpt_output.summary);
-        println!("Action items found: {}", gpt_output.action_items.len());
+        let mut content = String::new();
+        let mut usage = None;
+        // Bytes of a line that hasn't fully arrived yet
+        let mut pending: Vec<u8> = Vec::new();
+        'events: while let Some(bytes) = response
+            .chunk()
+            .await
+            .context("The streamed chat response broke off")?
+        {
+            pending.extend_from_slice(&bytes);
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if data == "[DONE]" {
+                    break 'events;
+                }
+                let chunk: ChatChunk =
+                    serde_json::from_str(data).context("Failed to parse chat stream event")?;
+                usage = chunk.usage.or(usage);
+                let delta = chunk
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.delta.content);
+                if let Some(delta) = delta.filter(|delta| !delta.is_empty()) {
+                    content.push_str(&delta);
+                    on_content(&content);
+                }
+            }
+        }
 
-        Ok(SummaryResult {
-            summary: gpt_output.summary,
-            action_items: gpt_output.action_items,
-            original_text: text.to_string(),
-            timestamp: chrono::Utc::now(),
+        if content.is_empty() {
+            anyhow::bail!("No response from GPT");
+        }
+        Ok(ChatReply {
+            content,
+            usage,
+            retries,
         })
     }
 
-    /// Save summary result to a file
-    pub async fn save_summary(
+    fn chat_request(
         &self,
-        result: &SummaryResult,
-        output_dir: &PathBuf,
-    ) -> Result<PathBuf> {
-        let timestamp = result.timestamp.format("%Y%m%d_%H%M%S");
-        let filename = format!("summary_{}.json", timestamp);
-        let filepath = output_dir.join(filename);
-
-        let json = serde_json::to_string_pretty(result)?;
-        tokio::fs::write(&filepath, json).await?;
+        messages: Vec<ChatMessage>,
+        temperature: f32,
+        stream: bool,
+        response_format: Option<ResponseFormat>,
+    ) -> ChatRequest {
+        ChatRequest {
+            model: self.model.clone(),
+            messages,
+            temperature,
+            stream,
+            stream_options: stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
+            response_format,
+            max_tokens: self.max_tokens,
+        }
+    }
 
+    /// Post a chat request, returning the response if it succeeded. A
+    /// server that doesn't take JSON schemas (older Azure API versions,
+    /// some self-hosted ones) is asked again without one.
+    async fn send(&self, what: &str, request: ChatRequest) -> Result<(reqwest::Response, u32)> {
+        match self.post(what, &request).await {
+            Err(e) if request.response_format.is_some() && rejects_schema(&e) => {
+                eprintln!(
+                    "{} doesn't take a JSON schema; asking without one: {:#}",
+                    self.model, e
+                );
+                let request = ChatRequest {
+                    response_format: None,
+                    ..request
+                };
+                self.post(what, &request).await
+            }
+            sent => sent,
+        }
+    }
+
+    async fn post(&self, what: &str, request: &ChatRequest) -> Result<(reqwest::Response, u32)> {
+        let (response, retries) = self
+            .retry
+            .send(what, || {
+                self.endpoint
+                    .post(&self.client, "chat/completions")
+                    .json(request)
+            })
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::from_response("OpenAI API request", response)
+                .await
+                .into());
+        }
+        Ok((response, retries))
+    }
+
+    /// Save summary result to a file
+    pub async fn save_summary(&self, result: &SummaryResult, output_dir: &Path) -> Result<PathBuf> {
+        let filepath = result.save(output_dir).await?;
         println!("Summary saved to: {:?}", filepath);
         Ok(filepath)
     }
 
-    /// Generate a cumulative summary from multiple transcription chunks
-    pub async fn summarize_conversation(&self, transcriptions: &[String]) -> Result<SummaryResult> {
-        let combined_text = transcriptions.join("\n\n--- Next segment ---\n\n");
-        self.summarize(&combined_text).await
+    /// Bring `previous` up to date with `new_transcriptions`, the segments
+    /// that came after the ones it covers, instead of summarizing the whole
+    /// conversation again, reporting to `on_progress` as it streams in
+    pub async fn update_summary<F>(
+        &self,
+        previous: &SummaryResult,
+        new_transcriptions: &[String],
+        on_progress: F,
+    ) -> Result<SummaryResult>
+    where
+        F: Fn(SummaryProgress) + Send + Sync,
+    {
+        let new_text = new_transcriptions.join(SEGMENT_SEPARATOR);
+        println!(
+            "Updating summary with {} new segments ({} characters)",
+            new_transcriptions.len(),
+            new_text.len()
+        );
+        let instructions = format!("{}{}", self.instructions(), UPDATE_INSTRUCTIONS);
+        let request = format!(
+            "Summary so far:\n\n{}\n\nWhat was said since:\n\n{}",
+            previous.to_json(),
+            new_text
+        );
+        let reply = self
+            .complete_streaming(
+                "Summary update request",
+                &instructions,
+                request,
+                Some(self.response_format()),
+                |content| {
+                    if let Some(summary) = partial_summary(content) {
+                        on_progress(SummaryProgress::Draft(summary));
+                    }
+                },
+            )
+            .await?;
+        self.summary_result(&new_text, reply)
+    }
+
+    /// Answer `question` about the conversation in `transcriptions`, with
+    /// the earlier questions and answers in `history` for follow-ups,
+    /// calling `on_answer` with the answer so far as it streams in. Only
+    /// the latest segments are sent when the whole transcript doesn't fit.
+    pub async fn ask<F>(
+        &self,
+        transcriptions: &[String],
+        history: &[QuestionTurn],
+        question: &str,
+        on_answer: F,
+    ) -> Result<ChatReply>
+    where
+        F: Fn(&str) + Send + Sync,
+    {
+        let mut messages = Vec::with_capacity(history.len() * 2 + 2);
+        for turn in history {
+            messages.push(message("user", turn.question.clone()));
+            messages.push(message("assistant", turn.answer.clone()));
+        }
+        messages.push(message("user", question.to_string()));
+
+        let asked: usize = messages
+            .iter()
+            .map(|m| tokens::count(&self.model, &m.content))
+            .sum();
+        let window = self
+            .window_tokens(QUESTION_INSTRUCTIONS)
+            .saturating_sub(asked)
+            .max(MIN_WINDOW_TOKENS);
+        let separator_tokens = tokens::count(&self.model, SEGMENT_SEPARATOR);
+        let mut used = 0;
+        let first_sent = transcriptions
+            .iter()
+            .rposition(|segment| {
+                used += tokens::count(&self.model, segment) + separator_tokens;
+                used > window
+            })
+            .map_or(0, |too_many| too_many + 1);
+        let mut transcript = transcriptions[first_sent..].join(SEGMENT_SEPARATOR);
+        if first_sent > 0 {
+            println!(
+                "Transcript is over the {} tokens left for it; asking about the last {} of {} segments",
+                window,
+                transcriptions.len() - first_sent,
+                transcriptions.len()
+            );
+            transcript.insert_str(0, "[The start of the conversation is left out]\n\n");
+        }
+        messages.insert(
+            0,
+            message("system", format!("{}{}", QUESTION_INSTRUCTIONS, transcript)),
+        );
+
+        self.stream_messages("Question request", messages, 0.2, None, on_answer)
+            .await
+    }
+
+    /// Generate a cumulative summary from multiple transcription chunks,
+    /// reporting to `on_progress` as it comes along
+    pub async fn summarize_conversation<F>(
+        &self,
+        transcriptions: &[String],
+        on_progress: F,
+    ) -> Result<SummaryResult>
+    where
+        F: Fn(SummaryProgress) + Send + Sync,
+    {
+        self.summarize_streaming(transcriptions, on_progress).await
+    }
+}
+
+fn summary_request(text: &str) -> String {
+    format!(
+        "Please summarize the following conversation and extract any action items:\n\n{}",
+        text
+    )
+}
+
+/// The summaries saved in `summaries_dir`, newest first, for the action
+/// items of earlier sessions. Files that can't be read are skipped.
+pub fn saved_summaries(summaries_dir: &Path) -> Vec<SummaryResult> {
+    let Ok(entries) = std::fs::read_dir(summaries_dir) else {
+        return Vec::new();
+    };
+    let mut summaries: Vec<SummaryResult> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let json = std::fs::read_to_string(&path).ok()?;
+            match serde_json::from_str(&json) {
+                Ok(summary) => Some(summary),
+                Err(e) => {
+                    eprintln!("Skipping saved summary {:?}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    summaries.sort_by_key(|summary| std::cmp::Reverse(summary.timestamp));
+    summaries
+}
+
+/// `system` instructions followed by one `user` message
+fn prompt(system: &str, user: String) -> Vec<ChatMessage> {
+    vec![message("system", system.to_string()), message("user", user)]
+}
+
+fn message(role: &str, content: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content,
+    }
+}
+
+/// Whether a request failed because the server didn't accept its
+/// `response_format`
+fn rejects_schema(error: &anyhow::Error) -> bool {
+    match ApiError::find(error) {
+        Some(ApiError::Failed { detail, .. } | ApiError::InvalidAudio { detail, .. }) => {
+            detail.contains("response_format") || detail.contains("json_schema")
+        }
+        _ => false,
+    }
+}
+
+/// `content` parsed as JSON, or failing that, the outermost `{...}` in it,
+/// for answers written without a schema that come wrapped in a Markdown
+/// fence or a sentence
+pub fn parse_json<T: DeserializeOwned>(content: &str) -> Result<T> {
+    let error = match serde_json::from_str(content) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+    let object = match (content.find('{'), content.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => return Err(error).context("Failed to parse GPT JSON output"),
+    };
+    serde_json::from_str(object).context("Failed to parse GPT JSON output")
+}
+
+/// The request for part `index` of `total` of a conversation too long for
+/// one request
+fn part_request(index: usize, total: usize, text: &str) -> String {
+    format!(
+        "This is part {} of {} of a longer conversation. Please summarize it and extract any \
+         action items:\n\n{}",
+        index + 1,
+        total,
+        text
+    )
+}
+
+/// Summaries of parts, each headed with its place in the conversation
+fn numbered(parts: &[String]) -> Vec<String> {
+    parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| format!("Part {}:\n{}", index + 1, part.trim()))
+        .collect()
+}
+
+fn combine_request(parts: &[String]) -> String {
+    format!(
+        "Summaries of the {} parts of the conversation, in order:\n\n{}",
+        parts.len(),
+        parts.join("\n\n")
+    )
+}
+
+/// The `"summary"` value of JSON that is still being written, as far as
+/// it has come, or `None` until the value has started
+fn partial_summary(json: &str) -> Option<String> {
+    let key = "\"summary\"";
+    let rest = &json[json.find(key)? + key.len()..];
+    let rest = rest
+        .trim_start()
+        .strip_prefix(':')?
+        .trim_start()
+        .strip_prefix('"')?;
+
+    let mut summary = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => summary.push('\n'),
+                Some('t') => summary.push('\t'),
+                Some('r') => {}
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    if hex.len() < 4 {
+                        break;
+                    }
+                    // Halves of a surrogate pair (emoji) are left out
+                    if let Some(c) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        summary.push(c);
+                    }
+                }
+                // \" \\ \/
+                Some(c) => summary.push(c),
+                // The escape is cut off
+                None => break,
+            },
+            c => summary.push(c),
+        }
     }
+    Some(summary)
 }
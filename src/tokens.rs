@@ -0,0 +1,32 @@
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+use tiktoken_rs::tokenizer::{Tokenizer, get_tokenizer};
+
+/// Tokens `text` takes up for `model`. Models tiktoken doesn't know, such
+/// as Azure deployment names, are counted with o200k_base, the encoding of
+/// the GPT-4o family.
+pub fn count(model: &str, text: &str) -> usize {
+    encoding(model).encode_ordinary(text).len()
+}
+
+/// Tokens `model` reads in one request, prompt and answer together, or
+/// `configured` when set. Unknown models get tiktoken's cautious 4096.
+pub fn context_window(model: &str, configured: usize) -> usize {
+    match configured {
+        0 => tiktoken_rs::model::get_context_size(model),
+        tokens => tokens,
+    }
+}
+
+/// The encoding `model` uses, loaded the first time it is needed
+fn encoding(model: &str) -> &'static CoreBPE {
+    static O200K: OnceLock<CoreBPE> = OnceLock::new();
+    static CL100K: OnceLock<CoreBPE> = OnceLock::new();
+    match get_tokenizer(model) {
+        Some(Tokenizer::Cl100kBase) => CL100K
+            .get_or_init(|| tiktoken_rs::cl100k_base().expect("cl100k_base ships with tiktoken")),
+        _ => {
+            O200K.get_or_init(|| tiktoken_rs::o200k_base().expect("o200k_base ships with tiktoken"))
+        }
+    }
+}
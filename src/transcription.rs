@@ -1,49 +1,701 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
 use reqwest::multipart::{Form, Part};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use tokio_util::io::ReaderStream;
 
+use crate::api_error::ApiError;
+use crate::chunk_writer::ChunkMetadata;
+use crate::encoding;
+use crate::entities::Entity;
+use crate::http;
+use crate::openai_api::ApiEndpoint;
+use crate::retry::RetryPolicy;
+use crate::sentiment::Sentiment;
+
+/// Where audio is transcribed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptionBackend {
+    /// OpenAI's hosted Whisper API
+    #[default]
+    OpenAi,
+    /// whisper.cpp on this machine, without an API key
+    Local,
+    /// AssemblyAI, which also labels who is speaking
+    AssemblyAi,
+    /// Google Cloud Speech-to-Text
+    Google,
+    /// Vosk on this machine: small models, fast on any CPU
+    Vosk,
+    /// A self-hosted faster-whisper or whisperX server with an
+    /// OpenAI-compatible API
+    WhisperServer,
+}
+
+impl TranscriptionBackend {
+    pub const ALL: [TranscriptionBackend; 6] = [
+        TranscriptionBackend::OpenAi,
+        TranscriptionBackend::Local,
+        TranscriptionBackend::AssemblyAi,
+        TranscriptionBackend::Google,
+        TranscriptionBackend::Vosk,
+        TranscriptionBackend::WhisperServer,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TranscriptionBackend::OpenAi => "OpenAI Whisper API",
+            TranscriptionBackend::Local => "Local (whisper.cpp)",
+            TranscriptionBackend::AssemblyAi => "AssemblyAI (speaker labels)",
+            TranscriptionBackend::Google => "Google Speech-to-Text",
+            TranscriptionBackend::Vosk => "Local (Vosk)",
+            TranscriptionBackend::WhisperServer => "Self-hosted Whisper server",
+        }
+    }
+}
+
+/// Models the OpenAI backend offers, as API name and label
+pub const TRANSCRIPTION_MODELS: [(&str, &str); 3] = [
+    ("whisper-1", "Whisper"),
+    ("gpt-4o-transcribe", "GPT-4o Transcribe"),
+    ("gpt-4o-mini-transcribe", "GPT-4o mini Transcribe"),
+];
+
+/// Whether `model` is one of the GPT-4o transcription models, which only
+/// answer with plain `json` (no timings, scores or detected language) and
+/// can't translate
+pub fn is_gpt4o_model(model: &str) -> bool {
+    model.starts_with("gpt-4o")
+}
+
+/// Languages that can be forced instead of auto-detected, as ISO-639-1 code
+/// and English name (Whisper supports more; these are the common ones)
+pub const LANGUAGES: [(&str, &str); 20] = [
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("nl", "Dutch"),
+    ("sv", "Swedish"),
+    ("pl", "Polish"),
+    ("uk", "Ukrainian"),
+    ("ru", "Russian"),
+    ("tr", "Turkish"),
+    ("ar", "Arabic"),
+    ("hi", "Hindi"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("zh", "Chinese"),
+    ("id", "Indonesian"),
+    ("vi", "Vietnamese"),
+    ("th", "Thai"),
+];
+
+/// English name of a language code, e.g. "de" → "German"
+pub fn language_name(code: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, name)| *name)
+}
+
+/// The ISO-639-1 code for a language as an engine reports it: Whisper gives
+/// names ("english"), AssemblyAI codes with a region ("en_us")
+pub fn normalize_language(reported: &str) -> String {
+    let reported = reported.trim().to_lowercase();
+    let code = reported.split(['_', '-']).next().unwrap_or_default();
+    LANGUAGES
+        .iter()
+        .find(|(c, name)| *c == code || name.eq_ignore_ascii_case(&reported))
+        .map_or(reported.clone(), |(c, _)| c.to_string())
+}
+
+/// Whisper only reads the last 224 tokens of a prompt; longer prompts are
+/// trimmed from the front to roughly that many characters
+const MAX_PROMPT_CHARS: usize = 800;
+
+/// Words from the end of the previous segment carried into the prompt
+const PROMPT_TAIL_WORDS: usize = 50;
+
+/// Hints that help an engine spell names and continue sentences across
+/// chunk boundaries
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptionContext {
+    /// Names, acronyms and terms likely to come up
+    pub vocabulary: Vec<String>,
+    /// Text transcribed from the audio just before this chunk
+    pub previous_text: Option<String>,
+    /// Prompt written by the user, sent instead of one built from the
+    /// vocabulary and previous text
+    pub prompt: Option<String>,
+}
+
+impl TranscriptionContext {
+    /// Whisper `prompt`: the user's own, or the vocabulary followed by the
+    /// end of the previous segment, which Whisper weighs most
+    pub fn prompt(&self) -> Option<String> {
+        if let Some(prompt) = self.prompt.as_deref().map(str::trim)
+            && !prompt.is_empty()
+        {
+            return Some(truncate_prompt(prompt));
+        }
+
+        let mut parts = Vec::new();
+        if !self.vocabulary.is_empty() {
+            parts.push(format!("{}.", self.vocabulary.join(", ")));
+        }
+        if let Some(previous) = &self.previous_text {
+            let words: Vec<&str> = previous.split_whitespace().collect();
+            let tail = words[words.len().saturating_sub(PROMPT_TAIL_WORDS)..].join(" ");
+            if !tail.is_empty() {
+                parts.push(tail);
+            }
+        }
+
+        let prompt = parts.join(" ");
+        (!prompt.is_empty()).then(|| truncate_prompt(&prompt))
+    }
+}
+
+/// The last `MAX_PROMPT_CHARS` of a prompt, the part Whisper reads
+fn truncate_prompt(prompt: &str) -> String {
+    let start = prompt
+        .char_indices()
+        .rev()
+        .nth(MAX_PROMPT_CHARS - 1)
+        .map_or(0, |(i, _)| i);
+    prompt[start..].to_string()
+}
+
+/// Settings that replace the configured ones when a segment is transcribed
+/// again; `None` keeps what is configured
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptionOverrides {
+    /// OpenAI transcription model
+    pub model: Option<String>,
+    /// ISO-639-1 code, or "auto" to auto-detect
+    pub language: Option<String>,
+    /// Prompt to send instead of the vocabulary and previous text; empty
+    /// keeps the usual one
+    pub prompt: String,
+}
+
+impl TranscriptionOverrides {
+    /// What was changed, for the revision history, e.g.
+    /// "GPT-4o Transcribe, German"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(model) = &self.model {
+            let label = TRANSCRIPTION_MODELS
+                .iter()
+                .find(|(m, _)| m == model)
+                .map_or(model.as_str(), |(_, label)| label);
+            parts.push(label.to_string());
+        }
+        match self.language.as_deref() {
+            Some("auto") => parts.push("auto-detected language".to_string()),
+            Some(code) => parts.push(language_name(code).unwrap_or(code).to_string()),
+            None => {}
+        }
+        if !self.prompt.trim().is_empty() {
+            parts.push(format!("prompt \"{}\"", self.prompt.trim()));
+        }
+
+        if parts.is_empty() {
+            "the same settings".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// A stretch of speech a diarizing engine attributed to one speaker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Utterance {
+    /// e.g. "Speaker 1"; numbered per chunk, so the same person may get a
+    /// different number in the next chunk
+    pub speaker: String,
+    pub text: String,
+    /// Offset from the start of the audio file
+    pub start_secs: f64,
+}
+
+/// Whisper's own cut-off: below this average token log-probability a
+/// segment's text is likely misheard
+const LOW_CONFIDENCE_LOGPROB: f64 = -1.0;
+
+/// Above this, Whisper thinks the segment is probably silence and any text
+/// in it may be made up
+const HIGH_NO_SPEECH_PROB: f64 = 0.6;
+
+/// A phrase and where it lies in the audio file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedSegment {
+    pub text: String,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    /// Average log-probability of the phrase's tokens (closer to 0 is surer)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_logprob: Option<f64>,
+    /// Probability that the phrase's audio holds no speech at all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_speech_prob: Option<f64>,
+}
+
+impl TimedSegment {
+    /// The engine wasn't sure of this phrase, or of there being speech
+    pub fn is_uncertain(&self) -> bool {
+        self.avg_logprob.is_some_and(|p| p < LOW_CONFIDENCE_LOGPROB)
+            || self.no_speech_prob.is_some_and(|p| p > HIGH_NO_SPEECH_PROB)
+    }
+
+    /// Average per-token probability, 0–1
+    pub fn confidence(&self) -> Option<f64> {
+        self.avg_logprob.map(f64::exp)
+    }
+}
+
+/// A single word and where it lies in the audio file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordTiming {
+    /// The word without surrounding punctuation
+    pub word: String,
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+/// Audio an API charged for, to estimate what a session costs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BilledAudio {
+    pub model: String,
+    pub secs: f64,
+}
+
+/// What an engine heard in one audio file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transcript {
+    pub text: String,
+    /// Who said what, from engines that diarize
+    pub utterances: Vec<Utterance>,
+    /// Phrase timings, from engines that report them
+    pub segments: Vec<TimedSegment>,
+    /// Word timings, from engines that report them
+    pub words: Vec<WordTiming>,
+    /// ISO-639-1 code of the language the engine detected or was told to use
+    pub language: Option<String>,
+    /// `text` is an English translation of speech in `language`
+    pub translated: bool,
+    /// API requests that had to be retried along the way
+    pub retries: u32,
+    /// Audio the request was billed for, from engines that charge by the minute
+    pub billed: Option<BilledAudio>,
+}
+
+impl From<String> for Transcript {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            ..Default::default()
+        }
+    }
+}
+
+/// Whisper's `verbose_json` response; `segments` and `words` are missing
+/// when a compatible API ignores `timestamp_granularities`
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TranscriptionResponse {
     pub text: String,
+    #[serde(default)]
+    pub segments: Vec<ResponseSegment>,
+    #[serde(default)]
+    pub words: Vec<ResponseWord>,
+    /// Detected (or forced) language name, e.g. "english"
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Length of the audio in seconds
+    #[serde(default)]
+    pub duration: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResponseSegment {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    #[serde(default)]
+    pub avg_logprob: Option<f64>,
+    #[serde(default)]
+    pub no_speech_prob: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResponseWord {
+    pub word: String,
+    pub start: f64,
+    pub end: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionResult {
     pub text: String,
     pub audio_file: PathBuf,
+    /// When the transcript came back, or for streamed utterances when
+    /// they were spoken; see `recorded_at()` for when the audio was captured
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Capture details of the chunk this text came from (live recordings only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ChunkMetadata>,
+    /// `text` split by speaker, when the engine diarizes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub utterances: Vec<Utterance>,
+    /// Phrase timings within `audio_file`, when the engine reports them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<TimedSegment>,
+    /// Word timings within `audio_file`, when the engine reports them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<WordTiming>,
+    /// ISO-639-1 code of the spoken language, when the engine reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// `text` was translated to English from `language`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub translated: bool,
+    /// API requests that failed and were retried before this succeeded
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32,
+    /// Audio the transcription was billed for, when the engine charges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub billed: Option<BilledAudio>,
+    /// Text this segment had before it was transcribed again, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revisions: Vec<Revision>,
+    /// Sentiment and tone, when sentiment analysis is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sentiment: Option<Sentiment>,
+    /// People, organizations, products and dates mentioned, when entity
+    /// extraction has run on the segment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entities: Option<Vec<Entity>>,
 }
 
-pub struct Transcriber {
-    api_key: String,
-    client: reqwest::Client,
+/// Text a segment had before a re-run replaced it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub text: String,
+    /// Language that text was in, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub replaced_at: chrono::DateTime<chrono::Utc>,
+    /// Settings of the re-run that replaced it, from
+    /// `TranscriptionOverrides::describe`
+    pub replaced_by: String,
 }
 
-impl Transcriber {
-    pub fn new(api_key: String) -> Self {
-        Self {
-            api_key,
-            client: reqwest::Client::new(),
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+impl TranscriptionResult {
+    /// Who the text came from: the capture source when several record at
+    /// once and/or the channel's speaker when channels are split
+    pub fn speaker(&self) -> Option<String> {
+        let metadata = self.metadata.as_ref()?;
+        match (&metadata.source, &metadata.speaker) {
+            (Some(source), Some(speaker)) => Some(format!("{} · {}", source, speaker)),
+            (source, speaker) => source.clone().or_else(|| speaker.clone()),
         }
     }
 
-    /// Transcribe an audio file using OpenAI Whisper API
-    pub async fn transcribe(&self, audio_file: PathBuf) -> Result<TranscriptionResult> {
-        println!("Transcribing audio file: {:?}", audio_file);
+    /// When the audio this came from started, for ordering, showing and
+    /// exporting segments; segments without capture details (imports) fall
+    /// back to when they were transcribed
+    pub fn recorded_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.metadata
+            .as_ref()
+            .map_or(self.timestamp, |metadata| metadata.recorded_at)
+    }
 
-        // Read the audio file
-        let mut file = File::open(&audio_file)
-            .await
-            .context("Failed to open audio file")?;
+    /// When the audio this came from ended
+    pub fn recorded_until(&self) -> chrono::DateTime<chrono::Utc> {
+        self.metadata
+            .as_ref()
+            .map_or(self.timestamp, |metadata| metadata.recorded_until())
+    }
 
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer)
-            .await
-            .context("Failed to read audio file")?;
+    /// Length of the audio the text came from: the chunk's, or for imports
+    /// the end of the last timed phrase or word (0 without timings)
+    fn audio_secs(&self) -> f64 {
+        match &self.metadata {
+            Some(metadata) => metadata.duration_secs,
+            None => self
+                .segments
+                .iter()
+                .map(|s| s.end_secs)
+                .chain(self.words.iter().map(|w| w.end_secs))
+                .fold(0.0, f64::max),
+        }
+    }
+
+    /// Name of the language the text was translated from, when it was
+    pub fn original_language(&self) -> Option<String> {
+        if !self.translated {
+            return None;
+        }
+        let code = self.language.as_deref()?;
+        Some(language_name(code).map_or_else(|| code.to_string(), str::to_string))
+    }
+
+    /// Average token probability (0–1) across the phrases, weighted by
+    /// their length; `None` when the engine reports no scores
+    pub fn confidence(&self) -> Option<f64> {
+        let (weighted, total) = self
+            .segments
+            .iter()
+            .filter_map(|s| Some((s.confidence()?, (s.end_secs - s.start_secs).max(0.01))))
+            .fold((0.0, 0.0), |(weighted, total), (confidence, secs)| {
+                (weighted + confidence * secs, total + secs)
+            });
+        (total > 0.0).then(|| weighted / total)
+    }
+
+    /// Phrases the engine wasn't sure of
+    pub fn uncertain_segments(&self) -> impl Iterator<Item = &TimedSegment> {
+        self.segments.iter().filter(|s| s.is_uncertain())
+    }
+
+    /// Some of the text may be misheard or made up and is worth checking
+    pub fn is_low_confidence(&self) -> bool {
+        self.uncertain_segments().next().is_some()
+    }
+
+    /// Music, noise or silence rather than speech: Whisper judged every
+    /// phrase to be silence (its own rule: likely no speech and a low
+    /// log-probability), or the text is nothing but sound tags such as
+    /// "[Music]" or "♪"
+    pub fn is_non_speech(&self) -> bool {
+        let all_silence = !self.segments.is_empty()
+            && self.segments.iter().all(|s| {
+                s.no_speech_prob.is_some_and(|p| p > HIGH_NO_SPEECH_PROB)
+                    && s.avg_logprob.is_none_or(|p| p < LOW_CONFIDENCE_LOGPROB)
+            });
+        all_silence || !has_spoken_words(&self.text)
+    }
+
+    /// Sort key for showing segments in the order their audio was captured:
+    /// capture time, then the chunk's sequence number within its session
+    /// to order chunks that started in the same instant
+    pub fn capture_order(&self) -> (chrono::DateTime<chrono::Utc>, u64) {
+        (
+            self.recorded_at(),
+            self.metadata
+                .as_ref()
+                .map_or(0, |metadata| metadata.sequence),
+        )
+    }
+
+    /// Take the text and timings of `rerun`, a new transcription of the same
+    /// audio, keeping the current text in the revision history. Capture
+    /// details and the original timestamp stay, so the saved file is
+    /// replaced rather than duplicated.
+    pub fn revise(&mut self, rerun: TranscriptionResult, replaced_by: String) {
+        self.revisions.push(Revision {
+            text: std::mem::replace(&mut self.text, rerun.text),
+            language: self.language.take(),
+            replaced_at: chrono::Utc::now(),
+            replaced_by,
+        });
+        self.utterances = rerun.utterances;
+        self.segments = rerun.segments;
+        self.words = rerun.words;
+        self.language = rerun.language;
+        self.translated = rerun.translated;
+        self.retries = rerun.retries;
+        self.billed = rerun.billed;
+        // Rated and extracted for the old text
+        self.sentiment = None;
+        self.entities = None;
+    }
+
+    /// Text as shown under the segment's label: one "Speaker 1: ..." line
+    /// per utterance when the engine diarized, otherwise the plain text
+    pub fn display_text(&self) -> String {
+        if self.utterances.is_empty() {
+            return self.text.clone();
+        }
+        self.utterances
+            .iter()
+            .map(|u| format!("{}: {}", u.speaker, u.text))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Text prefixed with the speaker label, e.g. "Me: hello", or
+    /// "Me · Speaker 2: hello" per utterance when diarized
+    pub fn labeled_text(&self) -> String {
+        let speaker = self.speaker();
+        if self.utterances.is_empty() {
+            return match speaker {
+                Some(speaker) => format!("{}: {}", speaker, self.text),
+                None => self.text.clone(),
+            };
+        }
+        self.utterances
+            .iter()
+            .map(|u| match &speaker {
+                Some(speaker) => format!("{} · {}: {}", speaker, u.speaker, u.text),
+                None => format!("{}: {}", u.speaker, u.text),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Forget the timings of the first `count` words after `strip_overlap`
+    /// removed them from `text`, along with segments that ended before the
+    /// first remaining word
+    pub fn drop_leading_words(&mut self, count: usize) {
+        if count == 0 || self.words.is_empty() {
+            return;
+        }
+        self.words.drain(..count.min(self.words.len()));
+        let start = self.words.first().map_or(f64::INFINITY, |w| w.start_secs);
+        self.segments.retain(|s| s.end_secs > start);
+    }
+
+    /// The words of `text` paired with their timings in the audio file.
+    ///
+    /// Timed words carry no punctuation and `text` may have lost its first
+    /// words to `strip_overlap`, so each word of `text` is matched against
+    /// the next few timed words; words without a match get `None`.
+    pub fn timed_words(&self) -> Vec<(&str, Option<&WordTiming>)> {
+        let mut next = 0;
+        self.text
+            .split_whitespace()
+            .map(|token| {
+                let token_norm = normalize_word(token);
+                let found = self.words[next..]
+                    .iter()
+                    .take(MAX_OVERLAP_WORDS + 1)
+                    .position(|w| normalize_word(&w.word) == token_norm);
+                match found {
+                    Some(offset) => {
+                        let word = &self.words[next + offset];
+                        next += offset + 1;
+                        (token, Some(word))
+                    }
+                    None => (token, None),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A speech-to-text engine.
+///
+/// Engines only turn an audio file into text; the app builds results, saves
+/// them and deletes audio the same way whichever engine is configured. A new
+/// engine implements `transcribe_audio` and gets a `TranscriptionBackend`
+/// variant that `Config::transcription_provider` maps to it.
+#[async_trait]
+pub trait TranscriptionProvider: Send + Sync {
+    /// Engine name for logs, e.g. "OpenAI Whisper"
+    fn name(&self) -> &'static str;
+
+    /// What was said in `audio_file`; engines use whatever hints in
+    /// `context` they can
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript>;
 
+    /// Transcribe an audio file into a result ready to save
+    async fn transcribe(
+        &self,
+        audio_file: PathBuf,
+        context: &TranscriptionContext,
+    ) -> Result<TranscriptionResult> {
+        println!(
+            "Transcribing audio file with {}: {:?}",
+            self.name(),
+            audio_file
+        );
+
+        let transcript = self.transcribe_audio(&audio_file, context).await?;
+
+        println!("Transcription: {}", transcript.text);
+
+        Ok(TranscriptionResult {
+            text: transcript.text,
+            audio_file,
+            timestamp: chrono::Utc::now(),
+            metadata: None,
+            utterances: transcript.utterances,
+            segments: transcript.segments,
+            words: transcript.words,
+            language: transcript.language,
+            translated: transcript.translated,
+            retries: transcript.retries,
+            billed: transcript.billed,
+            revisions: Vec::new(),
+            sentiment: None,
+            entities: None,
+        })
+    }
+}
+
+/// OpenAI's Whisper API, or a compatible one (Azure OpenAI, LiteLLM)
+pub struct OpenAiWhisper {
+    endpoint: ApiEndpoint,
+    /// "whisper-1" or a GPT-4o transcription model
+    model: String,
+    /// ISO-639-1 code to transcribe as, or `None` to auto-detect
+    language: Option<String>,
+    /// Translate to English through `audio/translations`
+    translate: bool,
+    retry: RetryPolicy,
+    client: reqwest::Client,
+}
+
+impl OpenAiWhisper {
+    pub fn new(
+        endpoint: ApiEndpoint,
+        model: String,
+        language: Option<String>,
+        translate: bool,
+        retry: RetryPolicy,
+    ) -> Self {
+        Self {
+            endpoint,
+            model,
+            language,
+            translate,
+            retry,
+            client: http::client(),
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for OpenAiWhisper {
+    fn name(&self) -> &'static str {
+        "OpenAI Whisper"
+    }
+
+    /// Transcribe an audio file using OpenAI Whisper API
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
         // Get filename
         let filename = audio_file
             .file_name()
@@ -51,34 +703,61 @@ impl Transcriber {
             .unwrap_or("audio.wav")
             .to_string();
 
-        // Create multipart form
-        let file_part = Part::bytes(buffer)
-            .file_name(filename)
-            .mime_str("audio/wav")?;
+        // GPT-4o models reject verbose_json; their plain json response
+        // parses into the same struct with only `text` set
+        let verbose = !is_gpt4o_model(&self.model);
+        let response_format = if verbose { "verbose_json" } else { "json" };
+        let mut fields = vec![
+            ("model", self.model.clone()),
+            ("response_format", response_format.to_string()),
+        ];
+        if let Some(prompt) = context.prompt() {
+            fields.push(("prompt", prompt));
+        }
+        // Translations only report segment timings and always detect the
+        // source language themselves
+        let path = if self.translate {
+            "audio/translations"
+        } else {
+            if verbose {
+                fields.push(("timestamp_granularities[]", "segment".to_string()));
+                fields.push(("timestamp_granularities[]", "word".to_string()));
+            }
+            if let Some(language) = &self.language {
+                fields.push(("language", language.clone()));
+            }
+            "audio/transcriptions"
+        };
 
-        let form = Form::new()
-            .part("file", file_part)
-            .text("model", "whisper-1")
-            .text("response_format", "json");
+        // A multipart form can only be sent once, so each attempt builds its
+        // own, streaming the audio from disk again
+        let mut content_type = HeaderMap::new();
+        content_type.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(mime_type_for(audio_file)),
+        );
+        let build_request = || {
+            let (body, len) = file_body(audio_file)?;
+            let file_part = Part::stream_with_length(body, len)
+                .file_name(filename.clone())
+                .headers(content_type.clone());
+            let form = fields.iter().fold(
+                Form::new().part("file", file_part),
+                |form, (name, value)| form.text(*name, value.clone()),
+            );
+            Ok(self.endpoint.post(&self.client, path).multipart(form))
+        };
 
         // Send request to OpenAI
-        let response = self
-            .client
-            .post("https://api.openai.com/v1/audio/transcriptions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
-            .await
-            .context("Failed to send transcription request")?;
+        let (response, retries) = self
+            .retry
+            .try_send("Whisper API request", build_request)
+            .await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            anyhow::bail!(
-                "Whisper API request failed with status {}: {}",
-                status,
-                error_text
-            );
+            return Err(ApiError::from_response("Whisper API request", response)
+                .await
+                .into());
         }
 
         let transcription: TranscriptionResponse = response
@@ -86,29 +765,396 @@ impl Transcriber {
             .await
             .context("Failed to parse transcription response")?;
 
-        println!("Transcription: {}", transcription.text);
+        // Only verbose_json reports the duration; otherwise measure the file
+        let billed_secs = match transcription.duration {
+            Some(secs) => Some(secs),
+            None => {
+                let path = audio_file.to_path_buf();
+                tokio::task::spawn_blocking(move || encoding::audio_duration(&path))
+                    .await?
+                    .ok()
+            }
+        };
 
-        Ok(TranscriptionResult {
-            text: transcription.text,
-            audio_file,
-            timestamp: chrono::Utc::now(),
+        Ok(Transcript {
+            text: transcription.text.trim().to_string(),
+            utterances: Vec::new(),
+            segments: transcription
+                .segments
+                .into_iter()
+                .map(|s| TimedSegment {
+                    text: s.text.trim().to_string(),
+                    start_secs: s.start,
+                    end_secs: s.end,
+                    avg_logprob: s.avg_logprob,
+                    no_speech_prob: s.no_speech_prob,
+                })
+                .collect(),
+            words: transcription
+                .words
+                .into_iter()
+                .map(|w| WordTiming {
+                    word: w.word.trim().to_string(),
+                    start_secs: w.start,
+                    end_secs: w.end,
+                })
+                .collect(),
+            language: transcription
+                .language
+                .as_deref()
+                .map(normalize_language)
+                .or_else(|| self.language.clone()),
+            translated: self.translate,
+            retries,
+            billed: billed_secs.map(|secs| BilledAudio {
+                model: self.model.clone(),
+                secs,
+            }),
         })
     }
+}
 
-    /// Save transcription result to a file
-    pub async fn save_transcription(
-        &self,
-        result: &TranscriptionResult,
-        output_dir: &PathBuf,
-    ) -> Result<PathBuf> {
-        let timestamp = result.timestamp.format("%Y%m%d_%H%M%S");
-        let filename = format!("transcription_{}.json", timestamp);
-        let filepath = output_dir.join(filename);
+/// Save transcription result to a file
+pub async fn save_transcription(
+    result: &TranscriptionResult,
+    output_dir: &Path,
+) -> Result<PathBuf> {
+    // The audio file's name keeps chunks transcribed in the same second
+    // (e.g. both sides of a split stereo stream) from overwriting each other
+    let timestamp = result.timestamp.format("%Y%m%d_%H%M%S");
+    let stem = result
+        .audio_file
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let filename = format!("transcription_{}_{}.json", timestamp, stem);
+    let filepath = output_dir.join(filename);
+
+    let json = serde_json::to_string_pretty(result)?;
+    tokio::fs::write(&filepath, json).await?;
 
-        let json = serde_json::to_string_pretty(result)?;
-        tokio::fs::write(&filepath, json).await?;
+    println!("Transcription saved to: {:?}", filepath);
+    Ok(filepath)
+}
+
+/// Audio files that already have a transcription saved in `dir`
+pub fn transcribed_audio_files(dir: &Path) -> HashSet<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return HashSet::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|json| serde_json::from_str::<TranscriptionResult>(&json).ok())
+        .map(|result| result.audio_file)
+        .collect()
+}
+
+/// Every segment saved in `dir`, from all sessions, oldest first
+pub fn saved_transcriptions(dir: &Path) -> Vec<TranscriptionResult> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut results: Vec<TranscriptionResult> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+    results.sort_by_key(TranscriptionResult::capture_order);
+    results
+}
 
-        println!("Transcription saved to: {:?}", filepath);
-        Ok(filepath)
+/// Temporary file names handed out so far
+static TEMP_FILES: AtomicU64 = AtomicU64::new(0);
+
+/// Start of the names of one job's temporary files for `audio_file`, e.g.
+/// "audio-assistant-whisper-4242-7-chunk_0003". Each call gets its own, so
+/// a segment and its session being transcribed again at once don't share.
+pub fn temp_file_stem(kind: &str, audio_file: &Path) -> String {
+    let stem = audio_file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    format!(
+        "audio-assistant-{}-{}-{}-{}",
+        kind,
+        std::process::id(),
+        TEMP_FILES.fetch_add(1, Ordering::Relaxed),
+        stem
+    )
+}
+
+/// A request body that streams `path` from disk rather than holding it in
+/// memory, with the file's length so the upload has a Content-Length
+pub fn file_body(path: &Path) -> Result<(reqwest::Body, u64)> {
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let len = file.metadata()?.len();
+    let stream = ReaderStream::new(File::from_std(file));
+    Ok((reqwest::Body::wrap_stream(stream), len))
+}
+
+/// MIME type for an audio file based on its extension
+fn mime_type_for(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("flac") => "audio/flac",
+        Some("ogg") | Some("opus") => "audio/ogg",
+        Some("mp3") => "audio/mpeg",
+        Some("m4a") | Some("mp4") => "audio/mp4",
+        Some("webm") => "audio/webm",
+        _ => "audio/wav",
+    }
+}
+
+/// Whether `text` has any words outside sound tags ("[Music]",
+/// "(applause)", "*laughs*") and lyrics between musical notes ("♪ ... ♪")
+fn has_spoken_words(text: &str) -> bool {
+    let mut closing = None;
+    for c in text.chars() {
+        match closing {
+            Some(close) if c == close => closing = None,
+            Some(_) => {}
+            None => match c {
+                '[' => closing = Some(']'),
+                '(' => closing = Some(')'),
+                '*' => closing = Some('*'),
+                '♪' => closing = Some('♪'),
+                c if c.is_alphanumeric() => return true,
+                _ => {}
+            },
+        }
+    }
+    false
+}
+
+/// Longest run of repeated words to look for between consecutive segments
+const MAX_OVERLAP_WORDS: usize = 30;
+
+/// Shortest run a fuzzy match is accepted for; shorter runs must repeat
+/// exactly
+const MIN_FUZZY_OVERLAP_WORDS: usize = 4;
+
+/// Share of words in a fuzzy match that may differ, be missing or be extra
+const MAX_FUZZY_MISMATCH: f64 = 0.2;
+
+/// How `strip_overlap` decides that words repeat
+#[derive(Debug, Clone, Copy)]
+pub struct OverlapMatch {
+    /// Shortest run of words that counts as a repeat
+    pub min_words: usize,
+    /// Also accept runs of `MIN_FUZZY_OVERLAP_WORDS` or more that differ in
+    /// a few words or spellings, as two transcriptions of the same speech do
+    pub fuzzy: bool,
+}
+
+/// Time from the start of the earliest segment's audio to the end of the
+/// latest one's, or `None` without segments
+pub fn recorded_span<'a>(
+    results: impl IntoIterator<Item = &'a TranscriptionResult>,
+) -> Option<chrono::Duration> {
+    let (start, end) = results
+        .into_iter()
+        .map(|r| (r.recorded_at(), r.recorded_until()))
+        .reduce(|(start, end), (s, e)| (start.min(s), end.max(e)))?;
+    Some(end - start)
+}
+
+/// The languages spoken across `results` with their share of the words,
+/// e.g. "80% English, 20% Hindi", or `None` when no language was detected
+pub fn session_languages<'a>(
+    results: impl IntoIterator<Item = &'a TranscriptionResult>,
+) -> Option<String> {
+    let mut words: HashMap<&str, usize> = HashMap::new();
+    for result in results {
+        if let Some(language) = &result.language {
+            *words.entry(language).or_default() += result.text.split_whitespace().count();
+        }
+    }
+    let total: usize = words.values().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut shares: Vec<(&str, usize)> = words.into_iter().filter(|(_, n)| *n > 0).collect();
+    shares.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let names = shares
+        .iter()
+        .map(|(code, count)| {
+            let name = language_name(code).unwrap_or(code);
+            match shares.len() {
+                1 => name.to_string(),
+                _ => format!("{:.0}% {}", *count as f64 * 100.0 / total as f64, name),
+            }
+        })
+        .collect::<Vec<_>>();
+    Some(names.join(", "))
+}
+
+/// How long one speaker talked in a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TalkTime {
+    pub speaker: String,
+    /// Seconds of audio attributed to them; 0 when the segments carry no
+    /// timings (imports without them)
+    pub secs: f64,
+    pub words: usize,
+}
+
+impl TalkTime {
+    /// "4m 10s (38%), 620 words", with the share of `total_secs`
+    pub fn describe(&self, total_secs: f64) -> String {
+        let secs = self.secs.round() as u64;
+        if total_secs <= 0.0 {
+            return format!("{} words", self.words);
+        }
+        format!(
+            "{}m {}s ({:.0}%), {} words",
+            secs / 60,
+            secs % 60,
+            self.secs * 100.0 / total_secs,
+            self.words
+        )
+    }
+}
+
+/// Talk time per speaker across `results`, most first. A diarized
+/// utterance lasts until the next one starts, the last until its chunk
+/// ends; segments without a speaker label aren't counted.
+pub fn talk_time<'a>(results: impl IntoIterator<Item = &'a TranscriptionResult>) -> Vec<TalkTime> {
+    let mut speakers: Vec<TalkTime> = Vec::new();
+    let mut add = |speaker: String, secs: f64, text: &str| {
+        let words = text.split_whitespace().count();
+        match speakers.iter_mut().find(|t| t.speaker == speaker) {
+            Some(talk) => {
+                talk.secs += secs;
+                talk.words += words;
+            }
+            None => speakers.push(TalkTime {
+                speaker,
+                secs,
+                words,
+            }),
+        }
+    };
+    for result in results {
+        let source = result.speaker();
+        let length = result.audio_secs();
+        if result.utterances.is_empty() {
+            if let Some(speaker) = source {
+                add(speaker, length, &result.text);
+            }
+            continue;
+        }
+        for (i, utterance) in result.utterances.iter().enumerate() {
+            let end = result
+                .utterances
+                .get(i + 1)
+                .map_or(length, |next| next.start_secs);
+            let speaker = match &source {
+                Some(source) => format!("{} · {}", source, utterance.speaker),
+                None => utterance.speaker.clone(),
+            };
+            add(
+                speaker,
+                (end - utterance.start_secs).max(0.0),
+                &utterance.text,
+            );
+        }
+    }
+    speakers.sort_by(|a, b| b.secs.total_cmp(&a.secs).then(b.words.cmp(&a.words)));
+    speakers
+}
+
+/// Remove words at the start of `current` that repeat the end of `previous`.
+///
+/// Overlapping chunks transcribe the shared audio twice, and a retried chunk
+/// or Whisper echoing its prompt can repeat a sentence. The longest head of
+/// the current segment that lines up with the tail of the previous one
+/// (ignoring case and punctuation, and with `fuzzy` a few differences) is
+/// dropped. Both runs must end on the same word, so nothing past the
+/// repeat is lost.
+pub fn strip_overlap(previous: &str, current: &str, matching: OverlapMatch) -> String {
+    let prev_words: Vec<String> = previous.split_whitespace().map(normalize_word).collect();
+    let cur_words: Vec<&str> = current.split_whitespace().collect();
+    let cur_norm: Vec<String> = cur_words.iter().map(|w| normalize_word(w)).collect();
+
+    let max = MAX_OVERLAP_WORDS.min(cur_words.len());
+    let overlap = (matching.min_words.max(1)..=max)
+        .rev()
+        .find(|&n| {
+            let head = &cur_norm[..n];
+            if n <= prev_words.len() && prev_words[prev_words.len() - n..] == *head {
+                return true;
+            }
+            if !matching.fuzzy || n < MIN_FUZZY_OVERLAP_WORDS {
+                return false;
+            }
+            // The repeat may have gained or lost a word or two
+            let allowed = (n as f64 * MAX_FUZZY_MISMATCH).floor() as usize;
+            (n.saturating_sub(allowed)..=n + allowed)
+                .filter(|&k| k > 0 && k <= prev_words.len())
+                .any(|k| {
+                    let tail = &prev_words[prev_words.len() - k..];
+                    similar_words(&tail[k - 1], &head[n - 1])
+                        && word_distance(tail, head) <= allowed
+                })
+        })
+        .unwrap_or(0);
+
+    if overlap == 0 {
+        current.to_string()
+    } else {
+        cur_words[overlap..].join(" ")
+    }
+}
+
+/// Edits (changed, missing or extra words) that turn `a` into `b`, with
+/// near-identical spellings counting as the same word
+fn word_distance(a: &[String], b: &[String]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, word_a) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, word_b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(!similar_words(word_a, word_b));
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
     }
+    row[b.len()]
+}
+
+/// The same normalized word, or a spelling variant of a longer one
+/// ("colour" and "color") differing in one letter
+fn similar_words(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    if a.len().min(b.len()) < 4 || a.len().abs_diff(b.len()) > 1 {
+        return false;
+    }
+    // One substitution, insertion or deletion at most
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    a.len().max(b.len()) - prefix - suffix <= 1
+}
+
+/// A word lowercased without punctuation, for comparing transcripts
+fn normalize_word(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
 }
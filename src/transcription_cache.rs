@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::chunk_writer;
+use crate::transcription::{Transcript, TranscriptionContext, TranscriptionProvider};
+
+/// A cached transcript and the engine settings that produced it
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    settings: String,
+    transcript: Transcript,
+}
+
+/// Transcripts stored by the SHA-256 of the audio they came from, one JSON
+/// file per audio file
+#[derive(Debug, Clone)]
+pub struct TranscriptionCache {
+    dir: PathBuf,
+}
+
+impl TranscriptionCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn entry_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", hash))
+    }
+
+    /// The transcript cached for `hash`, if it was made with `settings`
+    pub fn get(&self, hash: &str, settings: &str) -> Option<Transcript> {
+        let json = fs::read_to_string(self.entry_path(hash)).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&json).ok()?;
+        (entry.settings == settings).then_some(entry.transcript)
+    }
+
+    /// Remember `transcript` for `hash`, replacing one made with other settings
+    pub fn put(&self, hash: &str, settings: &str, transcript: &Transcript) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {:?}", self.dir))?;
+        let entry = CacheEntry {
+            settings: settings.to_string(),
+            transcript: Transcript {
+                // Retries and billing belong to the request that made it,
+                // not to reuses
+                retries: 0,
+                billed: None,
+                ..transcript.clone()
+            },
+        };
+
+        let path = self.entry_path(hash);
+        let partial = chunk_writer::partial_path(&path);
+        fs::write(&partial, serde_json::to_string(&entry)?)
+            .context("Failed to write transcription cache entry")?;
+        fs::rename(&partial, &path).context("Failed to write transcription cache entry")?;
+        Ok(())
+    }
+}
+
+/// SHA-256 of a file's contents, as lowercase hex
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Wraps an engine so audio it has already transcribed with the same
+/// settings is answered from the cache instead of being sent again, e.g.
+/// when a file is re-imported, a failed chunk is retried or leftover chunks
+/// are recovered after a crash.
+///
+/// The context prompt is not part of the key: identical audio is reused
+/// even if the vocabulary or the previous segment changed since.
+pub struct CachedProvider {
+    inner: Arc<dyn TranscriptionProvider>,
+    cache: TranscriptionCache,
+    /// Engine, model, language and translation settings the cache entries
+    /// must match
+    settings: String,
+}
+
+impl CachedProvider {
+    pub fn new(
+        inner: Arc<dyn TranscriptionProvider>,
+        cache: TranscriptionCache,
+        settings: String,
+    ) -> Self {
+        Self {
+            inner,
+            cache,
+            settings,
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for CachedProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        let path = audio_file.to_path_buf();
+        let hash = match tokio::task::spawn_blocking(move || hash_file(&path)).await? {
+            Ok(hash) => hash,
+            Err(e) => {
+                eprintln!("Not caching {:?}: {}", audio_file, e);
+                return self.inner.transcribe_audio(audio_file, context).await;
+            }
+        };
+
+        if let Some(transcript) = self.cache.get(&hash, &self.settings) {
+            println!("Using cached transcription for {:?}", audio_file);
+            return Ok(transcript);
+        }
+
+        let transcript = self.inner.transcribe_audio(audio_file, context).await?;
+        if let Err(e) = self.cache.put(&hash, &self.settings, &transcript) {
+            eprintln!("Failed to cache transcription of {:?}: {}", audio_file, e);
+        }
+        Ok(transcript)
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::encoding;
+use crate::transcription::{self, Transcript, TranscriptionContext, TranscriptionProvider};
+use crate::vad;
+
+/// Largest file OpenAI's transcription API accepts
+pub const WHISPER_UPLOAD_LIMIT: u64 = 25 * 1024 * 1024;
+
+/// Pieces are sent as 16 kHz mono 16-bit WAV, 32 kB a second
+const PIECE_SAMPLE_RATE: u32 = 16_000;
+const PIECE_BYTES_PER_SEC: u64 = PIECE_SAMPLE_RATE as u64 * 2;
+
+/// Longest piece, well inside the limit and short enough that one failed
+/// request doesn't cost much
+const MAX_PIECE_SECS: u64 = 10 * 60;
+
+/// The cut goes at the quietest moment in this last stretch of a piece
+const CUT_SEARCH_SECS: u64 = 30;
+
+/// Level is measured over frames of this length when looking for a pause
+const CUT_FRAME_SECS: f32 = 0.1;
+
+/// Wraps an engine with an upload limit so files over it are split at
+/// pauses into pieces under it, transcribed one after another and stitched
+/// back into one transcript. Live chunks rarely get that big, but imported
+/// recordings often do.
+pub struct SplittingProvider {
+    inner: Arc<dyn TranscriptionProvider>,
+    max_bytes: u64,
+}
+
+impl SplittingProvider {
+    pub fn new(inner: Arc<dyn TranscriptionProvider>, max_bytes: u64) -> Self {
+        Self { inner, max_bytes }
+    }
+
+    /// Seconds of 16 kHz WAV that fit in one upload
+    fn piece_secs(&self) -> u64 {
+        // A tenth spare for the header and rounding
+        (self.max_bytes / PIECE_BYTES_PER_SEC * 9 / 10).clamp(1, MAX_PIECE_SECS)
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for SplittingProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        let size = std::fs::metadata(audio_file)
+            .with_context(|| format!("Failed to open {:?}", audio_file))?
+            .len();
+        if size <= self.max_bytes {
+            return self.inner.transcribe_audio(audio_file, context).await;
+        }
+
+        let path = audio_file.to_path_buf();
+        let samples = tokio::task::spawn_blocking(move || {
+            encoding::read_audio_resampled(&path, PIECE_SAMPLE_RATE)
+        })
+        .await??;
+        let cuts = cut_points(&samples, self.piece_secs());
+        println!(
+            "{:?} is {:.1} MB, over the {:.0} MB upload limit; sending it in {} pieces",
+            audio_file,
+            size as f64 / (1024.0 * 1024.0),
+            self.max_bytes as f64 / (1024.0 * 1024.0),
+            cuts.len() - 1
+        );
+
+        let mut stitched = Transcript::default();
+        let mut texts: Vec<String> = Vec::new();
+        let stem = transcription::temp_file_stem("piece", audio_file);
+        for (index, range) in cuts.windows(2).enumerate() {
+            let piece = std::env::temp_dir().join(format!("{}-{}.wav", stem, index + 1));
+            let (piece_samples, target) = (samples[range[0]..range[1]].to_vec(), piece.clone());
+            tokio::task::spawn_blocking(move || -> Result<()> {
+                let file = std::fs::File::create(&target)
+                    .with_context(|| format!("Failed to write audio to {:?}", target))?;
+                encoding::write_speech_wav(
+                    &piece_samples,
+                    PIECE_SAMPLE_RATE,
+                    std::io::BufWriter::new(file),
+                )
+            })
+            .await??;
+
+            // Later pieces are primed with the text just before them
+            let piece_context = TranscriptionContext {
+                previous_text: match texts.last() {
+                    Some(text) if context.previous_text.is_some() => Some(text.clone()),
+                    _ => context.previous_text.clone(),
+                },
+                ..context.clone()
+            };
+            let transcript = self.inner.transcribe_audio(&piece, &piece_context).await;
+            let _ = tokio::fs::remove_file(&piece).await;
+            let transcript = transcript
+                .with_context(|| format!("Piece {} of {:?} failed", index + 1, audio_file))?;
+
+            let offset = range[0] as f64 / PIECE_SAMPLE_RATE as f64;
+            append(&mut stitched, transcript, offset, &mut texts);
+        }
+        stitched.text = texts.join(" ");
+        Ok(stitched)
+    }
+}
+
+/// Sample positions to cut `samples` at, from 0 to its length, with no
+/// piece longer than `piece_secs` and each cut at the quietest frame near
+/// the end of its piece
+fn cut_points(samples: &[f32], piece_secs: u64) -> Vec<usize> {
+    let rate = PIECE_SAMPLE_RATE as usize;
+    let piece_len = piece_secs as usize * rate;
+    let search_len = (CUT_SEARCH_SECS as usize * rate).min(piece_len / 2);
+    let frame_len = ((CUT_FRAME_SECS * PIECE_SAMPLE_RATE as f32) as usize).max(1);
+
+    let mut cuts = vec![0];
+    let mut start = 0;
+    while samples.len() - start > piece_len {
+        let search_start = start + piece_len - search_len;
+        let quietest = samples[search_start..start + piece_len]
+            .chunks(frame_len)
+            .enumerate()
+            .min_by(|(_, a), (_, b)| vad::rms(a).total_cmp(&vad::rms(b)))
+            .map_or(0, |(frame, _)| frame);
+        start = search_start + quietest * frame_len + frame_len / 2;
+        cuts.push(start);
+    }
+    cuts.push(samples.len());
+    cuts
+}
+
+/// Add a piece's transcript to the stitched one, moving its timings by
+/// where the piece starts
+fn append(stitched: &mut Transcript, piece: Transcript, offset: f64, texts: &mut Vec<String>) {
+    if !piece.text.is_empty() {
+        texts.push(piece.text);
+    }
+    stitched
+        .utterances
+        .extend(piece.utterances.into_iter().map(|mut utterance| {
+            utterance.start_secs += offset;
+            utterance
+        }));
+    stitched
+        .segments
+        .extend(piece.segments.into_iter().map(|mut segment| {
+            segment.start_secs += offset;
+            segment.end_secs += offset;
+            segment
+        }));
+    stitched
+        .words
+        .extend(piece.words.into_iter().map(|mut word| {
+            word.start_secs += offset;
+            word.end_secs += offset;
+            word
+        }));
+    stitched.language = stitched.language.take().or(piece.language);
+    stitched.translated |= piece.translated;
+    stitched.retries += piece.retries;
+    if let Some(billed) = piece.billed {
+        match &mut stitched.billed {
+            Some(total) => total.secs += billed.secs,
+            None => stitched.billed = Some(billed),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+/// User-facing VAD chunking parameters
+#[derive(Debug, Clone)]
+pub struct VadSettings {
+    pub min_chunk_secs: u64,
+    pub max_chunk_secs: u64,
+    /// Length of pause that ends a chunk
+    pub silence_ms: u64,
+    /// RMS level below which audio counts as silence
+    pub energy_threshold: f32,
+}
+
+/// Energy-based voice activity detector used to end chunks at pauses
+/// instead of cutting sentences at a fixed duration.
+#[derive(Debug, Clone)]
+pub struct VoiceActivityDetector {
+    /// Samples per analysis frame
+    frame_len: usize,
+    /// RMS level below which a frame counts as silence
+    energy_threshold: f32,
+    /// Consecutive silent frames that make a usable boundary
+    silence_frames: usize,
+    /// Never end a chunk before this many samples
+    min_chunk_samples: usize,
+    /// Always end a chunk once it reaches this many samples
+    max_chunk_samples: usize,
+}
+
+impl VoiceActivityDetector {
+    pub fn new(sample_rate: u32, settings: &VadSettings) -> Self {
+        // 30ms frames, the usual granularity for speech detection
+        let frame_len = (sample_rate as usize * 30 / 1000).max(1);
+        let max_chunk_secs = settings.max_chunk_secs.max(settings.min_chunk_secs).max(1);
+
+        Self {
+            frame_len,
+            energy_threshold: settings.energy_threshold,
+            silence_frames: ((settings.silence_ms / 30) as usize).max(1),
+            min_chunk_samples: sample_rate as usize * settings.min_chunk_secs as usize,
+            max_chunk_samples: sample_rate as usize * max_chunk_secs as usize,
+        }
+    }
+
+    /// Whether a frame contains speech-level energy
+    pub fn is_speech(&self, frame: &[f32]) -> bool {
+        rms(frame) >= self.energy_threshold
+    }
+
+    /// Find where the next chunk should end in the buffered samples.
+    ///
+    /// Returns the midpoint of the first long enough pause after the minimum
+    /// chunk length, the maximum chunk length if no pause was found in time,
+    /// or `None` if more audio is needed.
+    pub fn find_chunk_boundary(&self, samples: &[f32]) -> Option<usize> {
+        if samples.len() < self.min_chunk_samples {
+            return None;
+        }
+
+        let limit = samples.len().min(self.max_chunk_samples);
+        let mut run_start = None;
+        let mut run_frames = 0;
+        let mut pos = self.min_chunk_samples;
+
+        while pos + self.frame_len <= limit {
+            if self.is_speech(&samples[pos..pos + self.frame_len]) {
+                run_start = None;
+                run_frames = 0;
+            } else {
+                let start = *run_start.get_or_insert(pos);
+                run_frames += 1;
+                if run_frames >= self.silence_frames {
+                    return Some(start + run_frames * self.frame_len / 2);
+                }
+            }
+            pos += self.frame_len;
+        }
+
+        if samples.len() >= self.max_chunk_samples {
+            Some(self.max_chunk_samples)
+        } else {
+            None
+        }
+    }
+}
+
+/// Root-mean-square level of a block of samples
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum: f32 = samples.iter().map(|s| s * s).sum();
+    (sum / samples.len() as f32).sqrt()
+}
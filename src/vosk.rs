@@ -0,0 +1,113 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::encoding;
+use crate::transcription::{self, Transcript, TranscriptionContext, TranscriptionProvider};
+
+/// Sample rate Vosk models are trained at
+const VOSK_SAMPLE_RATE: u32 = 16_000;
+
+/// Transcribes on this machine with Vosk's `vosk-transcriber` program. Vosk
+/// models are small (around 50 MB) and run in real time on modest CPUs, at
+/// some cost in accuracy compared to Whisper.
+#[derive(Debug, Clone)]
+pub struct Vosk {
+    /// `vosk-transcriber`, installed with `pip install vosk`
+    program: String,
+    /// Unpacked model directory, or `None` to let Vosk fetch the small
+    /// model for `language`
+    model_dir: Option<PathBuf>,
+    /// ISO-639-1 code picking the model when no directory is set
+    language: Option<String>,
+}
+
+impl Vosk {
+    pub fn new(program: String, model_dir: Option<PathBuf>, language: Option<String>) -> Self {
+        Self {
+            program,
+            model_dir,
+            language,
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for Vosk {
+    fn name(&self) -> &'static str {
+        "Vosk"
+    }
+
+    /// Vosk has no prompt or vocabulary hints; the context is ignored
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        _context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        let stem = transcription::temp_file_stem("vosk", audio_file);
+        let temp = |extension: &str| std::env::temp_dir().join(format!("{}.{}", stem, extension));
+        let (input, output_path) = (temp("wav"), temp("txt"));
+
+        let (source, target) = (audio_file.to_path_buf(), input.clone());
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let samples = encoding::read_audio_resampled(&source, VOSK_SAMPLE_RATE)?;
+            let file = std::fs::File::create(&target)
+                .with_context(|| format!("Failed to write audio to {:?}", target))?;
+            encoding::write_speech_wav(&samples, VOSK_SAMPLE_RATE, std::io::BufWriter::new(file))
+        })
+        .await??;
+
+        let mut command = Command::new(&self.program);
+        command
+            .arg("--input")
+            .arg(&input)
+            .arg("--output")
+            .arg(&output_path)
+            .args(["--output-type", "txt", "--log-level", "WARNING"])
+            .kill_on_drop(true);
+        match (&self.model_dir, &self.language) {
+            (Some(model_dir), _) => {
+                command.arg("--model").arg(model_dir);
+            }
+            (None, Some(language)) => {
+                command.arg("--lang").arg(language);
+            }
+            (None, None) => {}
+        }
+        let output = command.output().await;
+        let _ = tokio::fs::remove_file(&input).await;
+        let text = tokio::fs::read_to_string(&output_path).await;
+        let _ = tokio::fs::remove_file(&output_path).await;
+
+        let output = output.with_context(|| {
+            format!(
+                "Failed to run {}. Install Vosk with `pip install vosk` or set the path to \
+                 vosk-transcriber.",
+                self.program
+            )
+        })?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "{} exited with status {}: {}",
+                self.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let text = text.with_context(|| format!("{} wrote no transcript", self.program))?;
+
+        // One line per utterance
+        let text = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Ok(Transcript {
+            text,
+            language: self.language.clone(),
+            ..Default::default()
+        })
+    }
+}
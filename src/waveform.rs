@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// Number of envelope points kept for a full-length chunk
+pub const WAVEFORM_POINTS: usize = 120;
+
+/// Downsampled min/max envelope of a chunk, used for thumbnails
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Waveform {
+    /// (min, max) sample value of each bucket, in order
+    pub points: Vec<(f32, f32)>,
+}
+
+impl Waveform {
+    /// Envelope of a complete block of samples with at most `points` buckets
+    pub fn from_samples(samples: &[f32], points: usize) -> Self {
+        let bucket_len = samples.len().div_ceil(points.max(1)).max(1);
+        let mut builder = WaveformBuilder::new(bucket_len);
+        builder.push(samples);
+        builder.finish()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+/// Builds a waveform incrementally with a fixed number of samples per bucket
+pub struct WaveformBuilder {
+    bucket_len: usize,
+    points: Vec<(f32, f32)>,
+    current: Option<(f32, f32)>,
+    filled: usize,
+}
+
+impl WaveformBuilder {
+    pub fn new(bucket_len: usize) -> Self {
+        Self {
+            bucket_len: bucket_len.max(1),
+            points: Vec::new(),
+            current: None,
+            filled: 0,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            let (min, max) = self.current.get_or_insert((sample, sample));
+            *min = min.min(sample);
+            *max = max.max(sample);
+            self.filled += 1;
+
+            if self.filled == self.bucket_len {
+                self.points.extend(self.current.take());
+                self.filled = 0;
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> Waveform {
+        self.points.extend(self.current.take());
+        Waveform {
+            points: self.points,
+        }
+    }
+}
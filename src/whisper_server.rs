@@ -0,0 +1,64 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::Path;
+
+use crate::openai_api::{ApiEndpoint, AuthHeader};
+use crate::retry::RetryPolicy;
+use crate::transcription::{
+    OpenAiWhisper, Transcript, TranscriptionContext, TranscriptionProvider,
+};
+
+/// Base URL of a faster-whisper-server (speaches) running on this machine
+pub const DEFAULT_URL: &str = "http://localhost:8000/v1";
+
+/// Model a faster-whisper-server loads when asked for it
+pub const DEFAULT_MODEL: &str = "Systran/faster-whisper-large-v3";
+
+/// A self-hosted transcription server with an OpenAI-compatible
+/// `audio/transcriptions` route, such as faster-whisper-server or a
+/// whisperX API, so a team can share one GPU machine on its own network.
+///
+/// Requests are the same as for OpenAI's API; the token, if any, is sent as
+/// `Authorization: Bearer`. Nothing is billed.
+pub struct WhisperServer {
+    inner: OpenAiWhisper,
+}
+
+impl WhisperServer {
+    pub fn new(
+        url: String,
+        token: String,
+        model: String,
+        language: Option<String>,
+        translate: bool,
+        retry: RetryPolicy,
+    ) -> Self {
+        let endpoint = ApiEndpoint {
+            base_url: url,
+            api_version: String::new(),
+            auth: AuthHeader::Bearer,
+            api_key: token,
+        };
+        Self {
+            inner: OpenAiWhisper::new(endpoint, model, language, translate, retry),
+        }
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for WhisperServer {
+    fn name(&self) -> &'static str {
+        "Whisper server"
+    }
+
+    async fn transcribe_audio(
+        &self,
+        audio_file: &Path,
+        context: &TranscriptionContext,
+    ) -> Result<Transcript> {
+        let mut transcript = self.inner.transcribe_audio(audio_file, context).await?;
+        // Self-hosted: the audio costs nothing
+        transcript.billed = None;
+        Ok(transcript)
+    }
+}